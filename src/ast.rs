@@ -1,5 +1,11 @@
 use crate::tokenizer::Token;
 use crate::data_type::{DataType, DataTypeDescription};
+use std::collections::HashSet;
+
+pub type ModuleId = usize;
+
+/// The module of the file passed on the command line.
+pub const ROOT_MODULE: ModuleId = 0;
 
 #[derive(Debug)]
 pub struct Field
@@ -13,7 +19,53 @@ pub struct Struct
 {
     pub name: Token,
     pub type_variable: Option<DataType>,
+    pub traits: Vec<DataType>,
     pub fields: Vec<Field>,
+    pub module: ModuleId,
+    pub is_exported: bool,
+}
+
+#[derive(Debug)]
+pub struct Variant
+{
+    pub name: Token,
+    pub fields: Vec<DataType>,
+}
+
+#[derive(Debug)]
+pub struct Enum
+{
+    pub name: Token,
+    pub type_variable: Option<DataType>,
+    pub variants: Vec<Variant>,
+    pub module: ModuleId,
+    pub is_exported: bool,
+}
+
+#[derive(Debug)]
+pub struct TypeVariable
+{
+    pub name: Token,
+    pub bounds: Vec<Token>,
+}
+
+#[derive(Debug)]
+pub struct TraitFunction
+{
+    pub name: Token,
+    pub params: Vec<DataType>,
+    pub return_type: Option<DataType>,
+}
+
+#[derive(Debug)]
+pub struct Trait
+{
+    pub name: Token,
+    pub type_variables: Vec<TypeVariable>,
+    pub supertraits: Vec<DataType>,
+    pub functions: Vec<TraitFunction>,
+    pub module: ModuleId,
+    pub is_exported: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -22,14 +74,41 @@ pub enum OperationType
     Add,
     Subtract,
     Multiply,
+    Divide,
+    Modulo,
     GreaterThan,
     LessThan,
+    GreaterThanEquals,
+    LessThanEquals,
+    Equals,
+    NotEquals,
+    And,
+    Or,
+    Not,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
+    Negate,
     Ref,
+    MutRef,
     Deref,
     Sizeof,
     Indexed,
     Access,
     Assign,
+    AddAssign,
+    SubtractAssign,
+    MultiplyAssign,
+    DivideAssign,
+    ModuloAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    ShiftLeftAssign,
+    ShiftRightAssign,
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +117,9 @@ pub struct Operation
     pub operation_type: OperationType,
     pub lhs: Box<Expression>,
     pub rhs: Option<Box<Expression>>,
+
+    // The operator, like the `+` in `a + b`.
+    pub token: Token,
 }
 
 #[derive(Debug, Clone)]
@@ -48,6 +130,15 @@ pub struct Call
     pub type_variable: Option<DataType>,
 }
 
+/// Convert `value` to another primitive type, like `x as u8`.
+#[derive(Debug, Clone)]
+pub struct Cast
+{
+    pub value: Box<Expression>,
+    pub data_type: DataType,
+    pub token: Token,
+}
+
 #[derive(Debug, Clone)]
 pub struct InitializerList
 {
@@ -55,15 +146,48 @@ pub struct InitializerList
     pub list: Vec<(Token, Expression)>,
 }
 
+/// A value of an enum, like `new Shape.Circle(5)`.
+#[derive(Debug, Clone)]
+pub struct VariantLiteral
+{
+    pub data_type: DataType,
+    pub variant: Token,
+    pub arguments: Vec<Expression>,
+}
+
+/// The address of a function, like `fun compare(ref int, ref int)`.
+/// The param types pick which overload is meant.
+#[derive(Debug, Clone)]
+pub struct FunctionRef
+{
+    pub name: Token,
+    pub params: Vec<DataType>,
+    pub type_variable: Option<DataType>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Expression
 {
     Operation(Operation),
     Call(Call),
     ExternCall(Call),
+    Cast(Cast),
     InitializerList(InitializerList),
+    VariantLiteral(VariantLiteral),
     ArrayLiteral(Vec<Expression>),
+    TupleLiteral(Vec<Expression>),
+    FunctionRef(FunctionRef),
     IntLiteral(i32),
+
+    // An int literal with a type suffix, like `255u8`, as the bits
+    // of a value of that type.
+    TypedIntLiteral(u64, DataType),
+
+    // A float literal without a suffix is an `f64`, unless it's used
+    // where an `f32` is expected.
+    FloatLiteral(f64),
+    TypedFloatLiteral(f64, DataType),
+
     BoolLiteral(bool),
     StringLiteral(Token),
     CharLiteral(Token),
@@ -74,6 +198,24 @@ pub enum Expression
 pub struct Let
 {
     pub name: Token,
+    pub is_mutable: bool,
+    pub data_type: Option<DataType>,
+    pub value: Option<Expression>,
+}
+
+#[derive(Debug)]
+pub struct TupleBinding
+{
+    pub name: Token,
+    pub is_mutable: bool,
+}
+
+/// Unpack a tuple into a variable for each item, like `let (a, mut b) = f()`.
+#[derive(Debug)]
+pub struct LetTuple
+{
+    pub bindings: Vec<TupleBinding>,
+    pub data_type: Option<DataType>,
     pub value: Expression,
 }
 
@@ -85,22 +227,63 @@ pub struct If
     pub else_block: Option<Vec<Statement>>,
 }
 
+#[derive(Debug)]
+pub enum Iterable
+{
+    Range(Expression, Expression),
+    Collection(Expression),
+}
+
+#[derive(Debug)]
+pub struct For
+{
+    pub variable: Token,
+    pub iterable: Iterable,
+    pub block: Vec<Statement>,
+}
+
+#[derive(Debug)]
+pub struct MatchArm
+{
+    pub variant: Token,
+
+    // NOTE: This is `None` when the arm doesn't look at the
+    //       values held by the variant, like `Circle -> ...`.
+    pub bindings: Option<Vec<Token>>,
+
+    pub block: Vec<Statement>,
+}
+
+#[derive(Debug)]
+pub struct Match
+{
+    pub token: Token,
+    pub value: Expression,
+    pub arms: Vec<MatchArm>,
+    pub else_block: Option<Vec<Statement>>,
+}
+
 #[derive(Debug)]
 pub enum Statement
 {
     Expression(Expression),
     Return(Expression),
     Let(Let),
+    LetTuple(LetTuple),
     If(If),
     Loop(Vec<Statement>),
     While(Expression, Vec<Statement>),
-    Break,
+    For(For),
+    Match(Match),
+    Break(Token),
+    Continue(Token),
 }
 
 #[derive(Debug)]
 pub struct Param
 {
     pub name: Token,
+    pub is_mutable: bool,
     pub data_type_description: DataTypeDescription,
 }
 
@@ -110,8 +293,39 @@ pub struct Function
     pub name: Token,
     pub params: Vec<Param>,
     pub type_variable: Option<DataType>,
+    pub type_variable_bounds: Vec<Token>,
     pub return_type: Option<DataType>,
     pub body: Option<Vec<Statement>>,
+    pub module: ModuleId,
+    pub is_exported: bool,
+}
+
+#[derive(Debug)]
+pub struct Const
+{
+    pub name: Token,
+    pub data_type: Option<DataType>,
+    pub value: Expression,
+    pub module: ModuleId,
+    pub is_exported: bool,
+}
+
+#[derive(Debug)]
+pub struct Use
+{
+    pub path: Vec<Token>,
+    pub is_exported: bool,
+}
+
+#[derive(Debug)]
+pub struct Module
+{
+    pub name: String,
+    pub imports: Vec<ModuleId>,
+
+    // Modules imported with `export use`, whose exports are visible
+    // to anything importing this module.
+    pub exports: Vec<ModuleId>,
 }
 
 #[derive(Debug)]
@@ -119,7 +333,12 @@ pub struct SourceFile
 {
     pub functions: Vec<Function>,
     pub structs: Vec<Struct>,
+    pub enums: Vec<Enum>,
+    pub traits: Vec<Trait>,
+    pub consts: Vec<Const>,
     pub externs: Vec<Token>,
+    pub uses: Vec<Use>,
+    pub modules: Vec<Module>,
 }
 
 impl Default for SourceFile
@@ -130,7 +349,24 @@ impl Default for SourceFile
         {
             functions: Vec::new(),
             structs: Vec::new(),
+            enums: Vec::new(),
+            traits: Vec::new(),
+            consts: Vec::new(),
             externs: Vec::new(),
+            uses: Vec::new(),
+            modules: Vec::new(),
+        }
+    }
+}
+
+fn add_exported_modules(modules: &[Module],
+                        module: ModuleId,
+                        visible_modules: &mut HashSet<ModuleId>)
+{
+    for exported_module in &modules[module].exports
+    {
+        if visible_modules.insert(*exported_module) {
+            add_exported_modules(modules, *exported_module, visible_modules);
         }
     }
 }
@@ -138,17 +374,32 @@ impl Default for SourceFile
 impl SourceFile
 {
 
+    /// The modules whose exports can be seen from `module`, which are
+    /// those it imports and anything they export in turn.
+    pub fn visible_modules(&self, module: ModuleId) -> HashSet<ModuleId>
+    {
+        let mut visible_modules = HashSet::new();
+        for imported_module in &self.modules[module].imports
+        {
+            visible_modules.insert(*imported_module);
+            add_exported_modules(&self.modules, *imported_module, &mut visible_modules);
+        }
+
+        visible_modules
+    }
+
     pub fn find_function(&self,
                          name: &str,
                          params: &Vec<DataTypeDescription>,
-                         type_variable: &Option<DataType>)
+                         type_variable: &Option<DataType>,
+                         module: ModuleId)
         -> Option<&Function>
     {
         self.functions
             .iter()
             .find(|f|
         {
-            if f.name.content() != name {
+            if f.name.content() != name || f.module != module {
                 return false;
             }
             if f.params.len() != params.len() {
@@ -176,12 +427,20 @@ impl Expression
     {
         match self
         {
-            Self::Operation(operation) => operation.lhs.token(),
+            Self::Operation(operation) =>
+                operation.lhs.token().or(Some(&operation.token)),
             Self::Call(call) => call.callable.token(),
             Self::ExternCall(call) => call.callable.token(),
+            Self::Cast(cast) => Some(&cast.token),
             Self::InitializerList(list) => Some(&list.list.get(0)?.0),
+            Self::VariantLiteral(literal) => Some(&literal.variant),
             Self::ArrayLiteral(arr) => arr.get(0)?.token(),
+            Self::TupleLiteral(items) => items.iter().find_map(|item| item.token()),
+            Self::FunctionRef(function_ref) => Some(&function_ref.name),
             Self::IntLiteral(_) => None,
+            Self::TypedIntLiteral(_, _) => None,
+            Self::FloatLiteral(_) => None,
+            Self::TypedFloatLiteral(_, _) => None,
             Self::BoolLiteral(_) => None,
             Self::StringLiteral(token) => Some(token),
             Self::CharLiteral(token) => Some(token),
@@ -7,7 +7,13 @@ pub struct Allocator
     registers_in_use: HashSet<char>,
     ir_to_x86: HashMap<IRRegister, X86Register>,
     ir_to_stack_offset: HashMap<IRRegister, (usize, usize)>,
+    freed_stack_slots: Vec<(usize, usize)>,
     stack_size: usize,
+
+    // Values that didn't get a register, and which dword of the
+    // function's spill area they're kept in.
+    ir_to_spill_slot: HashMap<IRRegister, (usize, usize)>,
+    spill_slots_in_use: HashSet<usize>,
 }
 
 #[derive(PartialEq, Debug)]
@@ -15,6 +21,7 @@ pub enum AllocationType
 {
     Register,
     Stack,
+    Spilled,
 }
 
 impl Allocator
@@ -27,7 +34,10 @@ impl Allocator
             registers_in_use: HashSet::new(),
             ir_to_x86: HashMap::new(),
             ir_to_stack_offset: HashMap::new(),
+            freed_stack_slots: Vec::new(),
             stack_size: 0,
+            ir_to_spill_slot: HashMap::new(),
+            spill_slots_in_use: HashSet::new(),
         }
     }
 
@@ -42,6 +52,8 @@ impl Allocator
             AllocationType::Register
         } else if self.ir_to_stack_offset.contains_key(&register) {
             AllocationType::Stack
+        } else if self.ir_to_spill_slot.contains_key(&register) {
+            AllocationType::Spilled
         } else {
             panic!()
         }
@@ -58,6 +70,28 @@ impl Allocator
         self.stack_size - offset
     }
 
+    /// The spill slot a value is kept in, and its size.
+    pub fn spill_slot(&self, register: IRRegister) -> (usize, usize)
+    {
+        self.ir_to_spill_slot[&register]
+    }
+
+    /// The registers holding a value, in order.
+    pub fn registers_in_use(&self) -> Vec<char>
+    {
+        ['a', 'b', 'c', 'd'].iter()
+            .filter(|letter| self.registers_in_use.contains(letter))
+            .cloned()
+            .collect()
+    }
+
+    pub fn available_register_count(&self) -> usize
+    {
+        ['a', 'b', 'c', 'd'].iter()
+            .filter(|letter| !self.registers_in_use.contains(letter))
+            .count()
+    }
+
     fn next_available_register(&mut self, size: usize) -> Option<(X86Register, char)>
     {
         for letter in ['a', 'b', 'c', 'd']
@@ -72,28 +106,48 @@ impl Allocator
 
     fn allocate_register(&mut self, register: IRRegister, size: usize) -> AllocationType
     {
-        // FIXME: Use stack if we don't have any registers left.
-        let (x86_register, letter) = self.next_available_register(size)
-            .expect("We have an available register");
+        // NOTE: Once the registers have run out, values are kept in
+        //       the function's spill area instead.
+        match self.next_available_register(size)
+        {
+            Some((x86_register, letter)) =>
+            {
+                self.registers_in_use.insert(letter);
+                self.ir_to_x86.insert(register, x86_register);
+                AllocationType::Register
+            },
 
-        self.registers_in_use.insert(letter);
-        self.ir_to_x86.insert(register, x86_register);
-        return AllocationType::Register;
+            None =>
+            {
+                let slot = (0..)
+                    .find(|slot| !self.spill_slots_in_use.contains(slot))
+                    .unwrap();
+                self.spill_slots_in_use.insert(slot);
+                self.ir_to_spill_slot.insert(register, (slot, size));
+                AllocationType::Spilled
+            },
+        }
     }
 
-    fn allocate_stack(&mut self, register: IRRegister, size: usize) -> AllocationType
+    fn allocate_stack(&mut self, register: IRRegister, size: usize) -> (AllocationType, usize)
     {
+        // NOTE: Values on the stack take up whole dwords, like arguments,
+        //       so the one on top can be used as an argument where it is.
+        let size = size.next_multiple_of(4);
         self.stack_size += size;
         self.ir_to_stack_offset.insert(register, (self.stack_size, size));
-        AllocationType::Stack
+        (AllocationType::Stack, size)
     }
 
-    pub fn allocate(&mut self, register: IRRegister, size: usize) -> AllocationType
+    /// Allocate a value, giving where it went and, if it's on the
+    /// stack, how many bytes were taken.
+    pub fn allocate(&mut self, register: IRRegister, size: usize) -> (AllocationType, usize)
     {
         assert!(!self.ir_to_x86.contains_key(&register));
         assert!(!self.ir_to_stack_offset.contains_key(&register));
+        assert!(!self.ir_to_spill_slot.contains_key(&register));
         if size <= 4 {
-            self.allocate_register(register, size)
+            (self.allocate_register(register, size), size)
         } else {
             self.allocate_stack(register, size)
         }
@@ -119,21 +173,118 @@ impl Allocator
 
         if self.ir_to_stack_offset.contains_key(&register)
         {
-            let (stack_offset, size) = self.ir_to_stack_offset.remove(&register).unwrap();
-            assert_eq!(stack_offset, self.stack_size);
-            self.stack_size -= size;
+            // NOTE: Only the top of the stack can be given back. Values
+            //       under it are remembered, and given back with it later.
+            let slot = self.ir_to_stack_offset.remove(&register).unwrap();
+            self.freed_stack_slots.push(slot);
+            return (AllocationType::Stack, self.free_top_of_stack());
+        }
 
-            return (AllocationType::Stack, size);
+        if let Some((slot, _)) = self.ir_to_spill_slot.remove(&register)
+        {
+            self.spill_slots_in_use.remove(&slot);
+            return (AllocationType::Spilled, 0);
         }
 
         eprintln!("{}", register);
         panic!();
     }
 
+    /// Give back any freed values on the top of the stack, and
+    /// the number of bytes they took up.
+    fn free_top_of_stack(&mut self) -> usize
+    {
+        let stack_size_before = self.stack_size;
+        while let Some(index) = self.freed_stack_slots.iter()
+            .position(|(offset, _)| *offset == self.stack_size)
+        {
+            let (_, size) = self.freed_stack_slots.remove(index);
+            self.stack_size -= size;
+        }
+
+        stack_size_before - self.stack_size
+    }
+
+    /// Turn the value on top of the stack into a pushed argument, so it
+    /// doesn't need copying. Gives false if it's not on top.
+    pub fn use_as_argument(&mut self, register: IRRegister) -> bool
+    {
+        match self.ir_to_stack_offset.get(&register)
+        {
+            Some((offset, _)) if *offset == self.stack_size =>
+            {
+                self.ir_to_stack_offset.remove(&register);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Arguments pushed for a call sit on top of any values already on
+    /// the stack, so these can't be given back until they're popped.
+    pub fn push(&mut self, size: usize)
+    {
+        self.stack_size += size;
+    }
+
+    /// Pop `size` bytes of arguments, giving the total number of bytes
+    /// to pop, including any values that were freed under them.
+    pub fn pop(&mut self, size: usize) -> usize
+    {
+        self.stack_size -= size;
+        size + self.free_top_of_stack()
+    }
+
+    /// Give a spilled value a register to be worked on in, for
+    /// the length of a single instruction.
+    pub fn borrow_register(&mut self, register: IRRegister) -> X86Register
+    {
+        let (_, size) = self.ir_to_spill_slot[&register];
+        let (x86_register, letter) = self.next_available_register(size)
+            .expect("We have an available register");
+
+        self.registers_in_use.insert(letter);
+        self.ir_to_x86.insert(register, x86_register.clone());
+        x86_register
+    }
+
+    pub fn give_back_register(&mut self, register: IRRegister)
+    {
+        let x86_register = self.ir_to_x86.remove(&register).unwrap();
+        self.free_scratch_register(x86_register);
+    }
+
+    /// A register holding a value other than those in `keep`, which can
+    /// be pushed to make room.
+    pub fn find_register_to_save(&self, keep: &[IRRegister]) -> Option<char>
+    {
+        self.ir_to_x86.iter()
+            .filter(|(register, _)| !keep.contains(register))
+            .filter_map(|(_, x86_register)| match x86_register
+            {
+                X86Register::General(letter, _, _) => Some(*letter),
+                _ => None,
+            })
+            .filter(|letter| self.registers_in_use.contains(letter))
+            .min()
+    }
+
+    /// Mark a register as free once its value has been pushed, so
+    /// it can be used until `restore_register`.
+    pub fn save_register(&mut self, letter: char)
+    {
+        assert!(self.registers_in_use.remove(&letter));
+        self.stack_size += 4;
+    }
+
+    pub fn restore_register(&mut self, letter: char)
+    {
+        assert!(self.registers_in_use.insert(letter));
+        self.stack_size -= 4;
+    }
+
     pub fn allocate_scratch_register(&mut self, size: usize) -> X86Register
     {
-        // FIXME: If no registers are available, reuse one by pushing 
-        //        its value onto the stack.
         let (x86_register, letter) = self.next_available_register(size)
             .expect("We have an available register");
 
@@ -3,16 +3,27 @@ mod allocator;
 use register::X86Register;
 use allocator::{Allocator, AllocationType};
 use crate::intermediate::{IR, IROperation, IRFunction, IRProgram};
-use crate::intermediate::IRStorage;
+use crate::intermediate::{IRStorage, IRRegister, argument_size};
 use std::io::Write;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
 struct X86Output<W>
 {
     stream: W,
     allocator: Allocator,
-    strings: HashMap<String, usize>,
+    strings: HashMap<Vec<u8>, usize>,
+    helper_functions: Vec<&'static str>,
+
+    // Where `ebx` was saved in the current function, if it's one
+    // whose address is taken.
+    saved_ebx_offset: Option<i32>,
+
+    // Where the current function's spill area starts, below ebp.
+    spill_area_offset: i32,
+
+    // Where registers are saved around calls, below the spill area.
+    call_save_area_offset: i32,
 }
 
 impl<W> X86Output<W>
@@ -26,6 +37,10 @@ impl<W> X86Output<W>
             stream,
             allocator: Allocator::new(),
             strings: HashMap::new(),
+            helper_functions: Vec::new(),
+            saved_ebx_offset: None,
+            spill_area_offset: 0,
+            call_save_area_offset: 0,
         }
     }
 
@@ -36,19 +51,29 @@ impl<W> X86Output<W>
         Ok(())
     }
 
-    fn get_string_id(&mut self, s: &str) -> usize
+    fn get_string_id(&mut self, s: &[u8]) -> usize
     {
         match self.strings.get(s)
         {
             Some(id) => *id,
             None =>
             {
-                self.strings.insert(s.to_owned(), self.strings.len());
+                self.strings.insert(s.to_vec(), self.strings.len());
                 self.strings.len() - 1
             },
         }
     }
 
+    fn generate_set_i64(&mut self, to: &IRStorage, i: i64)
+        -> Result<(), Box<dyn Error>>
+    {
+        let low_str = self.dword_of(to, 0, 0);
+        let high_str = self.dword_of(to, 4, 0);
+        self.emit(format!("mov {}, {}", low_str, i as u32))?;
+        self.emit(format!("mov {}, {}", high_str, (i >> 32) as u32))?;
+        Ok(())
+    }
+
     fn generate_set_i32(&mut self, to: &IRStorage, i: i32)
         -> Result<(), Box<dyn Error>>
     {
@@ -57,6 +82,14 @@ impl<W> X86Output<W>
         Ok(())
     }
 
+    fn generate_set_i16(&mut self, to: &IRStorage, i: i16)
+        -> Result<(), Box<dyn Error>>
+    {
+        let to_str = self.value_of(2, to);
+        self.emit(format!("mov {}, {}", to_str, i))?;
+        Ok(())
+    }
+
     fn generate_set_i8(&mut self, to: &IRStorage, i: i8)
         -> Result<(), Box<dyn Error>>
     {
@@ -65,7 +98,7 @@ impl<W> X86Output<W>
         Ok(())
     }
 
-    fn generate_set_string(&mut self, to: &IRStorage, s: &str)
+    fn generate_set_string(&mut self, to: &IRStorage, s: &[u8])
         -> Result<(), Box<dyn Error>>
     {
         let to_str = self.value_of(4, to);
@@ -89,6 +122,14 @@ impl<W> X86Output<W>
         Ok(())
     }
 
+    fn generate_set_function(&mut self, to: &IRStorage, function: &str)
+        -> Result<(), Box<dyn Error>>
+    {
+        let to_str = self.value_of(4, to);
+        self.emit(format!("mov {}, {}", to_str, function))?;
+        Ok(())
+    }
+
     fn generate_deref(&mut self, to: &IRStorage, value: &IRStorage, size: usize)
         -> Result<(), Box<dyn Error>>
     {
@@ -114,6 +155,31 @@ impl<W> X86Output<W>
         Ok(())
     }
 
+    fn generate_store(&mut self, address: &IRStorage, value: &IRStorage, size: usize)
+        -> Result<(), Box<dyn Error>>
+    {
+        let address_str = self.value_of(4, address);
+        let address_register = self.allocator.allocate_scratch_register(4);
+        self.emit(format!("mov {}, {}", address_register, address_str))?;
+        if size > 4
+        {
+            let (value_register, value_offset) = self.offset_of(value);
+            self.generate_copy(address_register.clone(), 0,
+                value_register, value_offset, size)?;
+        }
+        else
+        {
+            let value_str = self.value_of(size, value);
+            let scratch_register = self.allocator.allocate_scratch_register(size);
+            self.emit(format!("mov {}, {}", scratch_register, value_str))?;
+            self.emit(format!("mov {}, {}", address_register.offset(size, 0), scratch_register))?;
+            self.allocator.free_scratch_register(scratch_register);
+        }
+
+        self.allocator.free_scratch_register(address_register);
+        Ok(())
+    }
+
     fn register_for(&self, storage: &IRStorage) -> Option<X86Register>
     {
         match storage
@@ -136,9 +202,14 @@ impl<W> X86Output<W>
         {
             IRStorage::Register(register) =>
             {
-                assert_eq!(self.allocator.allocation_type(*register), AllocationType::Register);
-                let x86_register = self.allocator.register_for(*register);
-                format!("{}", x86_register)
+                match self.allocator.allocation_type(*register)
+                {
+                    AllocationType::Register =>
+                        format!("{}", self.allocator.register_for(*register)),
+                    AllocationType::Spilled =>
+                        X86Register::ebp().offset(size, self.spill_slot_offset(*register)),
+                    AllocationType::Stack => panic!(),
+                }
             },
 
             IRStorage::Param(offset) =>
@@ -149,15 +220,43 @@ impl<W> X86Output<W>
         }
     }
 
+    /// Like `value_of`, but a value in a register is read as only `size`
+    /// bytes of it, rather than at the size it was allocated with.
+    fn sized_value_of(&mut self, size: usize, storage: &IRStorage) -> String
+    {
+        match self.register_for(storage)
+        {
+            Some(register) => format!("{}", register.of_size(size, false)),
+            None => self.value_of(size, storage),
+        }
+    }
+
+    /// The dword at `offset` bytes into a value in memory, when `pushed`
+    /// bytes have been pushed onto the stack since it was allocated.
+    fn dword_of(&mut self, storage: &IRStorage, offset: i32, pushed: i32) -> String
+    {
+        let (register, storage_offset) = self.offset_of(storage);
+        let pushed = if register == X86Register::esp() { pushed } else { 0 };
+        register.offset(4, storage_offset + offset + pushed)
+    }
+
     fn offset_of(&mut self, storage: &IRStorage) -> (X86Register, i32)
     {
         match storage
         {
             IRStorage::Register(register) =>
             {
-                assert_eq!(self.allocator.allocation_type(*register), AllocationType::Stack);
-                let stack_offset = self.allocator.stack_offset(*register);
-                (X86Register::esp(), stack_offset as i32)
+                match self.allocator.allocation_type(*register)
+                {
+                    AllocationType::Stack =>
+                    {
+                        let stack_offset = self.allocator.stack_offset(*register);
+                        (X86Register::esp(), stack_offset as i32)
+                    },
+                    AllocationType::Spilled =>
+                        (X86Register::ebp(), self.spill_slot_offset(*register)),
+                    AllocationType::Register => panic!(),
+                }
             },
 
             IRStorage::Param(offset) =>
@@ -168,6 +267,12 @@ impl<W> X86Output<W>
         }
     }
 
+    fn spill_slot_offset(&self, register: IRRegister) -> i32
+    {
+        let (slot, _) = self.allocator.spill_slot(register);
+        -self.spill_area_offset - 4 * (slot as i32 + 1)
+    }
+
     fn generate_move_to_register_offset(&mut self,
                                         offset: usize,
                                         to: X86Register,
@@ -244,6 +349,17 @@ impl<W> X86Output<W>
         Ok(())
     }
 
+    /// The largest size that can be moved at once, with `remaining` bytes left.
+    fn chunk_size(remaining: usize) -> usize
+    {
+        match remaining
+        {
+            x if x >= 4 => 4,
+            x if x >= 2 => 2,
+            _ => 1,
+        }
+    }
+
     fn generate_copy(&mut self,
                      to_register: X86Register,
                      to_offset: i32,
@@ -253,20 +369,110 @@ impl<W> X86Output<W>
         -> Result<(), Box<dyn Error>>
     {
         let scratch_register = self.allocator.allocate_scratch_register(4);
-        for i in (0..size as i32).step_by(4)
+        let mut i = 0;
+        while i < size
         {
-            let chunk_size = std::cmp::min(size - i as usize, 4);
+            let chunk_size = Self::chunk_size(size - i);
             let temp = scratch_register.of_size(chunk_size, false);
             self.emit(format!("mov {}, {}",
-                temp, from_register.offset(chunk_size, from_offset + i)))?;
+                temp, from_register.offset(chunk_size, from_offset + i as i32)))?;
             self.emit(format!("mov {}, {}",
-                to_register.offset(chunk_size, to_offset + i), temp))?;
+                to_register.offset(chunk_size, to_offset + i as i32), temp))?;
+            i += chunk_size;
         }
 
         self.allocator.free_scratch_register(scratch_register);
         Ok(())
     }
 
+    fn generate_zero(&mut self, to: &IRStorage, size: usize)
+        -> Result<(), Box<dyn Error>>
+    {
+        if self.register_for(to).is_some()
+        {
+            let to_str = self.value_of(size, to);
+            self.emit(format!("mov {}, 0", to_str))?;
+            return Ok(());
+        }
+
+        let (to_register, to_offset) = self.offset_of(to);
+        let mut i = 0;
+        while i < size
+        {
+            let chunk_size = Self::chunk_size(size - i);
+            self.emit(format!("mov {}, 0",
+                to_register.offset(chunk_size, to_offset + i as i32)))?;
+            i += chunk_size;
+        }
+
+        Ok(())
+    }
+
+    fn extend_instruction(is_signed: bool) -> &'static str
+    {
+        if is_signed { "movsx" } else { "movzx" }
+    }
+
+    fn generate_convert_int(&mut self,
+                            to: &IRStorage,
+                            to_size: usize,
+                            from: &IRStorage,
+                            from_size: usize,
+                            is_signed: bool)
+        -> Result<(), Box<dyn Error>>
+    {
+        if to_size > 4 && from_size > 4 {
+            return self.generate_large_move(to, from, to_size);
+        }
+
+        // NOTE: Ints are little endian, so cutting one down to size
+        //       is just reading its first bytes.
+        if from_size > 4
+        {
+            let (from_register, from_offset) = self.offset_of(from);
+            let to_str = self.value_of(to_size, to);
+            self.emit(format!("mov {}, {}", to_str, from_register.offset(to_size, from_offset)))?;
+            return Ok(());
+        }
+
+        if to_size > 4
+        {
+            let from_str = self.sized_value_of(from_size, from);
+            let low_str = self.dword_of(to, 0, 0);
+            let high_str = self.dword_of(to, 4, 0);
+            let scratch_register = self.allocator.allocate_scratch_register(4);
+            if from_size == 4 {
+                self.emit(format!("mov {}, {}", scratch_register, from_str))?;
+            } else {
+                self.emit(format!("{} {}, {}", Self::extend_instruction(is_signed), scratch_register, from_str))?;
+            }
+            self.emit(format!("mov {}, {}", low_str, scratch_register))?;
+
+            // NOTE: The high dword is all copies of the sign bit, or zero.
+            if is_signed {
+                self.emit(format!("sar {}, 31", scratch_register))?;
+            } else {
+                self.emit(format!("mov {}, 0", scratch_register))?;
+            }
+            self.emit(format!("mov {}, {}", high_str, scratch_register))?;
+            self.allocator.free_scratch_register(scratch_register);
+            return Ok(());
+        }
+
+        let to_str = self.value_of(to_size, to);
+        if to_size < from_size
+        {
+            let from_str = self.sized_value_of(to_size, from);
+            self.emit(format!("mov {}, {}", to_str, from_str))?;
+        }
+        else
+        {
+            let from_str = self.sized_value_of(from_size, from);
+            self.emit(format!("{} {}, {}", Self::extend_instruction(is_signed), to_str, from_str))?;
+        }
+        Ok(())
+    }
+
     fn generate_large_move(&mut self, to: &IRStorage, from: &IRStorage, size: usize)
         -> Result<(), Box<dyn Error>>
     {
@@ -284,6 +490,16 @@ impl<W> X86Output<W>
 
         let to_value = self.value_of(size, to);
         let from_value = self.value_of(size, from);
+        if self.register_for(to).is_none() && self.register_for(from).is_none()
+        {
+            // NOTE: x86 can't move directly between two memory locations.
+            let scratch_register = self.allocator.allocate_scratch_register(size);
+            self.emit(format!("mov {}, {}", scratch_register, from_value))?;
+            self.emit(format!("mov {}, {}", to_value, scratch_register))?;
+            self.allocator.free_scratch_register(scratch_register);
+            return Ok(());
+        }
+
         self.emit(format!("mov {}, {}", to_value, from_value))?;
         Ok(())
     }
@@ -321,6 +537,10 @@ impl<W> X86Output<W>
                 value_str))?;
         }
 
+        if let Some(offset) = self.saved_ebx_offset {
+            self.emit(format!("mov ebx, {}", X86Register::ebp().offset(4, offset)))?;
+        }
+
         self.emit(format!("mov esp, ebp"))?;
         self.emit(format!("pop ebp"))?;
         self.emit(format!("ret"))?;
@@ -330,12 +550,11 @@ impl<W> X86Output<W>
     fn generate_push_u8(&mut self, i: i8)
         -> Result<(), Box<dyn Error>>
     {
-        self.emit(format!("sub esp, 1"))?;
-        self.emit(format!("mov byte [esp], {}", i))?;
+        self.emit(format!("push dword {}", i as u8))?;
         Ok(())
     }
 
-    fn generate_push_string(&mut self, s: &str)
+    fn generate_push_string(&mut self, s: &[u8])
         -> Result<(), Box<dyn Error>>
     {
         let id = self.get_string_id(s);
@@ -343,17 +562,51 @@ impl<W> X86Output<W>
         Ok(())
     }
 
+    fn generate_call_indirect(&mut self, function: &IRStorage, return_value: &IRStorage, size: usize)
+        -> Result<(), Box<dyn Error>>
+    {
+        let function_str = self.value_of(4, function);
+        self.generate_call(&function_str, return_value, size)
+    }
+
+    fn call_save_slot_offset(&self, slot: usize) -> i32
+    {
+        -self.call_save_area_offset - 4 * (slot as i32 + 1)
+    }
+
     fn generate_call(&mut self, function: &str, return_value: &IRStorage, size: usize)
         -> Result<(), Box<dyn Error>>
     {
-        let is_eax = self.is_eax(return_value);
-        let is_eax_in_use = self.allocator.is_in_use('a');
+        // NOTE: The function called is free to change any register, so
+        //       values kept in them are saved in the frame around the call.
+        let return_letter = match self.register_for(return_value)
+        {
+            Some(X86Register::General(letter, _, _)) => Some(letter),
+            _ => None,
+        };
+        let saved_registers = self.allocator.registers_in_use()
+            .into_iter()
+            .filter(|letter| Some(*letter) != return_letter)
+            .map(|letter| X86Register::General(letter, 4, false))
+            .collect::<Vec<_>>();
+        for (slot, register) in saved_registers.iter().enumerate()
+        {
+            let slot_str = X86Register::ebp().offset(4, self.call_save_slot_offset(slot));
+            self.emit(format!("mov {}, {}", slot_str, register))?;
+        }
 
         self.emit(format!("call {}", function))?;
-        if !is_eax && is_eax_in_use && size > 0
+        if !self.is_eax(return_value) && size > 0 && size <= 4
         {
             let return_str = self.value_of(size, return_value);
-            self.emit(format!("mov {}, eax", return_str))?;
+            let eax = X86Register::General('a', size, false);
+            self.emit(format!("mov {}, {}", return_str, eax))?;
+        }
+
+        for (slot, register) in saved_registers.iter().enumerate()
+        {
+            let slot_str = X86Register::ebp().offset(4, self.call_save_slot_offset(slot));
+            self.emit(format!("mov {}, {}", register, slot_str))?;
         }
         Ok(())
     }
@@ -363,10 +616,49 @@ impl<W> X86Output<W>
     {
         if size > 4
         {
-            let (from_register, from_offset) = self.offset_of(value);
-            self.emit(format!("sub esp, {}", size))?;
+            // NOTE: Values on the stack move further from esp once
+            //       space for the argument has been made.
+            let (from_register, mut from_offset) = self.offset_of(value);
+            if from_register == X86Register::esp() {
+                from_offset += argument_size(size) as i32;
+            }
+
+            self.emit(format!("sub esp, {}", argument_size(size)))?;
             self.generate_copy(X86Register::esp(), 0, from_register, from_offset, size)?;
         }
+        else if size < 4
+        {
+            // NOTE: Only whole dwords can be pushed, so zero extend
+            //       the value first.
+            let value_str = self.value_of(size, value);
+            match self.register_for(value)
+            {
+                Some(register) =>
+                {
+                    let extended_register = register.of_size(4, false);
+                    self.emit(format!("movzx {}, {}", extended_register, value_str))?;
+                    self.emit(format!("push {}", extended_register))?;
+                },
+
+                None if self.allocator.available_register_count() > 0 =>
+                {
+                    let scratch_register = self.allocator.allocate_scratch_register(4);
+                    self.emit(format!("movzx {}, {}", scratch_register, value_str))?;
+                    self.emit(format!("push {}", scratch_register))?;
+                    self.allocator.free_scratch_register(scratch_register);
+                },
+
+                // NOTE: Without a register to extend it in, the whole dword
+                //       is pushed, then cut down to the value's size.
+                None =>
+                {
+                    let mask = if size == 1 { "0xFF" } else { "0xFFFF" };
+                    let dword_str = self.value_of(4, value);
+                    self.emit(format!("push {}", dword_str))?;
+                    self.emit(format!("and dword [esp], {}", mask))?;
+                },
+            }
+        }
         else
         {
             let value_str = self.value_of(size, value);
@@ -376,29 +668,141 @@ impl<W> X86Output<W>
         Ok(())
     }
 
-    fn i32_operation_str(operation: &IROperation) -> (bool, &str)
+    fn i32_operation_str(operation: &IROperation, is_signed: bool) -> (bool, &str)
     {
-        match operation
+        match (operation, is_signed)
+        {
+            (IROperation::Add, _) => (false, "add"),
+            (IROperation::Subtract, _) => (false, "sub"),
+            (IROperation::Multiply, _) => (false, "imul"),
+            (IROperation::Divide, true) => (false, "idiv"),
+            (IROperation::Divide, false) => (false, "div"),
+            (IROperation::Modulo, true) => (false, "idiv"),
+            (IROperation::Modulo, false) => (false, "div"),
+            (IROperation::And, _) => (false, "and"),
+            (IROperation::Or, _) => (false, "or"),
+            (IROperation::Xor, _) => (false, "xor"),
+            (IROperation::ShiftLeft, _) => (false, "shl"),
+            (IROperation::ShiftRight, true) => (false, "sar"),
+            (IROperation::ShiftRight, false) => (false, "shr"),
+            (IROperation::GreaterThan, true) => (true, "setg"),
+            (IROperation::GreaterThan, false) => (true, "seta"),
+            (IROperation::LessThan, true) => (true, "setl"),
+            (IROperation::LessThan, false) => (true, "setb"),
+            (IROperation::GreaterThanEquals, true) => (true, "setge"),
+            (IROperation::GreaterThanEquals, false) => (true, "setae"),
+            (IROperation::LessThanEquals, true) => (true, "setle"),
+            (IROperation::LessThanEquals, false) => (true, "setbe"),
+            (IROperation::Equals, _) => (true, "sete"),
+            (IROperation::NotEquals, _) => (true, "setne"),
+        }
+    }
+
+    fn generate_i32_division(&mut self,
+                             operation: &IROperation,
+                             is_signed: bool,
+                             to: &IRStorage,
+                             lhs: &IRStorage,
+                             rhs_str: &str)
+        -> Result<(), Box<dyn Error>>
+    {
+        // NOTE: `idiv` and `div` always divide edx:eax, leaving the
+        //       quotient in eax and the remainder in edx. So both need
+        //       saving if they're holding something else.
+        let to_str = self.value_of(4, to);
+        let lhs_str = self.value_of(4, lhs);
+        let result_str = match operation
+        {
+            IROperation::Modulo => "edx",
+            _ => "eax",
+        };
+
+        let save_eax = to_str != "eax" && self.allocator.is_in_use('a');
+        let save_edx = to_str != "edx" && self.allocator.is_in_use('d');
+        if save_eax {
+            self.emit("push eax".to_owned())?;
+        }
+        if save_edx {
+            self.emit("push edx".to_owned())?;
+        }
+
+        // NOTE: The divisor goes on the stack, as it may be in
+        //       either of the registers we're about to overwrite.
+        self.emit(format!("push {}", rhs_str))?;
+        self.emit(format!("mov eax, {}", lhs_str))?;
+        if is_signed
+        {
+            self.emit("cdq".to_owned())?;
+            self.emit("idiv dword [esp]".to_owned())?;
+        }
+        else
         {
-            IROperation::Add => (false, "add"),
-            IROperation::Subtract => (false, "sub"),
-            IROperation::Multiply => (false, "imul"),
-            IROperation::GreaterThan => (true, "setg"),
-            IROperation::LessThan=> (true, "setl"),
+            self.emit("mov edx, 0".to_owned())?;
+            self.emit("div dword [esp]".to_owned())?;
+        }
+        self.emit("add esp, 4".to_owned())?;
+        if to_str != result_str {
+            self.emit(format!("mov {}, {}", to_str, result_str))?;
+        }
+
+        if save_edx {
+            self.emit("pop edx".to_owned())?;
+        }
+        if save_eax {
+            self.emit("pop eax".to_owned())?;
+        }
+        Ok(())
+    }
+
+    fn generate_i32_shift(&mut self,
+                          operation_str: &str,
+                          to: &IRStorage,
+                          lhs: &IRStorage,
+                          rhs: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        // NOTE: A variable shift count has to be in cl. The value being
+        //       shifted is kept on the stack, as it could be in ecx too.
+        let to_str = self.value_of(4, to);
+        let lhs_str = self.value_of(4, lhs);
+        let rhs_str = self.value_of(4, rhs);
+        let save_ecx = to_str != "ecx" && self.allocator.is_in_use('c');
+        if save_ecx {
+            self.emit("push ecx".to_owned())?;
+        }
+
+        self.emit(format!("push {}", lhs_str))?;
+        if rhs_str != "ecx" {
+            self.emit(format!("mov ecx, {}", rhs_str))?;
+        }
+        self.emit(format!("{} dword [esp], cl", operation_str))?;
+        self.emit(format!("pop {}", to_str))?;
+
+        if save_ecx {
+            self.emit("pop ecx".to_owned())?;
         }
+        Ok(())
     }
 
     fn generate_i32_operation(&mut self,
                               operation: &IROperation,
+                              is_signed: bool,
                               to: &IRStorage,
                               lhs: &IRStorage,
                               rhs: &IRStorage)
         -> Result<(), Box<dyn Error>>
     {
-        let (is_comparison, operation_str) = Self::i32_operation_str(operation);
+        let (is_comparison, operation_str) = Self::i32_operation_str(operation, is_signed);
         let to_str = self.value_of(4, to);
         let lhs_str = self.value_of(4, lhs);
         let rhs_str = self.value_of(4, rhs);
+        if operation_str == "idiv" || operation_str == "div" {
+            return self.generate_i32_division(operation, is_signed, to, lhs, &rhs_str);
+        }
+        if operation_str == "shl" || operation_str == "sar" || operation_str == "shr" {
+            return self.generate_i32_shift(operation_str, to, lhs, rhs);
+        }
+
         if is_comparison
         {
             let scratch_register = self.allocator.allocate_scratch_register(4);
@@ -425,9 +829,13 @@ impl<W> X86Output<W>
                                        i: i32)
         -> Result<(), Box<dyn Error>>
     {
-        let (is_comparison, operation_str) = Self::i32_operation_str(operation);
+        let (is_comparison, operation_str) = Self::i32_operation_str(operation, true);
         let to_str = self.value_of(4, to);
         let lhs_str = self.value_of(4, lhs);
+        if operation_str == "idiv" {
+            return self.generate_i32_division(operation, true, to, lhs, &i.to_string());
+        }
+
         if is_comparison
         {
             self.emit(format!("cmp {}, {}", lhs_str, i))?;
@@ -444,103 +852,937 @@ impl<W> X86Output<W>
         Ok(())
     }
 
-    fn generate_goto_if_not(&mut self, label: &str, condition: &IRStorage)
+    fn generate_i32_negate(&mut self, to: &IRStorage, value: &IRStorage)
         -> Result<(), Box<dyn Error>>
     {
-        let condition_str = self.value_of(1, condition);
-        self.emit(format!("cmp {}, 0", condition_str))?;
-        self.emit(format!("jz {}", label))?;
+        let to_str = self.value_of(4, to);
+        let value_str = self.value_of(4, value);
+        if to_str != value_str {
+            self.emit(format!("mov {}, {}", to_str, value_str))?;
+        }
+        self.emit(format!("neg {}", to_str))?;
         Ok(())
     }
 
-    fn generate_function(&mut self, function: &IRFunction)
+    fn generate_i8_comparison(&mut self,
+                              operation: &IROperation,
+                              to: &IRStorage,
+                              lhs: &IRStorage,
+                              rhs: &str)
         -> Result<(), Box<dyn Error>>
     {
-        self.emit(format!("{}:", function.name))?;
-        self.emit(format!("push ebp"))?;
-        self.emit(format!("mov ebp, esp"))?;
-        if function.stack_frame_size > 0 {
-            self.emit(format!("sub esp, {}", function.stack_frame_size))?;
-        }
-
-        for ir in &function.code
-        {
-            // self.emit(format!("; {}", ir))?;
-            match ir
-            {
-                IR::AllocateRegister(register, size) => 
-                {
-                    match self.allocator.allocate(*register, *size)
-                    {
-                        AllocationType::Register => {},
-                        AllocationType::Stack => self.emit(format!("sub esp, {}", size))?,
-                    }
-                },
-                IR::FreeRegister(register) =>
-                {
-                    let (allocation_type, size) = self.allocator.free(*register);
-                    match allocation_type
-                    {
-                        AllocationType::Register => {},
-                        AllocationType::Stack => self.emit(format!("add esp, {}", size))?,
-                    }
-                },
-
-                IR::SetI32(to, i) => self.generate_set_i32(to, *i)?,
-                IR::SetI8(to, i) => self.generate_set_i8(to, *i)?,
-                IR::SetString(to, s) => self.generate_set_string(to, s)?,
-                IR::SetRef(to, value) => self.generate_set_ref(to, value)?,
-                IR::Deref(to, value, size) => self.generate_deref(to, value, *size)?,
-                IR::Move(to, from, size) => self.generate_move(to, from, *size)?,
-
-                IR::MoveToOffset(offset, to, from, size) =>
-                    self.generate_move_to_offset(*offset, to, from, *size)?,
-
-                // IR::MoveFromOffset(_, _, _, _) => panic!(),
-                IR::PushI32(i) => self.emit(format!("push {}", i))?,
-                IR::PushI8(i) => self.generate_push_u8(*i)?,
-                IR::PushString(s) => self.generate_push_string(s)?,
-                IR::Push(value, size) => self.generate_push(value, *size)?,
-                IR::Pop(count) => self.emit(format!("add esp, {}", count))?,
-
-                IR::I32ConstantOperation(op, to, lhs, i) => self.generate_i32_constant_operation(op, to, lhs, *i)?,
-                IR::I32Operation(op, to, lhs, rhs) => self.generate_i32_operation(op, to, lhs, rhs)?,
-
-                IR::Call(function, return_value, size) => self.generate_call(function, return_value, *size)?,
-                IR::Label(label) => self.emit(format!("{}:", label))?,
-                IR::Goto(label) => self.emit(format!("jmp {}", label))?,
-                IR::GotoIfNot(label, condition) => self.generate_goto_if_not(label, condition)?,
-                IR::Return(value, size) => self.generate_return(value, *size)?,
-            }
-        }
+        let (is_comparison, operation_str) = Self::i32_operation_str(operation, true);
+        assert!(is_comparison);
 
-        self.emit(format!(""))?;
+        let to_str = self.value_of(1, to);
+        let lhs_str = self.value_of(1, lhs);
+        let scratch_register = self.allocator.allocate_scratch_register(1);
+        self.emit(format!("mov {}, {}", scratch_register, lhs_str))?;
+        self.emit(format!("cmp {}, {}", scratch_register, rhs))?;
+        self.emit(format!("{} {}", operation_str, to_str))?;
+        self.allocator.free_scratch_register(scratch_register);
         Ok(())
     }
 
-    pub fn generate_extern(&mut self, extern_: &str)
+    fn generate_i8_operation(&mut self,
+                             operation: &IROperation,
+                             to: &IRStorage,
+                             lhs: &IRStorage,
+                             rhs: &IRStorage)
         -> Result<(), Box<dyn Error>>
     {
-        self.emit(format!("extern {}", extern_))?;
-        Ok(())
+        let rhs_str = self.value_of(1, rhs);
+        self.generate_i8_comparison(operation, to, lhs, &rhs_str)
     }
 
-    pub fn generate_header(&mut self)
+    fn generate_i8_constant_operation(&mut self,
+                                      operation: &IROperation,
+                                      to: &IRStorage,
+                                      lhs: &IRStorage,
+                                      i: i8)
         -> Result<(), Box<dyn Error>>
     {
-        self.emit(format!("global main"))?;
-        self.emit(format!("section .text"))?;
-        self.emit(format!(""))?;
-        Ok(())
+        self.generate_i8_comparison(operation, to, lhs, &i.to_string())
     }
 
-    pub fn generate_footer(&mut self)
-        -> Result<(), Box<dyn Error>>
+    /// The libgcc function that does a 64-bit operation, for those that
+    /// can't be done a dword at a time.
+    fn i64_helper_function(operation: &IROperation, is_signed: bool) -> Option<&'static str>
     {
-        self.emit(format!("section .data"))?;
-        for (string, id) in self.strings.clone() {
-            self.emit(format!("str{}: db \"{}\", 0", id, string))?;
-        }
+        Some(match (operation, is_signed)
+        {
+            (IROperation::Multiply, _) => "__muldi3",
+            (IROperation::Divide, true) => "__divdi3",
+            (IROperation::Divide, false) => "__udivdi3",
+            (IROperation::Modulo, true) => "__moddi3",
+            (IROperation::Modulo, false) => "__umoddi3",
+            (IROperation::ShiftLeft, _) => "__ashldi3",
+            (IROperation::ShiftRight, true) => "__ashrdi3",
+            (IROperation::ShiftRight, false) => "__lshrdi3",
+            _ => return None,
+        })
+    }
+
+    /// Call a libgcc function with `arguments`, given as the dwords of
+    /// each in order, and store the 64-bit int it returns in `to`.
+    fn generate_helper_call(&mut self,
+                            function: &'static str,
+                            arguments: &[(&IRStorage, i32)],
+                            to: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        if !self.helper_functions.contains(&function) {
+            self.helper_functions.push(function);
+        }
+
+        // NOTE: The helpers are C functions, so they're free to change
+        //       eax, ecx and edx.
+        let saved_registers = ['a', 'c', 'd'].iter()
+            .filter(|letter| self.allocator.is_in_use(**letter))
+            .map(|letter| X86Register::General(*letter, 4, false))
+            .collect::<Vec<_>>();
+        for register in &saved_registers {
+            self.emit(format!("push {}", register))?;
+        }
+
+        // NOTE: Arguments are pushed last to first, and the high dword
+        //       of each before the low one.
+        let mut pushed = 4 * saved_registers.len() as i32;
+        let arguments_size = 4 * arguments.len() as i32;
+        for (argument, offset) in arguments.iter().rev()
+        {
+            let argument_str = match self.register_for(argument)
+            {
+                Some(register) => register.to_string(),
+                None => self.dword_of(argument, *offset, pushed),
+            };
+            self.emit(format!("push {}", argument_str))?;
+            pushed += 4;
+        }
+
+        self.emit(format!("call {}", function))?;
+        self.emit(format!("add esp, {}", arguments_size))?;
+        pushed -= arguments_size;
+
+        let low_str = self.dword_of(to, 0, pushed);
+        let high_str = self.dword_of(to, 4, pushed);
+        self.emit(format!("mov {}, eax", low_str))?;
+        self.emit(format!("mov {}, edx", high_str))?;
+        for register in saved_registers.iter().rev() {
+            self.emit(format!("pop {}", register))?;
+        }
+        Ok(())
+    }
+
+    fn generate_i64_comparison(&mut self,
+                               operation: &IROperation,
+                               is_signed: bool,
+                               to: &IRStorage,
+                               lhs: &IRStorage,
+                               rhs: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        let to_register = self.register_for(to).unwrap();
+        let scratch_register = self.allocator.allocate_scratch_register(4);
+        match operation
+        {
+            IROperation::Equals | IROperation::NotEquals =>
+            {
+                // NOTE: The values are equal when neither half has
+                //       any bits that differ.
+                let to_dword = to_register.of_size(4, false);
+                let (_, set_str) = Self::i32_operation_str(operation, is_signed);
+                for (register, offset) in [(&to_dword, 4), (&scratch_register, 0)]
+                {
+                    let lhs_str = self.dword_of(lhs, offset, 0);
+                    let rhs_str = self.dword_of(rhs, offset, 0);
+                    self.emit(format!("mov {}, {}", register, lhs_str))?;
+                    self.emit(format!("xor {}, {}", register, rhs_str))?;
+                }
+                self.emit(format!("or {}, {}", to_dword, scratch_register))?;
+                self.emit(format!("{} {}", set_str, to_register))?;
+            },
+
+            _ =>
+            {
+                // NOTE: Subtracting the whole 64-bit values sets the flags
+                //       for `<` and `>=`, so the operands are swapped
+                //       for `>` and `<=`.
+                let (lhs, rhs, operation) = match operation
+                {
+                    IROperation::GreaterThan => (rhs, lhs, IROperation::LessThan),
+                    IROperation::LessThanEquals => (rhs, lhs, IROperation::GreaterThanEquals),
+                    operation => (lhs, rhs, operation.clone()),
+                };
+
+                let (_, set_str) = Self::i32_operation_str(&operation, is_signed);
+                for (operation_str, offset) in [("cmp", 0), ("sbb", 4)]
+                {
+                    let lhs_str = self.dword_of(lhs, offset, 0);
+                    let rhs_str = self.dword_of(rhs, offset, 0);
+                    self.emit(format!("mov {}, {}", scratch_register, lhs_str))?;
+                    self.emit(format!("{} {}, {}", operation_str, scratch_register, rhs_str))?;
+                }
+                self.emit(format!("{} {}", set_str, to_register))?;
+            },
+        }
+
+        self.allocator.free_scratch_register(scratch_register);
+        Ok(())
+    }
+
+    /// 64-bit ints are kept in memory, and worked on a dword at a time.
+    fn generate_i64_operation(&mut self,
+                              operation: &IROperation,
+                              is_signed: bool,
+                              to: &IRStorage,
+                              lhs: &IRStorage,
+                              rhs: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        if let Some(function) = Self::i64_helper_function(operation, is_signed)
+        {
+            // NOTE: A shift count is just an int.
+            let is_shift = matches!(operation, IROperation::ShiftLeft | IROperation::ShiftRight);
+            let arguments = [(lhs, 0), (lhs, 4), (rhs, 0), (rhs, 4)];
+            let argument_count = if is_shift { 3 } else { 4 };
+            return self.generate_helper_call(function, &arguments[..argument_count], to);
+        }
+
+        if operation.is_comparison() {
+            return self.generate_i64_comparison(operation, is_signed, to, lhs, rhs);
+        }
+
+        // NOTE: The low dwords are done first, so any carry or borrow
+        //       from them goes into the high dwords.
+        let (low_operation_str, high_operation_str) = match operation
+        {
+            IROperation::Add => ("add", "adc"),
+            IROperation::Subtract => ("sub", "sbb"),
+            IROperation::And => ("and", "and"),
+            IROperation::Or => ("or", "or"),
+            IROperation::Xor => ("xor", "xor"),
+            _ => panic!(),
+        };
+
+        let scratch_register = self.allocator.allocate_scratch_register(4);
+        for (operation_str, offset) in [(low_operation_str, 0), (high_operation_str, 4)]
+        {
+            let to_str = self.dword_of(to, offset, 0);
+            let lhs_str = self.dword_of(lhs, offset, 0);
+            let rhs_str = self.dword_of(rhs, offset, 0);
+            self.emit(format!("mov {}, {}", scratch_register, lhs_str))?;
+            self.emit(format!("{} {}, {}", operation_str, scratch_register, rhs_str))?;
+            self.emit(format!("mov {}, {}", to_str, scratch_register))?;
+        }
+
+        self.allocator.free_scratch_register(scratch_register);
+        Ok(())
+    }
+
+    fn generate_i64_negate(&mut self, to: &IRStorage, value: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        // NOTE: Negating the low dword sets the carry flag if it wasn't
+        //       zero, which is then borrowed from the high dword.
+        let scratch_register = self.allocator.allocate_scratch_register(4);
+        let value_low_str = self.dword_of(value, 0, 0);
+        let value_high_str = self.dword_of(value, 4, 0);
+        let to_low_str = self.dword_of(to, 0, 0);
+        let to_high_str = self.dword_of(to, 4, 0);
+        self.emit(format!("mov {}, {}", scratch_register, value_low_str))?;
+        self.emit(format!("neg {}", scratch_register))?;
+        self.emit(format!("mov {}, {}", to_low_str, scratch_register))?;
+        self.emit(format!("mov {}, {}", scratch_register, value_high_str))?;
+        self.emit(format!("adc {}, 0", scratch_register))?;
+        self.emit(format!("neg {}", scratch_register))?;
+        self.emit(format!("mov {}, {}", to_high_str, scratch_register))?;
+        self.allocator.free_scratch_register(scratch_register);
+        Ok(())
+    }
+
+    /// A float in memory or, for an `f32`, in a register, when `pushed`
+    /// bytes have been pushed onto the stack since it was allocated.
+    fn float_value_of(&mut self, size: usize, storage: &IRStorage, pushed: i32) -> String
+    {
+        if self.register_for(storage).is_some() {
+            return self.value_of(size, storage);
+        }
+
+        let (register, offset) = self.offset_of(storage);
+        let pushed = if register == X86Register::esp() { pushed } else { 0 };
+        register.offset(size, offset + pushed)
+    }
+
+    fn float_suffix(size: usize) -> &'static str
+    {
+        match size
+        {
+            4 => "ss",
+            8 => "sd",
+            _ => panic!(),
+        }
+    }
+
+    fn float_move_instruction(size: usize) -> &'static str
+    {
+        match size
+        {
+            4 => "movd",
+            8 => "movsd",
+            _ => panic!(),
+        }
+    }
+
+    // NOTE: Floats are worked on in the SSE registers, but only within
+    //       a single instruction, so `xmm0` and `xmm1` are always free.
+    fn generate_load_float(&mut self, xmm_register: &str, size: usize, from: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        let from_str = self.float_value_of(size, from, 0);
+        self.emit(format!("{} {}, {}", Self::float_move_instruction(size), xmm_register, from_str))?;
+        Ok(())
+    }
+
+    fn generate_store_float(&mut self, to: &IRStorage, size: usize, xmm_register: &str)
+        -> Result<(), Box<dyn Error>>
+    {
+        let to_str = self.float_value_of(size, to, 0);
+        self.emit(format!("{} {}, {}", Self::float_move_instruction(size), to_str, xmm_register))?;
+        Ok(())
+    }
+
+    // NOTE: Converting from and to 64-bit ints, and floats returned from
+    //       C functions, need the x87 FPU, whose stack is loaded from and
+    //       stored to memory.
+    fn generate_fld(&mut self, from: &IRStorage, size: usize)
+        -> Result<(), Box<dyn Error>>
+    {
+        match self.register_for(from)
+        {
+            Some(register) =>
+            {
+                self.emit(format!("push {}", register))?;
+                self.emit("fld dword [esp]".to_owned())?;
+                self.emit("add esp, 4".to_owned())?;
+            },
+
+            None =>
+            {
+                let from_str = self.float_value_of(size, from, 0);
+                self.emit(format!("fld {}", from_str))?;
+            },
+        }
+        Ok(())
+    }
+
+    fn generate_fstp(&mut self, to: &IRStorage, size: usize)
+        -> Result<(), Box<dyn Error>>
+    {
+        match self.register_for(to)
+        {
+            Some(register) =>
+            {
+                self.emit("sub esp, 4".to_owned())?;
+                self.emit("fstp dword [esp]".to_owned())?;
+                self.emit(format!("pop {}", register))?;
+            },
+
+            None =>
+            {
+                let to_str = self.float_value_of(size, to, 0);
+                self.emit(format!("fstp {}", to_str))?;
+            },
+        }
+        Ok(())
+    }
+
+    fn generate_set_f32(&mut self, to: &IRStorage, x: f32)
+        -> Result<(), Box<dyn Error>>
+    {
+        let to_str = self.value_of(4, to);
+        self.emit(format!("mov {}, {}", to_str, x.to_bits()))?;
+        Ok(())
+    }
+
+    fn generate_float_operation(&mut self,
+                                operation: &IROperation,
+                                size: usize,
+                                to: &IRStorage,
+                                lhs: &IRStorage,
+                                rhs: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        // NOTE: `ucomiss` and `ucomisd` set the flags like an unsigned
+        //       compare, where being unordered, because of a NaN, looks
+        //       like being both less than and equal. So `<` and `<=` are
+        //       done as `>` and `>=` with the operands swapped, which are
+        //       false for NaN.
+        let (lhs, rhs, operation) = match operation
+        {
+            IROperation::LessThan => (rhs, lhs, IROperation::GreaterThan),
+            IROperation::LessThanEquals => (rhs, lhs, IROperation::GreaterThanEquals),
+            operation => (lhs, rhs, operation.clone()),
+        };
+
+        let suffix = Self::float_suffix(size);
+        self.generate_load_float("xmm0", size, lhs)?;
+        self.generate_load_float("xmm1", size, rhs)?;
+        if !operation.is_comparison()
+        {
+            let operation_str = match operation
+            {
+                IROperation::Add => "add",
+                IROperation::Subtract => "sub",
+                IROperation::Multiply => "mul",
+                IROperation::Divide => "div",
+                _ => panic!(),
+            };
+
+            self.emit(format!("{}{} xmm0, xmm1", operation_str, suffix))?;
+            return self.generate_store_float(to, size, "xmm0");
+        }
+
+        let to_str = self.value_of(1, to);
+        self.emit(format!("ucomi{} xmm0, xmm1", suffix))?;
+        match operation
+        {
+            IROperation::GreaterThan => self.emit(format!("seta {}", to_str))?,
+            IROperation::GreaterThanEquals => self.emit(format!("setae {}", to_str))?,
+
+            // NOTE: The parity flag is set when the values are unordered.
+            IROperation::Equals | IROperation::NotEquals =>
+            {
+                let (set_str, parity_set_str, combine_str) = match operation
+                {
+                    IROperation::Equals => ("sete", "setnp", "and"),
+                    _ => ("setne", "setp", "or"),
+                };
+
+                let scratch_register = self.allocator.allocate_scratch_register(1);
+                self.emit(format!("{} {}", set_str, to_str))?;
+                self.emit(format!("{} {}", parity_set_str, scratch_register))?;
+                self.emit(format!("{} {}, {}", combine_str, to_str, scratch_register))?;
+                self.allocator.free_scratch_register(scratch_register);
+            },
+
+            _ => panic!(),
+        }
+        Ok(())
+    }
+
+    fn generate_float_negate(&mut self, size: usize, to: &IRStorage, value: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        // NOTE: A float is negated by flipping its sign bit, which is
+        //       the top bit of its last dword.
+        let scratch_register = self.allocator.allocate_scratch_register(4);
+        let mut offset = 0;
+        while offset < size as i32
+        {
+            let is_last_dword = offset + 4 == size as i32;
+            let (value_str, to_str) = match size
+            {
+                4 => (self.value_of(4, value), self.value_of(4, to)),
+                _ => (self.dword_of(value, offset, 0), self.dword_of(to, offset, 0)),
+            };
+
+            self.emit(format!("mov {}, {}", scratch_register, value_str))?;
+            if is_last_dword {
+                self.emit(format!("xor {}, 0x80000000", scratch_register))?;
+            }
+            self.emit(format!("mov {}, {}", to_str, scratch_register))?;
+            offset += 4;
+        }
+
+        self.allocator.free_scratch_register(scratch_register);
+        Ok(())
+    }
+
+    fn generate_convert_float(&mut self,
+                              to: &IRStorage,
+                              to_size: usize,
+                              from: &IRStorage,
+                              from_size: usize)
+        -> Result<(), Box<dyn Error>>
+    {
+        self.generate_load_float("xmm0", from_size, from)?;
+        self.emit(format!("cvt{}2{} xmm0, xmm0",
+            Self::float_suffix(from_size), Self::float_suffix(to_size)))?;
+        self.generate_store_float(to, to_size, "xmm0")
+    }
+
+    fn generate_int_to_float(&mut self,
+                             to: &IRStorage,
+                             to_size: usize,
+                             from: &IRStorage,
+                             from_size: usize,
+                             is_signed: bool)
+        -> Result<(), Box<dyn Error>>
+    {
+        if from_size == 4
+        {
+            let from_str = self.value_of(4, from);
+            self.emit(format!("cvtsi2{} xmm0, {}", Self::float_suffix(to_size), from_str))?;
+            return self.generate_store_float(to, to_size, "xmm0");
+        }
+
+        let (from_register, from_offset) = self.offset_of(from);
+        self.emit(format!("fild {}", from_register.offset(8, from_offset)))?;
+        if !is_signed
+        {
+            // NOTE: `fild` reads the int as signed, so when its top bit is
+            //       set 2^64 is added back on, given here as an `f32`.
+            let scratch_register = self.allocator.allocate_scratch_register(4);
+            let high_str = self.dword_of(from, 4, 0);
+            self.emit(format!("mov {}, {}", scratch_register, high_str))?;
+            self.emit(format!("sar {}, 31", scratch_register))?;
+            self.emit(format!("and {}, 0x5F800000", scratch_register))?;
+            self.emit(format!("push {}", scratch_register))?;
+            self.emit("fadd dword [esp]".to_owned())?;
+            self.emit("add esp, 4".to_owned())?;
+            self.allocator.free_scratch_register(scratch_register);
+        }
+        self.generate_fstp(to, to_size)
+    }
+
+    fn generate_float_to_int(&mut self,
+                             to: &IRStorage,
+                             to_size: usize,
+                             from: &IRStorage,
+                             from_size: usize,
+                             is_signed: bool)
+        -> Result<(), Box<dyn Error>>
+    {
+        if to_size == 4
+        {
+            let to_str = self.value_of(4, to);
+            let scratch_register = self.allocator.allocate_scratch_register(4);
+            self.generate_load_float("xmm0", from_size, from)?;
+            self.emit(format!("cvtt{}2si {}, xmm0", Self::float_suffix(from_size), scratch_register))?;
+            self.emit(format!("mov {}, {}", to_str, scratch_register))?;
+            self.allocator.free_scratch_register(scratch_register);
+            return Ok(());
+        }
+
+        if !is_signed
+        {
+            let function = if from_size == 4 { "__fixunssfdi" } else { "__fixunsdfdi" };
+            let arguments = [(from, 0), (from, 4)];
+            return self.generate_helper_call(function, &arguments[..from_size / 4], to);
+        }
+
+        // NOTE: `fistp` rounds the way the FPU control word says to, so
+        //       it's changed to round towards zero, then put back after.
+        self.generate_fld(from, from_size)?;
+        let scratch_register = self.allocator.allocate_scratch_register(2);
+        self.emit("sub esp, 4".to_owned())?;
+        self.emit("fnstcw word [esp]".to_owned())?;
+        self.emit(format!("mov {}, word [esp]", scratch_register))?;
+        self.emit(format!("or {}, 0x0C00", scratch_register))?;
+        self.emit(format!("mov word [esp+2], {}", scratch_register))?;
+        self.emit("fldcw word [esp+2]".to_owned())?;
+        let to_str = self.float_value_of(8, to, 4);
+        self.emit(format!("fistp {}", to_str))?;
+        self.emit("fldcw word [esp]".to_owned())?;
+        self.emit("add esp, 4".to_owned())?;
+        self.allocator.free_scratch_register(scratch_register);
+        Ok(())
+    }
+
+    fn generate_goto_if(&mut self, label: &str, condition: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        let condition_str = self.value_of(1, condition);
+        self.emit(format!("cmp {}, 0", condition_str))?;
+        self.emit(format!("jnz {}", label))?;
+        Ok(())
+    }
+
+    fn generate_goto_if_not(&mut self, label: &str, condition: &IRStorage)
+        -> Result<(), Box<dyn Error>>
+    {
+        let condition_str = self.value_of(1, condition);
+        self.emit(format!("cmp {}, 0", condition_str))?;
+        self.emit(format!("jz {}", label))?;
+        Ok(())
+    }
+
+    /// A value on top of the stack that's freed as soon as it's pushed
+    /// is already where the argument would go, so it's left there rather
+    /// than copied. The free that follows is then skipped.
+    fn push_in_place(&mut self, ir: &IR, next_ir: Option<&IR>) -> bool
+    {
+        match (ir, next_ir)
+        {
+            (IR::Push(IRStorage::Register(register), size), Some(IR::FreeRegister(freed)))
+                if register == freed && *size > 4 =>
+            {
+                self.allocator.use_as_argument(*register)
+            },
+
+            _ => false,
+        }
+    }
+
+    /// The most values that are alive at once in a function beyond
+    /// what fits in the registers.
+    fn spill_slot_count(function: &IRFunction) -> usize
+    {
+        let mut register_values = HashSet::new();
+        let mut most_alive = 0;
+        for ir in &function.code
+        {
+            match ir
+            {
+                IR::AllocateRegister(register, size) if *size <= 4 =>
+                {
+                    register_values.insert(*register);
+                    most_alive = most_alive.max(register_values.len());
+                },
+                IR::FreeRegister(register) =>
+                {
+                    register_values.remove(register);
+                },
+                _ => {},
+            }
+        }
+
+        most_alive.saturating_sub(4)
+    }
+
+    /// The most registers holding a value other than the one returned,
+    /// at any call in a function.
+    fn call_save_slot_count(function: &IRFunction) -> usize
+    {
+        let mut register_values = HashSet::new();
+        let mut most_saved = 0;
+        for ir in &function.code
+        {
+            match ir
+            {
+                IR::AllocateRegister(register, size) if *size <= 4 =>
+                {
+                    register_values.insert(*register);
+                },
+                IR::FreeRegister(register) =>
+                {
+                    register_values.remove(register);
+                },
+                IR::Call(_, return_value, _) | IR::CallIndirect(_, return_value, _) =>
+                {
+                    let saved = match return_value
+                    {
+                        IRStorage::Register(register) if register_values.contains(register) =>
+                            register_values.len() - 1,
+                        _ => register_values.len(),
+                    };
+                    most_saved = most_saved.max(saved.min(4));
+                },
+                _ => {},
+            }
+        }
+
+        most_saved
+    }
+
+    /// The IR registers an instruction uses, if it's one that can have
+    /// registers pushed around it. Those that jump, or push and pop
+    /// arguments, can't.
+    fn register_operands(ir: &IR) -> Option<Vec<IRRegister>>
+    {
+        let storages = match ir
+        {
+            IR::SetI64(to, _) | IR::SetI32(to, _) | IR::SetI16(to, _) | IR::SetI8(to, _) |
+            IR::SetF64(to, _) | IR::SetF32(to, _) | IR::SetString(to, _) |
+            IR::SetFunction(to, _) | IR::Zero(to, _) | IR::ExternFloatReturn(to, _) =>
+                vec![to],
+
+            IR::SetRef(to, value) | IR::Deref(to, value, _) | IR::Store(to, value, _) |
+            IR::Move(to, value, _) | IR::MoveToOffset(_, to, value, _) |
+            IR::I32Negate(to, value) | IR::I64Negate(to, value) |
+            IR::F32Negate(to, value) | IR::F64Negate(to, value) |
+            IR::I32ConstantOperation(_, to, value, _) | IR::I8ConstantOperation(_, to, value, _) |
+            IR::ConvertInt(to, _, value, _, _) | IR::ConvertFloat(to, _, value, _) |
+            IR::IntToFloat(to, _, value, _, _) | IR::FloatToInt(to, _, value, _, _) =>
+                vec![to, value],
+
+            IR::I32Operation(_, to, lhs, rhs) | IR::U32Operation(_, to, lhs, rhs) |
+            IR::I64Operation(_, to, lhs, rhs) | IR::U64Operation(_, to, lhs, rhs) |
+            IR::I8Operation(_, to, lhs, rhs) |
+            IR::F32Operation(_, to, lhs, rhs) | IR::F64Operation(_, to, lhs, rhs) =>
+                vec![to, lhs, rhs],
+
+            _ => return None,
+        };
+
+        Some(storages.into_iter()
+            .filter_map(|storage| match storage
+            {
+                IRStorage::Register(register) => Some(*register),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Make sure an instruction has its spilled values in registers, and
+    /// enough scratch registers, by pushing values it doesn't use. Gives
+    /// the registers pushed and the values moved into registers.
+    fn make_room_for(&mut self, ir: &IR, operands: Vec<IRRegister>)
+        -> Result<(Vec<char>, Vec<IRRegister>), Box<dyn Error>>
+    {
+        // NOTE: Storing through an address, or copying from one, needs a
+        //       register for the address as well as one to move with.
+        let scratch_count = match ir
+        {
+            IR::Store(_, _, _) | IR::Deref(_, _, _) => 2,
+            _ => 1,
+        };
+
+        let mut spilled = operands.iter()
+            .filter(|register| self.allocator.allocation_type(**register) == AllocationType::Spilled)
+            .cloned()
+            .collect::<Vec<_>>();
+        spilled.sort_unstable();
+        spilled.dedup();
+
+        let mut saved = Vec::new();
+        while self.allocator.available_register_count() < spilled.len() + scratch_count
+        {
+            match self.allocator.find_register_to_save(&operands)
+            {
+                Some(letter) =>
+                {
+                    self.emit(format!("push e{}x", letter))?;
+                    self.allocator.save_register(letter);
+                    saved.push(letter);
+                },
+                None => break,
+            }
+        }
+
+        for register in &spilled
+        {
+            let (_, size) = self.allocator.spill_slot(*register);
+            let slot_str = X86Register::ebp().offset(size, self.spill_slot_offset(*register));
+            let x86_register = self.allocator.borrow_register(*register);
+            self.emit(format!("mov {}, {}", x86_register, slot_str))?;
+        }
+
+        Ok((saved, spilled))
+    }
+
+    fn give_back_room(&mut self, (saved, spilled): (Vec<char>, Vec<IRRegister>))
+        -> Result<(), Box<dyn Error>>
+    {
+        for register in spilled
+        {
+            let (_, size) = self.allocator.spill_slot(register);
+            let slot_str = X86Register::ebp().offset(size, self.spill_slot_offset(register));
+            let x86_register = self.allocator.register_for(register);
+            self.emit(format!("mov {}, {}", slot_str, x86_register))?;
+            self.allocator.give_back_register(register);
+        }
+
+        for letter in saved.into_iter().rev()
+        {
+            self.emit(format!("pop e{}x", letter))?;
+            self.allocator.restore_register(letter);
+        }
+        Ok(())
+    }
+
+    fn generate_function(&mut self, function: &IRFunction, is_address_taken: bool)
+        -> Result<(), Box<dyn Error>>
+    {
+        self.emit(format!("{}:", function.name))?;
+        self.emit(format!("push ebp"))?;
+        self.emit(format!("mov ebp, esp"))?;
+        if function.stack_frame_size > 0 {
+            self.emit(format!("sub esp, {}", function.stack_frame_size))?;
+        }
+
+        // NOTE: Functions given out as values may be called from C, like
+        //       a `qsort` comparator, which expects `ebx` to be kept.
+        //       It's saved just below the locals.
+        self.saved_ebx_offset = None;
+        if is_address_taken
+        {
+            self.emit("push ebx".to_owned())?;
+            self.saved_ebx_offset = Some(-(function.stack_frame_size as i32) - 4);
+        }
+
+        // NOTE: Values that don't fit in the registers are spilled to
+        //       a dword each, just below the locals.
+        self.spill_area_offset = function.stack_frame_size as i32 +
+            if is_address_taken { 4 } else { 0 };
+        let spill_slot_count = Self::spill_slot_count(function);
+        self.call_save_area_offset = self.spill_area_offset + spill_slot_count as i32 * 4;
+        let save_slot_count = spill_slot_count + Self::call_save_slot_count(function);
+        if save_slot_count > 0 {
+            self.emit(format!("sub esp, {}", save_slot_count * 4))?;
+        }
+
+        let mut is_freed_as_argument = false;
+        for (index, ir) in function.code.iter().enumerate()
+        {
+            // self.emit(format!("; {}", ir))?;
+            if is_freed_as_argument
+            {
+                is_freed_as_argument = false;
+                continue;
+            }
+
+            if self.push_in_place(ir, function.code.get(index + 1))
+            {
+                is_freed_as_argument = true;
+                continue;
+            }
+
+            let room = match Self::register_operands(ir)
+            {
+                Some(operands) => Some(self.make_room_for(ir, operands)?),
+                None => None,
+            };
+
+            match ir
+            {
+                IR::AllocateRegister(register, size) => 
+                {
+                    match self.allocator.allocate(*register, *size)
+                    {
+                        (AllocationType::Register, _) => {},
+                        (AllocationType::Spilled, _) => {},
+                        (AllocationType::Stack, size) => self.emit(format!("sub esp, {}", size))?,
+                    }
+                },
+                IR::FreeRegister(register) =>
+                {
+                    let (allocation_type, size) = self.allocator.free(*register);
+                    match allocation_type
+                    {
+                        AllocationType::Register => {},
+                        AllocationType::Spilled => {},
+                        AllocationType::Stack if size == 0 => {},
+                        AllocationType::Stack => self.emit(format!("add esp, {}", size))?,
+                    }
+                },
+
+                IR::SetI64(to, i) => self.generate_set_i64(to, *i)?,
+                IR::SetI32(to, i) => self.generate_set_i32(to, *i)?,
+                IR::SetI16(to, i) => self.generate_set_i16(to, *i)?,
+                IR::SetI8(to, i) => self.generate_set_i8(to, *i)?,
+                IR::SetF64(to, x) => self.generate_set_i64(to, x.to_bits() as i64)?,
+                IR::SetF32(to, x) => self.generate_set_f32(to, *x)?,
+                IR::SetString(to, s) => self.generate_set_string(to, s)?,
+                IR::SetRef(to, value) => self.generate_set_ref(to, value)?,
+                IR::SetFunction(to, function) => self.generate_set_function(to, function)?,
+                IR::Deref(to, value, size) => self.generate_deref(to, value, *size)?,
+                IR::Store(address, value, size) => self.generate_store(address, value, *size)?,
+                IR::Move(to, from, size) => self.generate_move(to, from, *size)?,
+
+                IR::MoveToOffset(offset, to, from, size) =>
+                    self.generate_move_to_offset(*offset, to, from, *size)?,
+
+                // IR::MoveFromOffset(_, _, _, _) => panic!(),
+                IR::Zero(to, size) => self.generate_zero(to, *size)?,
+
+                IR::ConvertInt(to, to_size, from, from_size, is_signed) =>
+                    self.generate_convert_int(to, *to_size, from, *from_size, *is_signed)?,
+                IR::ConvertFloat(to, to_size, from, from_size) =>
+                    self.generate_convert_float(to, *to_size, from, *from_size)?,
+                IR::IntToFloat(to, to_size, from, from_size, is_signed) =>
+                    self.generate_int_to_float(to, *to_size, from, *from_size, *is_signed)?,
+                IR::FloatToInt(to, to_size, from, from_size, is_signed) =>
+                    self.generate_float_to_int(to, *to_size, from, *from_size, *is_signed)?,
+
+                IR::PushI32(i) => self.emit(format!("push {}", i))?,
+                IR::PushI8(i) => self.generate_push_u8(*i)?,
+                IR::PushString(s) => self.generate_push_string(s)?,
+                IR::Push(value, size) => self.generate_push(value, *size)?,
+
+                IR::Pop(count) =>
+                {
+                    let size = self.allocator.pop(*count);
+                    self.emit(format!("add esp, {}", size))?;
+                },
+
+                IR::I32ConstantOperation(op, to, lhs, i) => self.generate_i32_constant_operation(op, to, lhs, *i)?,
+                IR::I32Operation(op, to, lhs, rhs) => self.generate_i32_operation(op, true, to, lhs, rhs)?,
+                IR::I8ConstantOperation(op, to, lhs, i) => self.generate_i8_constant_operation(op, to, lhs, *i)?,
+                IR::I8Operation(op, to, lhs, rhs) => self.generate_i8_operation(op, to, lhs, rhs)?,
+                IR::I32Negate(to, value) => self.generate_i32_negate(to, value)?,
+                IR::U32Operation(op, to, lhs, rhs) => self.generate_i32_operation(op, false, to, lhs, rhs)?,
+                IR::I64Operation(op, to, lhs, rhs) => self.generate_i64_operation(op, true, to, lhs, rhs)?,
+                IR::U64Operation(op, to, lhs, rhs) => self.generate_i64_operation(op, false, to, lhs, rhs)?,
+                IR::I64Negate(to, value) => self.generate_i64_negate(to, value)?,
+                IR::F32Operation(op, to, lhs, rhs) => self.generate_float_operation(op, 4, to, lhs, rhs)?,
+                IR::F64Operation(op, to, lhs, rhs) => self.generate_float_operation(op, 8, to, lhs, rhs)?,
+                IR::F32Negate(to, value) => self.generate_float_negate(4, to, value)?,
+                IR::F64Negate(to, value) => self.generate_float_negate(8, to, value)?,
+
+                IR::Call(function, return_value, size) => self.generate_call(function, return_value, *size)?,
+                IR::CallIndirect(function, return_value, size) =>
+                    self.generate_call_indirect(function, return_value, *size)?,
+                IR::ExternFloatReturn(return_value, size) => self.generate_fstp(return_value, *size)?,
+                IR::Label(label) => self.emit(format!("{}:", label))?,
+                IR::Goto(label) => self.emit(format!("jmp {}", label))?,
+                IR::GotoIf(label, condition) => self.generate_goto_if(label, condition)?,
+                IR::GotoIfNot(label, condition) => self.generate_goto_if_not(label, condition)?,
+                IR::Return(value, size) => self.generate_return(value, *size)?,
+            }
+
+            if let Some(room) = room {
+                self.give_back_room(room)?;
+            }
+
+            // NOTE: Pushed arguments are on top of the stack until
+            //       they're popped after the call.
+            match ir
+            {
+                IR::PushI32(_) | IR::PushI8(_) | IR::PushString(_) =>
+                    self.allocator.push(4),
+                IR::Push(_, size) =>
+                    self.allocator.push(argument_size(*size)),
+                _ => {},
+            }
+        }
+
+        self.emit(format!(""))?;
+        Ok(())
+    }
+
+    pub fn generate_extern(&mut self, extern_: &str)
+        -> Result<(), Box<dyn Error>>
+    {
+        self.emit(format!("extern {}", extern_))?;
+        Ok(())
+    }
+
+    pub fn generate_header(&mut self)
+        -> Result<(), Box<dyn Error>>
+    {
+        self.emit(format!("global main"))?;
+        self.emit(format!("section .text"))?;
+        self.emit(format!(""))?;
+        Ok(())
+    }
+
+    pub fn generate_footer(&mut self)
+        -> Result<(), Box<dyn Error>>
+    {
+        self.emit(format!("section .data"))?;
+        for (string, id) in self.strings.clone()
+        {
+            // Emit strings as a list of bytes, so escaped characters
+            // don't need to be quoted for the assembler.
+            let bytes = string.iter()
+                .chain(std::iter::once(&0u8))
+                .map(|byte| byte.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.emit(format!("str{}: db {}", id, bytes))?;
+        }
 
         self.emit(format!(""))?;
         Ok(())
@@ -554,14 +1796,93 @@ pub fn generate(program: IRProgram, stream: &mut impl Write)
     let mut output = X86Output::new(stream);
     output.generate_header()?;
 
-    for function in &program.functions {
-        output.generate_function(function)?;
+    let functions_with_address_taken = program.functions
+        .iter()
+        .flat_map(|function| &function.code)
+        .filter_map(|ir| match ir
+        {
+            IR::SetFunction(_, function) => Some(function.as_str()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    for function in &program.functions
+    {
+        let is_address_taken = functions_with_address_taken.contains(function.name.as_str());
+        output.generate_function(function, is_address_taken)?;
     }
     for extern_ in &program.externs {
         output.generate_extern(extern_)?;
     }
+    for helper_function in output.helper_functions.clone() {
+        output.generate_extern(helper_function)?;
+    }
 
     output.generate_footer()?;
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::intermediate::IRStorage::{Register, Local};
+
+    fn generate_lines(code: Vec<IR>, stack_frame_size: usize) -> Vec<String>
+    {
+        let function = IRFunction
+        {
+            name: "test".to_owned(),
+            code,
+            stack_frame_size,
+        };
+
+        let mut output = X86Output::new(Vec::new());
+        output.generate_function(&function, false).unwrap();
+        String::from_utf8(output.stream).unwrap()
+            .lines()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn address_is_kept_across_call_on_right_side()
+    {
+        // `s.size += f()`, where the address of `s.size` is worked
+        // out before `f` is called.
+        let lines = generate_lines(vec![
+            IR::AllocateRegister(0, 4),
+            IR::SetRef(Register(0), Local(4)),
+            IR::AllocateRegister(1, 4),
+            IR::Call("f".to_owned(), Register(1), 4),
+            IR::Pop(0),
+            IR::AllocateRegister(2, 4),
+            IR::Deref(Register(2), Register(0), 4),
+            IR::I32Operation(IROperation::Add, Register(2), Register(2), Register(1)),
+            IR::Store(Register(0), Register(2), 4),
+            IR::FreeRegister(2),
+            IR::FreeRegister(1),
+            IR::FreeRegister(0),
+        ], 4);
+
+        let call = lines.iter().position(|line| line == "call f").unwrap();
+        assert_eq!(lines[call - 1], "mov dword [ebp-8], eax");
+        assert_eq!(lines[call + 1], "mov ebx, eax");
+        assert_eq!(lines[call + 2], "mov eax, dword [ebp-8]");
+        assert!(lines.contains(&"sub esp, 4".to_owned()));
+    }
+
+    #[test]
+    fn call_with_nothing_alive_saves_nothing()
+    {
+        let lines = generate_lines(vec![
+            IR::AllocateRegister(0, 4),
+            IR::Call("f".to_owned(), Register(0), 4),
+            IR::Pop(0),
+            IR::FreeRegister(0),
+        ], 0);
+
+        assert_eq!(lines, vec!["test:", "push ebp", "mov ebp, esp", "call f", "add esp, 0", ""]);
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt;
 
+const QWORD: usize = 8;
 const DWORD: usize = 4;
 const WORD: usize = 2;
 const BYTE: usize = 1;
@@ -21,6 +22,7 @@ impl X86Register
     {
         let size_name = match size
         {
+            x if x == QWORD => "qword",
             x if x == DWORD => "dword",
             x if x == WORD => "word",
             x if x == BYTE => "byte",
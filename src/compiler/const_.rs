@@ -0,0 +1,114 @@
+use super::name_table::{Scope, ConstantType};
+use super::data_type::{derive_data_type, coerce_literal};
+use super::error::CompilerError;
+use crate::tokenizer::Token;
+use crate::ast::{Const, Expression};
+use std::error::Error;
+
+pub fn register_const(scope: &mut Scope, const_: &Const)
+    -> Result<(), Box<dyn Error>>
+{
+    // NOTE: The optimizer has already folded every value it could.
+    let mut value = match &const_.value
+    {
+        Expression::IntLiteral(_) |
+        Expression::TypedIntLiteral(_, _) |
+        Expression::FloatLiteral(_) |
+        Expression::TypedFloatLiteral(_, _) |
+        Expression::BoolLiteral(_) =>
+            Some(const_.value.clone()),
+        _ => None,
+    };
+
+    if let (Some(expected), Some(literal)) = (&const_.data_type, &value)
+    {
+        if let Some(coerced) = coerce_literal(literal, expected, Some(&const_.name))? {
+            value = Some(coerced);
+        }
+    }
+
+    if let (Some(expected), Some(value)) = (&const_.data_type, &value)
+    {
+        let data_type = derive_data_type(scope, value)?;
+        if &data_type != expected
+        {
+            return Err(CompilerError::new(&const_.name, format!(
+                "Constant '{}' is of type '{}', but was given a value of type '{}'",
+                const_.name.content(), expected, data_type)));
+        }
+    }
+
+    let name = const_.name.content();
+    let is_new = scope.put_constant(name.to_owned(), ConstantType
+    {
+        value,
+        module: const_.module,
+        is_exported: const_.is_exported,
+    });
+
+    if !is_new
+    {
+        return Err(CompilerError::new(&const_.name, format!(
+            "Constant '{}' is already defined", name)));
+    }
+    Ok(())
+}
+
+/// Find the value of the constant `name`, if there is one visible.
+pub fn find_constant(scope: &Scope, name: &Token)
+    -> Result<Option<Expression>, Box<dyn Error>>
+{
+    // NOTE: A constant in the current module hides any it imports.
+    let constants = scope.lookup_constants(name.content());
+    let current_module = scope.current_module();
+    let own_constant = constants
+        .iter()
+        .find(|constant| constant.module == current_module);
+
+    let visible_constants = match own_constant
+    {
+        Some(constant) => vec![constant],
+        None =>
+        {
+            constants
+                .iter()
+                .filter(|constant| scope.is_visible(constant.module, constant.is_exported))
+                .collect::<Vec<_>>()
+        },
+    };
+
+    let module_name = |module| scope.lookup_module(module)
+        .map(|description| description.name)
+        .unwrap_or_default();
+
+    let constant = match (visible_constants.as_slice(), constants.first())
+    {
+        ([constant], _) => *constant,
+        ([first, second, ..], _) =>
+        {
+            return Err(CompilerError::new(name, format!(
+                "Constant '{}' is ambiguous, it could be from module '{}' or '{}'",
+                name.content(), module_name(first.module), module_name(second.module))));
+        },
+
+        ([], Some(hidden_constant)) =>
+        {
+            return Err(CompilerError::new(name, format!(
+                "Constant '{}' is not exported from module '{}'",
+                name.content(), module_name(hidden_constant.module))));
+        },
+
+        ([], None) => return Ok(None),
+    };
+
+    match &constant.value
+    {
+        Some(value) => Ok(Some(value.clone())),
+        None =>
+        {
+            Err(CompilerError::new(name, format!(
+                "Value of constant '{}' can not be known at compile time",
+                name.content())))
+        },
+    }
+}
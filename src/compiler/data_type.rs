@@ -1,9 +1,11 @@
 use super::name_table::Scope;
-use super::function::{find_function_for_call, create_local_scope};
+use super::function::{find_function_for_call, find_function, create_local_scope};
+use super::const_::find_constant;
 use super::error::CompilerError;
+use super::intermediate::value::IRLocation;
 use crate::tokenizer::Token;
-use crate::ast::{Function, Expression};
-use crate::ast::{Call, Operation, OperationType};
+use crate::ast::{Function, Expression, ModuleId, ROOT_MODULE};
+use crate::ast::{Call, Operation, OperationType, FunctionRef};
 use crate::data_type::DataType;
 use std::error::Error;
 
@@ -70,7 +72,7 @@ fn derive_access_type(name_table: &mut Scope,
             }
         },
 
-        DataType::Ref(ref_type) =>
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) =>
             derive_access_type(name_table, *ref_type, field_name_token),
 
         _ => panic!(),
@@ -81,6 +83,10 @@ fn derive_operation_type(name_table: &mut Scope,
                          operation: &Operation)
     -> Result<DataType, Box<dyn Error>>
 {
+    if operation.operation_type == OperationType::Sizeof {
+        return Ok(DataType::Int);
+    }
+
     let lhs_type = derive_data_type(name_table, &operation.lhs)?;
     match operation.operation_type
     {
@@ -92,6 +98,12 @@ fn derive_operation_type(name_table: &mut Scope,
                 Expression::Identifier(field_name) =>
                     derive_access_type(name_table, lhs_type, field_name),
 
+                Expression::IntLiteral(index) =>
+                    tuple_item_type(&lhs_type, *index, operation.lhs.token()),
+
+                Expression::Call(call) =>
+                    derive_call_type(name_table, &field_call(operation, call)),
+
                 _ => panic!(),
             }
         },
@@ -101,7 +113,7 @@ fn derive_operation_type(name_table: &mut Scope,
             match lhs_type
             {
                 DataType::Array(data_type, _) => Ok(*data_type),
-                DataType::Ref(data_type) => Ok(*data_type),
+                DataType::Ref(data_type) | DataType::MutRef(data_type) => Ok(*data_type),
                 _ => panic!(),
             }
         },
@@ -110,27 +122,203 @@ fn derive_operation_type(name_table: &mut Scope,
         {
             match lhs_type
             {
-                DataType::Ref(ref_type) => Ok(*ref_type),
+                DataType::Ref(ref_type) | DataType::MutRef(ref_type) => Ok(*ref_type),
                 _ => panic!(),
             }
         },
 
-        // TODO: This should actually derive type.
-        OperationType::Add => Ok(DataType::Int),
-        OperationType::Subtract => Ok(DataType::Int),
-        OperationType::Multiply => Ok(DataType::Int),
+        OperationType::Add |
+        OperationType::Subtract |
+        OperationType::Multiply |
+        OperationType::Divide |
+        OperationType::Modulo |
+        OperationType::BitAnd |
+        OperationType::BitOr |
+        OperationType::BitXor |
+        OperationType::ShiftLeft |
+        OperationType::ShiftRight =>
+        {
+            let rhs_type = derive_data_type(name_table, operation.rhs.as_ref().unwrap())?;
+            Ok(arithmetic_type(&lhs_type, &rhs_type))
+        },
+
+        OperationType::BitNot |
+        OperationType::Negate => Ok(arithmetic_type(&lhs_type, &lhs_type)),
         OperationType::GreaterThan => Ok(DataType::Bool),
         OperationType::LessThan => Ok(DataType::Bool),
+        OperationType::GreaterThanEquals => Ok(DataType::Bool),
+        OperationType::LessThanEquals => Ok(DataType::Bool),
+        OperationType::Equals => Ok(DataType::Bool),
+        OperationType::NotEquals => Ok(DataType::Bool),
+        OperationType::And => Ok(DataType::Bool),
+        OperationType::Or => Ok(DataType::Bool),
+        OperationType::Not => Ok(DataType::Bool),
 
         OperationType::Ref => Ok(DataType::Ref(Box::from(lhs_type))),
-        OperationType::Sizeof => Ok(DataType::Int),
-        OperationType::Assign => Ok(DataType::Null),
+        OperationType::MutRef => Ok(DataType::MutRef(Box::from(lhs_type))),
+        OperationType::Sizeof => unreachable!(),
+        OperationType::Assign => Ok(lhs_type),
+        OperationType::AddAssign |
+        OperationType::SubtractAssign |
+        OperationType::MultiplyAssign |
+        OperationType::DivideAssign |
+        OperationType::ModuloAssign |
+        OperationType::BitAndAssign |
+        OperationType::BitOrAssign |
+        OperationType::BitXorAssign |
+        OperationType::ShiftLeftAssign |
+        OperationType::ShiftRightAssign => Ok(arithmetic_type(&lhs_type, &lhs_type)),
+    }
+}
+
+/// The type of item `index` of a tuple, or a ref to one, like `t.0`.
+pub fn tuple_item_type(data_type: &DataType, index: i32, token: Option<&Token>)
+    -> Result<DataType, Box<dyn Error>>
+{
+    let items = match data_type
+    {
+        DataType::Tuple(items) => items,
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type)
+            if matches!(ref_type.as_ref(), DataType::Tuple(_)) =>
+        {
+            return tuple_item_type(ref_type, index, token);
+        },
+
+        _ =>
+        {
+            return Err(CompilerError::new_optional_position(token, format!(
+                "Can not take item {} of type '{}', as it's not a tuple",
+                index, data_type)));
+        },
+    };
+
+    let item = if index >= 0 { items.get(index as usize) } else { None };
+    match item
+    {
+        Some(item) => Ok(item.clone()),
+        None =>
+        {
+            Err(CompilerError::new_optional_position(token, format!(
+                "Tuple '{}' has no item {}", data_type, index)))
+        },
+    }
+}
+
+/// Each literal in a tuple literal takes on the type of its item,
+/// so `(1, 2.0)` can be used as a `(u8, f32)`.
+fn coerce_tuple_literal(items: &[Expression], item_types: &[DataType],
+                        token: Option<&Token>)
+    -> Result<Option<Expression>, Box<dyn Error>>
+{
+    if items.len() != item_types.len() {
+        return Ok(None);
+    }
+
+    let mut coerced_items = Vec::new();
+    let mut has_changed = false;
+    for (item, item_type) in items.iter().zip(item_types)
+    {
+        match coerce_literal(item, item_type, item.token().or(token))?
+        {
+            Some(coerced_item) =>
+            {
+                coerced_items.push(coerced_item);
+                has_changed = true;
+            },
+            None => coerced_items.push(item.clone()),
+        }
+    }
+
+    if has_changed {
+        Ok(Some(Expression::TupleLiteral(coerced_items)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// An untyped literal used where a sized int or a float is expected,
+/// like the `1` in `x + 1` when `x` is a `u8`, takes on that type.
+/// Gives `None` when `expression` should be left as it is.
+pub fn coerce_literal(expression: &Expression, data_type: &DataType,
+                      token: Option<&Token>)
+    -> Result<Option<Expression>, Box<dyn Error>>
+{
+    let value = match (expression, data_type)
+    {
+        (Expression::IntLiteral(value), _) => *value as i64,
+        (Expression::FloatLiteral(value), _) => return coerce_float_literal(*value, data_type, token),
+        (Expression::TupleLiteral(items), DataType::Tuple(item_types)) =>
+            return coerce_tuple_literal(items, item_types, token),
+        _ => return Ok(None),
+    };
+
+    if data_type.float_size().is_some() {
+        return coerce_float_literal(value as f64, data_type, token);
+    }
+
+    let (size, is_signed) = match data_type.int_layout()
+    {
+        Some(_) if data_type == &DataType::Int => return Ok(None),
+        Some(layout) => layout,
+        None => return Ok(None),
+    };
+
+    let bits = size as u32 * 8;
+    let fits =
+        if is_signed { bits == 64 || value >> (bits - 1) == 0 || value >> (bits - 1) == -1 }
+        else { value >= 0 && (bits == 64 || value >> bits == 0) };
+    if !fits
+    {
+        return Err(CompilerError::new_optional_position(token, format!(
+            "Int literal '{}' does not fit in type '{}'", value, data_type)));
+    }
+
+    let mask = u64::MAX >> (64 - bits);
+    Ok(Some(Expression::TypedIntLiteral(value as u64 & mask, data_type.clone())))
+}
+
+fn coerce_float_literal(value: f64, data_type: &DataType, token: Option<&Token>)
+    -> Result<Option<Expression>, Box<dyn Error>>
+{
+    match data_type
+    {
+        DataType::F32 if (value as f32).is_infinite() =>
+        {
+            Err(CompilerError::new_optional_position(token, format!(
+                "Float literal '{:?}' does not fit in type '{}'", value, data_type)))
+        },
+
+        DataType::F32 | DataType::F64 =>
+            Ok(Some(Expression::TypedFloatLiteral(value, data_type.clone()))),
+
+        _ => Ok(None),
+    }
+}
+
+/// The type of an arithmetic operation. Floats and sized ints keep
+/// their type, so a `u8` and a `u8` give a `u8`. Anything else, like
+/// pointer arithmetic on refs, is worked out as an int.
+pub fn arithmetic_type(lhs_type: &DataType, rhs_type: &DataType) -> DataType
+{
+    if lhs_type.float_size().is_some() {
+        return lhs_type.clone();
+    }
+    if rhs_type.float_size().is_some() {
+        return rhs_type.clone();
+    }
+
+    match (lhs_type.int_layout(), rhs_type.int_layout())
+    {
+        (Some(_), _) if lhs_type != &DataType::Int => lhs_type.clone(),
+        (_, Some(_)) if rhs_type != &DataType::Int => rhs_type.clone(),
+        _ => DataType::Int,
     }
 }
 
 pub fn call_signature(scope: &mut Scope,
                       function_name: &str,
-                      call: &Call,
+                      module: ModuleId,
+                      argument_types: &[DataType],
                       type_variable: Option<(&DataType, &DataType)>,
                       return_type: &Option<DataType>)
     -> Result<String, Box<dyn Error>>
@@ -147,11 +335,9 @@ pub fn call_signature(scope: &mut Scope,
         }
     }
 
-    let mut signature = function_name.to_owned() + "_";
-    for argument in &call.arguments
-    {
-        let data_type = derive_data_type(&mut local_scope, argument)?;
-        signature += &data_type_signature(&data_type);
+    let mut signature = module_prefix(scope, module) + function_name + "_";
+    for argument_type in argument_types {
+        signature += &data_type_signature(argument_type);
     }
 
     match return_type
@@ -171,22 +357,114 @@ pub fn call_signature(scope: &mut Scope,
     }
 }
 
-fn derive_call_type(scope: &mut Scope, call: &Call)
-    -> Result<DataType, Box<dyn Error>>
+/// The type of the function value a call goes through, or `None` when
+/// it calls a function by name. A local holding a function is called
+/// in place of any function with the same name.
+pub fn indirect_callee_type(scope: &mut Scope, call: &Call)
+    -> Result<Option<DataType>, Box<dyn Error>>
 {
     match call.callable.as_ref()
     {
         Expression::Identifier(name) =>
         {
+            match scope.lookup_value(name.content())
+            {
+                Some((_, data_type @ DataType::Function(_, _))) => Ok(Some(data_type)),
+                _ => Ok(None),
+            }
+        },
+
+        callable => Ok(Some(derive_data_type(scope, callable)?)),
+    }
+}
+
+/// A call through a function held in a field, like `h.f(4)`, as a
+/// call on the value of the field.
+pub fn field_call(operation: &Operation, call: &Call) -> Call
+{
+    let field = Expression::Operation(Operation
+    {
+        operation_type: OperationType::Access,
+        lhs: operation.lhs.clone(),
+        rhs: Some(call.callable.clone()),
+        token: operation.token.clone(),
+    });
+
+    Call
+    {
+        callable: Box::from(field),
+        arguments: call.arguments.clone(),
+        type_variable: call.type_variable.clone(),
+    }
+}
+
+fn derive_call_type(scope: &mut Scope, call: &Call)
+    -> Result<DataType, Box<dyn Error>>
+{
+    match indirect_callee_type(scope, call)?
+    {
+        Some(DataType::Function(_, Some(return_type))) => Ok(*return_type),
+
+        Some(data_type @ DataType::Function(_, None)) =>
+        {
+            Err(CompilerError::new_optional_position(call.callable.token(), format!(
+                "Function of type '{}' does not return a value", data_type)))
+        },
+
+        Some(data_type) =>
+        {
+            Err(CompilerError::new_optional_position(call.callable.token(), format!(
+                "Can not call value of type '{}'", data_type)))
+        },
+
+        None =>
+        {
+            let name = match call.callable.as_ref()
+            {
+                Expression::Identifier(name) => name,
+                _ => panic!(),
+            };
+
             let (_, function) = find_function_for_call(scope, name, call)?;
             assert!(function.return_type.is_some());
 
             let mut local_scope = create_local_scope(scope, &function);
             Ok(resolve_type_aliases(&mut local_scope, function.return_type.unwrap()))
         },
+    }
+}
 
-        _ => panic!(),
+/// Find the function `function_ref` names, giving its signature and type.
+pub fn find_function_ref(scope: &mut Scope, function_ref: &FunctionRef)
+    -> Result<(String, DataType), Box<dyn Error>>
+{
+    let token = Some(&function_ref.name);
+    let mut params = Vec::new();
+    for param in &function_ref.params
+    {
+        let param = resolve_type_names(scope, param.clone(), token)?;
+        params.push(resolve_type_aliases(scope, param));
     }
+
+    let type_variable = match &function_ref.type_variable
+    {
+        Some(type_variable) =>
+        {
+            let type_variable = resolve_type_names(scope, type_variable.clone(), token)?;
+            Some(resolve_type_aliases(scope, type_variable))
+        },
+        None => None,
+    };
+
+    let (signature, function) = find_function(
+        scope, &function_ref.name, &params, &type_variable)?;
+    let return_type = function.return_type.clone().map(|return_type|
+    {
+        let mut local_scope = create_local_scope(scope, &function);
+        Box::from(resolve_type_aliases(&mut local_scope, return_type))
+    });
+
+    Ok((signature, DataType::Function(function.params, return_type)))
 }
 
 pub fn resolve_type_aliases(scope: &mut Scope,
@@ -210,9 +488,31 @@ pub fn resolve_type_aliases(scope: &mut Scope,
         DataType::Ref(ref_type) =>
             DataType::Ref(Box::from(resolve_type_aliases(scope, *ref_type))),
 
+        DataType::MutRef(ref_type) =>
+            DataType::MutRef(Box::from(resolve_type_aliases(scope, *ref_type))),
+
         DataType::Generic(generic_type, name) =>
             DataType::Generic(Box::from(resolve_type_aliases(scope, *generic_type)), name),
 
+        DataType::Tuple(items) =>
+        {
+            DataType::Tuple(items
+                .into_iter()
+                .map(|item| resolve_type_aliases(scope, item))
+                .collect())
+        },
+
+        DataType::Function(params, return_type) =>
+        {
+            let params = params
+                .into_iter()
+                .map(|param| resolve_type_aliases(scope, param))
+                .collect();
+            let return_type = return_type.map(|return_type|
+                Box::from(resolve_type_aliases(scope, *return_type)));
+            DataType::Function(params, return_type)
+        },
+
         other => other,
     }
 }
@@ -224,6 +524,10 @@ pub fn derive_data_type(scope: &mut Scope,
     let result: Result<_, Box<dyn Error>> = match expression
     {
         Expression::IntLiteral(_) => Ok(DataType::Int),
+        Expression::TypedIntLiteral(_, data_type) => Ok(data_type.clone()),
+        Expression::FloatLiteral(_) => Ok(DataType::F64),
+        Expression::TypedFloatLiteral(_, data_type) => Ok(data_type.clone()),
+        Expression::Cast(cast) => Ok(cast.data_type.clone()),
         Expression::BoolLiteral(_) => Ok(DataType::Bool),
         Expression::StringLiteral(_) => Ok(DataType::Ref(Box::from(DataType::Char))),
         Expression::CharLiteral(_) => Ok(DataType::Char),
@@ -242,16 +546,38 @@ pub fn derive_data_type(scope: &mut Scope,
             match scope.lookup_value(name.content())
             {
                 Some((_, data_type)) => Ok(data_type),
-                None => 
+                None =>
                 {
-                    Err(CompilerError::new(name, format!(
-                        "Could not find '{}'", name.content())))
+                    match find_constant(scope, name)?
+                    {
+                        Some(value) => derive_data_type(scope, &value),
+                        None =>
+                        {
+                            Err(CompilerError::new(name, format!(
+                                "Could not find '{}'", name.content())))
+                        },
+                    }
                 },
             }
         }
 
         Expression::InitializerList(initilizer_list) =>
-            Ok(initilizer_list.data_type.clone()),
+            resolve_type_names(scope, initilizer_list.data_type.clone(), expression.token()),
+
+        Expression::VariantLiteral(literal) =>
+            resolve_type_names(scope, literal.data_type.clone(), Some(&literal.variant)),
+
+        Expression::TupleLiteral(items) =>
+        {
+            let item_types = items
+                .iter()
+                .map(|item| derive_data_type(scope, item))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(DataType::Tuple(item_types))
+        },
+
+        Expression::FunctionRef(function_ref) =>
+            Ok(find_function_ref(scope, function_ref)?.1),
 
         Expression::ArrayLiteral(items) =>
         {
@@ -280,18 +606,21 @@ fn size_of_struct(scope: &Scope, name: &str)
     }
 
     let struct_ = struct_of_none.unwrap();
-    let mut total_size = 0;
-    for (_, (_, data_type)) in struct_ {
-        total_size += size_of(scope, &data_type)?;
+    let mut end_of_fields = 0;
+    for (field, _) in struct_.values()
+    {
+        if let IRLocation::Field(offset, size) = &field.location {
+            end_of_fields = end_of_fields.max(offset + size);
+        }
     }
 
-    Ok(total_size)
+    Ok(align_to(end_of_fields, align_of_struct(scope, name)?))
 }
 
-fn size_of_typed_struct(scope: &Scope,
-                        name: &str,
-                        argument_type: &DataType)
-    -> Result<usize, Box<CompilerError>>
+fn typed_struct_field_types(scope: &Scope,
+                            name: &str,
+                            argument_type: &DataType)
+    -> Result<Vec<DataType>, Box<CompilerError>>
 {
     let typed_struct_of_none = scope.lookup_typed_struct(name);
     if typed_struct_of_none.is_none()
@@ -301,23 +630,259 @@ fn size_of_typed_struct(scope: &Scope,
     }
 
     let typed_struct = typed_struct_of_none.unwrap();
+    let variable = typed_struct.variable;
+    Ok(typed_struct.fields
+        .into_iter()
+        .map(|(_, data_type)|
+        {
+            let is_varable_type = match &data_type
+            {
+                DataType::Struct(name) => name == &variable,
+                _ => false,
+            };
+
+            if is_varable_type { argument_type.clone() } else { data_type }
+        })
+        .collect())
+}
+
+fn size_of_typed_struct(scope: &Scope,
+                        name: &str,
+                        argument_type: &DataType)
+    -> Result<usize, Box<CompilerError>>
+{
     let mut total_size = 0;
-    for (_, data_type) in typed_struct.fields
+    let mut alignment = 1;
+    for data_type in typed_struct_field_types(scope, name, argument_type)?
+    {
+        let field_alignment = align_of(scope, &data_type)?;
+        total_size = align_to(total_size, field_alignment) + size_of(scope, &data_type)?;
+        alignment = alignment.max(field_alignment);
+    }
+
+    Ok(align_to(total_size, alignment))
+}
+
+/// Every enum value starts with an int tag, the index of the variant
+/// it holds. The values of the variant follow in the space after it.
+pub const ENUM_TAG_SIZE: usize = 4;
+
+fn substitute_type_variable(data_type: &DataType, variable: &str, argument: &DataType)
+    -> DataType
+{
+    let substitute_inner = |inner: &DataType|
+        Box::from(substitute_type_variable(inner, variable, argument));
+
+    match data_type
+    {
+        DataType::Struct(name) if name == variable => argument.clone(),
+        DataType::Array(item_type, size) => DataType::Array(substitute_inner(item_type), *size),
+        DataType::Ref(ref_type) => DataType::Ref(substitute_inner(ref_type)),
+        DataType::MutRef(ref_type) => DataType::MutRef(substitute_inner(ref_type)),
+        DataType::Generic(generic_type, name) =>
+            DataType::Generic(substitute_inner(generic_type), name.clone()),
+        DataType::Tuple(items) =>
+        {
+            DataType::Tuple(items
+                .iter()
+                .map(|item| substitute_type_variable(item, variable, argument))
+                .collect())
+        },
+        DataType::Function(params, return_type) =>
+        {
+            DataType::Function(params
+                .iter()
+                .map(|param| substitute_type_variable(param, variable, argument))
+                .collect(),
+                return_type.as_deref().map(substitute_inner))
+        },
+        other => other.clone(),
+    }
+}
+
+/// The variants of `data_type` and the types of the values they hold,
+/// or `None` if it's not an enum. For a generic enum, like `int Option`,
+/// the type variable is replaced by the type argument.
+pub fn enum_variants(scope: &Scope, data_type: &DataType)
+    -> Option<Vec<(String, Vec<DataType>)>>
+{
+    let (enum_type, argument) = match data_type
+    {
+        DataType::Struct(name) => (scope.lookup_enum(name)?, None),
+        DataType::Generic(argument, name) => (scope.lookup_enum(name)?, Some(argument)),
+        _ => return None,
+    };
+
+    match (&enum_type.variable, argument)
     {
-        let is_varable_type = match &data_type
+        (Some(variable), Some(argument)) =>
         {
-            DataType::Struct(name) => name == &typed_struct.variable,
-            _ => false,
-        };
+            Some(enum_type.variants
+                .iter()
+                .map(|(name, fields)|
+                {
+                    let fields = fields
+                        .iter()
+                        .map(|field| substitute_type_variable(field, variable, argument))
+                        .collect();
+                    (name.clone(), fields)
+                })
+                .collect())
+        },
+
+        _ => Some(enum_type.variants),
+    }
+}
+
+/// The offset of each value held by a variant, from the start of
+/// the enum. Every variant shares the same space after the tag.
+pub fn variant_field_offsets(scope: &Scope, fields: &[DataType])
+    -> Result<Vec<usize>, Box<CompilerError>>
+{
+    let mut offsets = Vec::new();
+    let mut last_offset = ENUM_TAG_SIZE;
+    for field in fields
+    {
+        last_offset = align_to(last_offset, align_of(scope, field)?);
+        offsets.push(last_offset);
+        last_offset += size_of(scope, field)?;
+    }
+
+    Ok(offsets)
+}
+
+fn align_of_enum(scope: &Scope, variants: &[(String, Vec<DataType>)])
+    -> Result<usize, Box<CompilerError>>
+{
+    let mut alignment = ENUM_TAG_SIZE;
+    for (_, fields) in variants
+    {
+        for field in fields {
+            alignment = alignment.max(align_of(scope, field)?);
+        }
+    }
 
-        if is_varable_type {
-            total_size += size_of(scope, argument_type)?;
-        } else {
-            total_size += size_of(scope, &data_type)?;
+    Ok(alignment)
+}
+
+/// The tag, followed by enough space for the largest variant.
+fn size_of_enum(scope: &Scope, variants: &[(String, Vec<DataType>)])
+    -> Result<usize, Box<CompilerError>>
+{
+    let mut end_of_variants = ENUM_TAG_SIZE;
+    for (_, fields) in variants
+    {
+        let offsets = variant_field_offsets(scope, fields)?;
+        if let (Some(offset), Some(field)) = (offsets.last(), fields.last()) {
+            end_of_variants = end_of_variants.max(offset + size_of(scope, field)?);
         }
     }
 
-    Ok(total_size)
+    Ok(align_to(end_of_variants, align_of_enum(scope, variants)?))
+}
+
+/// The offset of each item of a tuple, laid out like the fields
+/// of a struct.
+pub fn tuple_item_offsets(scope: &Scope, items: &[DataType])
+    -> Result<Vec<usize>, Box<CompilerError>>
+{
+    let mut offsets = Vec::new();
+    let mut last_offset = 0;
+    for item in items
+    {
+        last_offset = align_to(last_offset, align_of(scope, item)?);
+        offsets.push(last_offset);
+        last_offset += size_of(scope, item)?;
+    }
+
+    Ok(offsets)
+}
+
+fn size_of_tuple(scope: &Scope, items: &[DataType])
+    -> Result<usize, Box<CompilerError>>
+{
+    let offsets = tuple_item_offsets(scope, items)?;
+    let end_of_items = match (offsets.last(), items.last())
+    {
+        (Some(offset), Some(item)) => offset + size_of(scope, item)?,
+        _ => 0,
+    };
+
+    Ok(align_to(end_of_items, align_of(scope, &DataType::Tuple(items.to_vec()))?))
+}
+
+fn align_of_struct(scope: &Scope, name: &str)
+    -> Result<usize, Box<CompilerError>>
+{
+    let struct_of_none = scope.lookup_struct(name);
+    if struct_of_none.is_none()
+    {
+        return Err(CompilerError::new_no_position(format!(
+            "Could not find struct '{}'", name)));
+    }
+
+    let mut alignment = 1;
+    for (_, (_, data_type)) in struct_of_none.unwrap() {
+        alignment = alignment.max(align_of(scope, &data_type)?);
+    }
+
+    Ok(alignment)
+}
+
+/// Round `offset` up to the next multiple of `alignment`.
+pub fn align_to(offset: usize, alignment: usize) -> usize
+{
+    offset.next_multiple_of(alignment)
+}
+
+/// The alignment values of this type need in memory, so that fields
+/// can be read without crossing their natural boundary.
+pub fn align_of(scope: &Scope, data_type: &DataType)
+    -> Result<usize, Box<CompilerError>>
+{
+    Ok(match data_type
+    {
+        DataType::Struct(name) =>
+        {
+            match (scope.lookup_type_alias(name), enum_variants(scope, data_type))
+            {
+                (Some(alias), _) => align_of(scope, &alias)?,
+                (None, Some(variants)) => align_of_enum(scope, &variants)?,
+                (None, None) => align_of_struct(scope, name)?,
+            }
+        },
+
+        DataType::Array(item_type, _) => align_of(scope, item_type)?,
+
+        DataType::Generic(argument, name) =>
+        {
+            if let Some(variants) = enum_variants(scope, data_type) {
+                return align_of_enum(scope, &variants);
+            }
+
+            let mut alignment = 1;
+            for data_type in typed_struct_field_types(scope, name, argument)? {
+                alignment = alignment.max(align_of(scope, &data_type)?);
+            }
+            alignment
+        },
+
+        DataType::Tuple(items) =>
+        {
+            let mut alignment = 1;
+            for item in items {
+                alignment = alignment.max(align_of(scope, item)?);
+            }
+            alignment
+        },
+
+        // NOTE: Like C on x86, 64-bit ints and doubles only need
+        //       to be aligned to 4.
+        DataType::I64 | DataType::U64 | DataType::F64 => 4,
+
+        // NOTE: Primitive types are aligned to their own size.
+        _ => size_of(scope, data_type)?,
+    })
 }
 
 pub fn size_of(scope: &Scope, data_type: &DataType)
@@ -325,26 +890,53 @@ pub fn size_of(scope: &Scope, data_type: &DataType)
 {
     Ok(match data_type
     {
-        DataType::Null => 0,
-        DataType::Int => 4,
+        DataType::Any =>
+        {
+            return Err(CompilerError::new_no_position(
+                "Can not take the size of 'any'".to_owned()));
+        },
+
+        DataType::Int |
+        DataType::I8 | DataType::I16 | DataType::I64 |
+        DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 =>
+            data_type.int_layout().unwrap().0,
+
+        DataType::F32 | DataType::F64 =>
+            data_type.float_size().unwrap(),
+
         DataType::Char => 1,
         DataType::Bool => 1,
         DataType::Ref(_) => 4,
+        DataType::MutRef(_) => 4,
+        DataType::Function(_, _) => 4,
 
         DataType::Struct(name) =>
         {
-            match scope.lookup_type_alias(name)
+            match (scope.lookup_type_alias(name), enum_variants(scope, data_type))
             {
-                Some(alias) => size_of(scope, &alias)?,
-                None => size_of_struct(scope, name)?,
+                (Some(alias), _) => size_of(scope, &alias)?,
+                (None, Some(variants)) => size_of_enum(scope, &variants)?,
+                (None, None) => size_of_struct(scope, name)?,
             }
         },
 
         DataType::Array(item_type, size) => 
             size_of(scope, item_type)? * size,
 
+        // NOTE: These are all resolved by the optimizer.
+        DataType::ConstSizedArray(_, _) => panic!(),
+
         DataType::Generic(argument, name) =>
-            size_of_typed_struct(scope, name, &*argument)?,
+        {
+            match enum_variants(scope, data_type)
+            {
+                Some(variants) => size_of_enum(scope, &variants)?,
+                None => size_of_typed_struct(scope, name, argument)?,
+            }
+        },
+
+        DataType::Tuple(items) =>
+            size_of_tuple(scope, items)?,
     })
 }
 
@@ -353,29 +945,79 @@ fn data_type_signature(data_type: &DataType)
 {
     match data_type
     {
-        DataType::Null => "null".to_owned(),
+        DataType::Any => "any".to_owned(),
         DataType::Int => "int".to_owned(),
+        DataType::I8 | DataType::I16 | DataType::I64 |
+        DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64 |
+        DataType::F32 | DataType::F64 =>
+            data_type.to_string(),
         DataType::Char => "char".to_owned(),
         DataType::Bool => "bool".to_owned(),
-        DataType::Struct(token) => token.to_owned(),
+        DataType::Struct(name) => name.replace("::", "."),
 
         DataType::Array(data_type, size) =>
             format!("{}{}", data_type_signature(data_type), size),
 
+        DataType::ConstSizedArray(_, _) => panic!(),
+
         DataType::Ref(data_type) =>
             format!("ref{}", data_type_signature(data_type)),
 
+        DataType::MutRef(data_type) =>
+            format!("mutref{}", data_type_signature(data_type)),
+
         DataType::Generic(argument, token) =>
-            format!("{}of{}", data_type_signature(argument), token),
+            format!("{}of{}", data_type_signature(argument), token.replace("::", ".")),
+
+        DataType::Tuple(items) =>
+        {
+            let item_signatures = items
+                .iter()
+                .map(data_type_signature)
+                .collect::<String>();
+            format!("tuple{}{}", items.len(), item_signatures)
+        },
+
+        DataType::Function(params, return_type) =>
+        {
+            let param_signatures = params
+                .iter()
+                .map(data_type_signature)
+                .collect::<String>();
+            let return_signature = return_type
+                .as_deref()
+                .map(data_type_signature)
+                .unwrap_or_default();
+            format!("fun{}{}{}", params.len(), param_signatures, return_signature)
+        },
+    }
+}
+
+fn module_prefix(scope: &Scope, module: ModuleId)
+    -> String
+{
+    if module == ROOT_MODULE {
+        return String::default();
+    }
+
+    // NOTE: Two modules may be imported by the same name from different
+    //       directories, so the id is what keeps their symbols apart.
+    match scope.lookup_module(module)
+    {
+        Some(description) =>
+            format!("module{}.{}.", module, description.name.replace("::", ".")),
+        None => format!("module{}.", module),
     }
 }
 
-pub fn function_signature(function: &Function,
+pub fn function_signature(scope: &Scope,
+                          function: &Function,
                           params: &Vec<DataType>,
                           return_type: &Option<DataType>)
     -> String
 {
-    let mut signature = function.name.content().to_owned() + "_";
+    let mut signature = module_prefix(scope, function.module) +
+        function.name.content() + "_";
     for param in params {
         signature += &data_type_signature(param);
     }
@@ -398,14 +1040,25 @@ pub fn doas_type_exist(scope: &Scope, data_type: &DataType) -> bool
 {
     match data_type
     {
-        DataType::Struct(name) => scope.lookup_struct(name).is_some(),
+        DataType::Struct(name) =>
+            scope.lookup_struct(name).is_some() || scope.lookup_enum(name).is_some(),
         DataType::Array(array_type, _) => doas_type_exist(scope, array_type),
         DataType::Ref(ref_type) => doas_type_exist(scope, ref_type),
+        DataType::MutRef(ref_type) => doas_type_exist(scope, ref_type),
+        DataType::Tuple(items) => items.iter().all(|item| doas_type_exist(scope, item)),
+        DataType::Function(params, return_type) =>
+        {
+            params
+                .iter()
+                .chain(return_type.as_deref())
+                .all(|param| doas_type_exist(scope, param))
+        },
 
         DataType::Generic(generic_type, name) =>
         {
-            doas_type_exist(scope, generic_type) && 
-                scope.lookup_typed_struct(name).is_some()
+            doas_type_exist(scope, generic_type) && (
+                scope.lookup_typed_struct(name).is_some() ||
+                scope.lookup_enum(name).is_some())
         },
 
         _ => true,
@@ -421,3 +1074,145 @@ pub fn type_variable_name(type_variable: &DataType) -> &str
     }
 }
 
+
+/// The name a struct, enum or trait defined in `module` is known by inside
+/// the compiler. Types declared with the same name in more than one module
+/// get the module name added, like `shapes::Point`, and the module id as
+/// well when the module name alone doesn't tell them apart.
+pub fn type_key(scope: &Scope, name: &str, module: ModuleId) -> String
+{
+    let modules = scope.lookup_struct_modules(name);
+    if modules.len() < 2 {
+        return name.to_owned();
+    }
+
+    let module_name = |module| scope.lookup_module(module)
+        .map(|description| description.name)
+        .unwrap_or_default();
+
+    let is_module_name_unique = modules
+        .iter()
+        .filter(|(other, _)| module_name(*other) == module_name(module))
+        .count() == 1;
+
+    if is_module_name_unique {
+        format!("{}::{}", module_name(module), name)
+    } else {
+        format!("{}#{}::{}", module_name(module), module, name)
+    }
+}
+
+/// The module defining the struct, enum or trait known as `key`.
+pub fn type_module(scope: &Scope, key: &str) -> Option<ModuleId>
+{
+    let name = key.rsplit("::").next().unwrap();
+    scope.lookup_struct_modules(name)
+        .into_iter()
+        .map(|(module, _)| module)
+        .find(|module| type_key(scope, name, *module) == key)
+}
+
+/// Find the struct, enum or trait `name` refers to from the current module,
+/// giving its key. One defined in the module itself hides any it imports.
+/// Names that aren't a type, like type variables, are given back as is.
+pub fn resolve_type_name(scope: &Scope, name: &str, token: Option<&Token>)
+    -> Result<String, Box<dyn Error>>
+{
+    let modules = scope.lookup_struct_modules(name);
+    let current_module = scope.current_module();
+    if modules.iter().any(|(module, _)| *module == current_module) {
+        return Ok(type_key(scope, name, current_module));
+    }
+
+    let visible_modules = modules
+        .iter()
+        .filter(|(module, is_exported)| scope.is_visible(*module, *is_exported))
+        .collect::<Vec<_>>();
+
+    let module_name = |module| scope.lookup_module(module)
+        .map(|description| description.name)
+        .unwrap_or_default();
+
+    match (visible_modules.as_slice(), modules.first())
+    {
+        ([(module, _)], _) => Ok(type_key(scope, name, *module)),
+        ([(first, _), (second, _), ..], _) =>
+        {
+            Err(CompilerError::new_optional_position(token, format!(
+                "Type '{}' is ambiguous, it could be from module '{}' or '{}'",
+                name, module_name(*first), module_name(*second))))
+        },
+
+        ([], Some((hidden_module, _))) =>
+        {
+            let key = type_key(scope, name, *hidden_module);
+            let kind =
+                if scope.lookup_enum(&key).is_some() {
+                    "Enum"
+                } else if scope.lookup_trait(&key).is_some() {
+                    "Trait"
+                } else {
+                    "Struct"
+                };
+
+            Err(CompilerError::new_optional_position(token, format!(
+                "{} '{}' is not exported from module '{}'",
+                kind, name, module_name(*hidden_module))))
+        },
+
+        ([], None) => Ok(name.to_owned()),
+    }
+}
+
+/// Resolve the names of every struct, enum and trait in `data_type`, which
+/// was written in the current module.
+pub fn resolve_type_names(scope: &Scope,
+                          data_type: DataType,
+                          token: Option<&Token>)
+    -> Result<DataType, Box<dyn Error>>
+{
+    Ok(match data_type
+    {
+        DataType::Struct(name) =>
+            DataType::Struct(resolve_type_name(scope, &name, token)?),
+
+        DataType::Array(array_type, size) =>
+            DataType::Array(Box::from(resolve_type_names(scope, *array_type, token)?), size),
+
+        DataType::Ref(ref_type) =>
+            DataType::Ref(Box::from(resolve_type_names(scope, *ref_type, token)?)),
+
+        DataType::MutRef(ref_type) =>
+            DataType::MutRef(Box::from(resolve_type_names(scope, *ref_type, token)?)),
+
+        DataType::Generic(generic_type, name) =>
+        {
+            let generic_type = resolve_type_names(scope, *generic_type, token)?;
+            DataType::Generic(Box::from(generic_type), resolve_type_name(scope, &name, token)?)
+        },
+
+        DataType::Tuple(items) =>
+        {
+            DataType::Tuple(items
+                .into_iter()
+                .map(|item| resolve_type_names(scope, item, token))
+                .collect::<Result<_, _>>()?)
+        },
+
+        DataType::Function(params, return_type) =>
+        {
+            let params = params
+                .into_iter()
+                .map(|param| resolve_type_names(scope, param, token))
+                .collect::<Result<_, _>>()?;
+            let return_type = match return_type
+            {
+                Some(return_type) => Some(Box::from(resolve_type_names(scope, *return_type, token)?)),
+                None => None,
+            };
+            DataType::Function(params, return_type)
+        },
+
+        other => other,
+    })
+}
@@ -30,6 +30,16 @@ impl CompilerError
         })
     }
 
+    /// For expressions that may not have a token, like int literals.
+    pub fn new_optional_position(token: Option<&Token>, message: String) -> Box<Self>
+    {
+        Box::from(Self
+        {
+            token: token.cloned(),
+            message,
+        })
+    }
+
 }
 
 impl fmt::Display for CompilerError
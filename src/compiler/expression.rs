@@ -1,24 +1,40 @@
 use super::intermediate::IRGenorator;
 use super::intermediate::value::IRValue;
 use super::name_table::{Scope, TypedStructType};
-use super::data_type::{size_of, derive_data_type};
+use super::data_type::{size_of, align_of, align_to, derive_data_type, resolve_type_names};
+use super::data_type::{arithmetic_type, coerce_literal, resolve_type_aliases};
+use super::data_type::{enum_variants, variant_field_offsets, ENUM_TAG_SIZE};
+use super::data_type::{tuple_item_type, tuple_item_offsets};
+use super::data_type::{indirect_callee_type, field_call, find_function_ref};
 use super::function::{find_function_for_call, create_local_scope};
+use super::const_::find_constant;
 use super::error::CompilerError;
-use crate::tokenizer::Token;
-use crate::ast::{Expression, Operation, OperationType, Call, InitializerList};
-use crate::data_type::DataType;
+use crate::tokenizer::{Token, TokenType};
+use crate::ast::{Expression, Operation, OperationType, Call, Cast, InitializerList};
+use crate::ast::{VariantLiteral, FunctionRef};
+use crate::intermediate::IROperation;
+use crate::data_type::{DataType, DataTypeDescription};
 use std::rc::Rc;
 use std::collections::HashMap;
 use std::error::Error;
 
-fn compile_identifier(scope: &Scope,
+fn compile_identifier(gen: &mut IRGenorator, scope: &mut Scope,
                       name_token: &Token)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
     let name = name_token.content();
     let value_or_none = scope.lookup_value(name);
-    if value_or_none.is_none() {
-        panic!(); // TODO
+    if value_or_none.is_none()
+    {
+        return match find_constant(scope, name_token)?
+        {
+            Some(value) => compile_expression(gen, scope, &value),
+            None =>
+            {
+                Err(CompilerError::new(name_token, format!(
+                    "Could not find '{}'", name)))
+            },
+        };
     }
 
     let (value, _) = value_or_none.unwrap();
@@ -29,7 +45,9 @@ fn compile_initilizer_list(gen: &mut IRGenorator, scope: &mut Scope,
                            initilizer_list: &InitializerList)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let struct_or_none = match &initilizer_list.data_type
+    let token = initilizer_list.list.first().map(|(first_field, _)| first_field);
+    let data_type = resolve_type_names(scope, initilizer_list.data_type.clone(), token)?;
+    let struct_or_none = match &data_type
     {
         DataType::Struct(struct_name) =>
             scope.lookup_struct(&struct_name),
@@ -49,7 +67,7 @@ fn compile_initilizer_list(gen: &mut IRGenorator, scope: &mut Scope,
     assert!(struct_or_none.is_some());
 
     let struct_layout = struct_or_none.unwrap();
-    let struct_size = size_of(scope, &initilizer_list.data_type)?;
+    let struct_size = size_of(scope, &data_type)?;
     let field_count = initilizer_list.list.len();
     let compile_field = |gen: &mut IRGenorator, index: usize|
     {
@@ -65,6 +83,98 @@ fn compile_initilizer_list(gen: &mut IRGenorator, scope: &mut Scope,
     Ok(gen.emit_struct_data(struct_size, field_count, compile_field)?)
 }
 
+fn compile_variant_literal(gen: &mut IRGenorator, scope: &mut Scope,
+                           literal: &VariantLiteral)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    let variant_token = &literal.variant;
+    let data_type = resolve_type_names(scope, literal.data_type.clone(), Some(variant_token))?;
+    let data_type = resolve_type_aliases(scope, data_type);
+    let variants_or_none = enum_variants(scope, &data_type);
+    if variants_or_none.is_none()
+    {
+        return Err(CompilerError::new(variant_token, format!(
+            "Could not find enum '{}'", data_type)));
+    }
+
+    let variants = variants_or_none.unwrap();
+    let variant_name = variant_token.content();
+    let variant_or_none = variants
+        .iter()
+        .enumerate()
+        .find(|(_, (name, _))| name == variant_name);
+    if variant_or_none.is_none()
+    {
+        return Err(CompilerError::new(variant_token, format!(
+            "Could not find variant '{}' in enum '{}'", variant_name, data_type)));
+    }
+
+    let (tag, (_, fields)) = variant_or_none.unwrap();
+    if fields.len() != literal.arguments.len()
+    {
+        return Err(CompilerError::new(variant_token, format!(
+            "Variant '{}' holds {} values, got {}",
+            variant_name, fields.len(), literal.arguments.len())));
+    }
+
+    let mut arguments = Vec::new();
+    for (argument, field_type) in literal.arguments.iter().zip(fields)
+    {
+        let coerced_argument = coerce_literal(argument, field_type, Some(variant_token))?;
+        let argument = coerced_argument.unwrap_or(argument.clone());
+        let argument_type = derive_data_type(scope, &argument)?;
+        if &argument_type != field_type
+        {
+            return Err(CompilerError::new(argument.token().unwrap_or(variant_token), format!(
+                "Expected value of type '{}' in variant '{}', got '{}'",
+                field_type, variant_name, argument_type)));
+        }
+
+        arguments.push(argument);
+    }
+
+    let offsets = variant_field_offsets(scope, fields)?;
+    let enum_size = size_of(scope, &data_type)?;
+    let compile_field = |gen: &mut IRGenorator, index: usize|
+    {
+        // NOTE: The tag comes first, followed by the values.
+        if index == 0
+        {
+            let tag_field = gen.emit_struct_offset(0, ENUM_TAG_SIZE);
+            return Ok((tag_field, gen.emit_int(tag as i32)));
+        }
+
+        let field_type = &fields[index - 1];
+        let field = gen.emit_struct_offset(
+            offsets[index - 1] as i32, size_of(scope, field_type)?);
+        let value = compile_expression(gen, scope, &arguments[index - 1])?;
+        Ok((field, value))
+    };
+
+    gen.emit_struct_data(enum_size, fields.len() + 1, compile_field)
+}
+
+fn compile_tuple_literal(gen: &mut IRGenorator, scope: &mut Scope,
+                         items: &[Expression])
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    let item_types = items
+        .iter()
+        .map(|item| derive_data_type(scope, item))
+        .collect::<Result<Vec<_>, _>>()?;
+    let offsets = tuple_item_offsets(scope, &item_types)?;
+    let tuple_size = size_of(scope, &DataType::Tuple(item_types.clone()))?;
+    let compile_item = |gen: &mut IRGenorator, index: usize|
+    {
+        let item = gen.emit_struct_offset(
+            offsets[index] as i32, size_of(scope, &item_types[index])?);
+        let value = compile_expression(gen, scope, &items[index])?;
+        Ok((item, value))
+    };
+
+    gen.emit_struct_data(tuple_size, items.len(), compile_item)
+}
+
 fn compile_array_literal(gen: &mut IRGenorator, scope: &mut Scope,
                          array: &Vec<Expression>)
     -> Result<Rc<IRValue>, Box<dyn Error>>
@@ -82,49 +192,334 @@ fn compile_array_literal(gen: &mut IRGenorator, scope: &mut Scope,
     Ok(gen.emit_array_literal(item_count, compile_item, item_size)?)
 }
 
-fn compile_add(gen: &mut IRGenorator, scope: &mut Scope,
-               lhs: &Expression, rhs: &Expression)
+fn ir_operation(operation_type: &OperationType) -> IROperation
+{
+    match operation_type
+    {
+        OperationType::Add | OperationType::AddAssign => IROperation::Add,
+        OperationType::Subtract | OperationType::SubtractAssign => IROperation::Subtract,
+        OperationType::Multiply | OperationType::MultiplyAssign => IROperation::Multiply,
+        OperationType::Divide | OperationType::DivideAssign => IROperation::Divide,
+        OperationType::Modulo | OperationType::ModuloAssign => IROperation::Modulo,
+        OperationType::BitAnd | OperationType::BitAndAssign => IROperation::And,
+        OperationType::BitOr | OperationType::BitOrAssign => IROperation::Or,
+        OperationType::BitXor | OperationType::BitXorAssign => IROperation::Xor,
+        OperationType::ShiftLeft | OperationType::ShiftLeftAssign => IROperation::ShiftLeft,
+        OperationType::ShiftRight | OperationType::ShiftRightAssign => IROperation::ShiftRight,
+        OperationType::GreaterThan => IROperation::GreaterThan,
+        OperationType::LessThan => IROperation::LessThan,
+        OperationType::GreaterThanEquals => IROperation::GreaterThanEquals,
+        OperationType::LessThanEquals => IROperation::LessThanEquals,
+        OperationType::Equals => IROperation::Equals,
+        OperationType::NotEquals => IROperation::NotEquals,
+        _ => panic!(),
+    }
+}
+
+/// Give an untyped int literal on either side the type of the other.
+fn coerce_operands(scope: &mut Scope, lhs: &Expression, rhs: &Expression)
+    -> Result<(Option<Expression>, Option<Expression>), Box<dyn Error>>
+{
+    let lhs_type = derive_data_type(scope, lhs)?;
+    let rhs_type = derive_data_type(scope, rhs)?;
+    Ok((coerce_literal(lhs, &rhs_type, rhs.token())?,
+        coerce_literal(rhs, &lhs_type, lhs.token())?))
+}
+
+fn compile_arithmetic_operation(gen: &mut IRGenorator, scope: &mut Scope,
+                                operation_type: &OperationType,
+                                lhs: &Expression, rhs: &Expression,
+                                token: &Token)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
+    let (coerced_lhs, coerced_rhs) = coerce_operands(scope, lhs, rhs)?;
+    let lhs = coerced_lhs.as_ref().unwrap_or(lhs);
+    let rhs = coerced_rhs.as_ref().unwrap_or(rhs);
+
+    // NOTE: Floats and sized ints can only be used with the same type,
+    //       anything else is worked out as an int.
+    let lhs_type = derive_data_type(scope, lhs)?;
+    let rhs_type = derive_data_type(scope, rhs)?;
+    let data_type = arithmetic_type(&lhs_type, &rhs_type);
+    if data_type != DataType::Int && (lhs_type != data_type || rhs_type != data_type)
+    {
+        return Err(CompilerError::new(token, format!(
+            "Can not use arithmetic with types '{}' and '{}'", lhs_type, rhs_type)));
+    }
+
+    let operation = ir_operation(operation_type);
+    if data_type.float_size().is_some() {
+        check_float_operation(&operation, &data_type, token)?;
+    }
+
     let lhs_value = compile_expression(gen, scope, lhs)?;
     let rhs_value = compile_expression(gen, scope, rhs)?;
-    Ok(gen.add(lhs_value, rhs_value))
+    Ok(typed_operation(gen, lhs_value, rhs_value, operation, &data_type))
+}
+
+/// Do `operation` on two ints or two floats of type `data_type`.
+fn typed_operation(gen: &mut IRGenorator,
+                   lhs: Rc<IRValue>, rhs: Rc<IRValue>,
+                   operation: IROperation,
+                   data_type: &DataType)
+    -> Rc<IRValue>
+{
+    match (data_type.float_size(), data_type.int_layout())
+    {
+        (Some(size), _) => gen.float_operation(lhs, rhs, operation, size),
+        (_, Some((size, is_signed))) => gen.int_operation(lhs, rhs, operation, size, is_signed),
+        _ => panic!(),
+    }
+}
+
+/// Floats only have the four basic arithmetic operations.
+fn check_float_operation(operation: &IROperation, data_type: &DataType,
+                         token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    match operation
+    {
+        IROperation::Add | IROperation::Subtract |
+        IROperation::Multiply | IROperation::Divide => Ok(()),
+
+        _ =>
+        {
+            Err(CompilerError::new(token, format!(
+                "Only '+', '-', '*' and '/' can be used with type '{}'", data_type)))
+        },
+    }
 }
 
-fn compile_subtract(gen: &mut IRGenorator, scope: &mut Scope,
-                    lhs: &Expression, rhs: &Expression)
+fn compile_bit_not(gen: &mut IRGenorator, scope: &mut Scope,
+                   lhs: &Expression, token: &Token)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let lhs_value = compile_expression(gen, scope, lhs)?;
-    let rhs_value = compile_expression(gen, scope, rhs)?;
-    Ok(gen.subtract(lhs_value, rhs_value))
+    let data_type = derive_data_type(scope, lhs)?;
+    let (size, is_signed) = match data_type.int_layout()
+    {
+        Some(layout) => layout,
+        None =>
+        {
+            return Err(CompilerError::new(token, format!(
+                "Can not bitwise not values of type '{}'", data_type)));
+        },
+    };
+
+    let value = compile_expression(gen, scope, lhs)?;
+    Ok(gen.int_bit_not(value, size, is_signed))
 }
 
-fn compile_multiply(gen: &mut IRGenorator, scope: &mut Scope,
-                    lhs: &Expression, rhs: &Expression)
+fn compile_negate(gen: &mut IRGenorator, scope: &mut Scope,
+                  lhs: &Expression, token: &Token)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let lhs_value = compile_expression(gen, scope, lhs)?;
-    let rhs_value = compile_expression(gen, scope, rhs)?;
-    Ok(gen.mul(lhs_value, rhs_value))
+    let data_type = derive_data_type(scope, lhs)?;
+    if let Some(size) = data_type.float_size()
+    {
+        let value = compile_expression(gen, scope, lhs)?;
+        return Ok(gen.float_negate(value, size));
+    }
+
+    let (size, _) = match data_type.int_layout()
+    {
+        Some(layout) => layout,
+        None =>
+        {
+            return Err(CompilerError::new(token, format!(
+                "Can not negate values of type '{}'", data_type)));
+        },
+    };
+
+    let value = compile_expression(gen, scope, lhs)?;
+    Ok(gen.int_negate(value, size))
 }
 
-fn compile_greater_than(gen: &mut IRGenorator, scope: &mut Scope,
-                        lhs: &Expression, rhs: &Expression)
+/// Convert between ints, floats, chars and bools. Ints are sign extended
+/// when they're signed, and zero extended otherwise. Floats are rounded
+/// towards zero when they become ints.
+fn compile_cast(gen: &mut IRGenorator, scope: &mut Scope, cast: &Cast)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let lhs_value = compile_expression(gen, scope, lhs)?;
-    let rhs_value = compile_expression(gen, scope, rhs)?;
-    Ok(gen.greater_than(lhs_value, rhs_value))
+    let cast_layout = |data_type: &DataType| match data_type
+    {
+        DataType::Char | DataType::Bool => Some((1, false)),
+        _ => data_type.int_layout(),
+    };
+
+    // NOTE: Bools have to be either 0 or 1, so nothing can be cast to one.
+    let from_type = derive_data_type(scope, &cast.value)?;
+    let from_layout = cast_layout(&from_type);
+    let to_layout = match &cast.data_type
+    {
+        DataType::Bool => None,
+        data_type => cast_layout(data_type),
+    };
+
+    let from_float_size = from_type.float_size();
+    let to_float_size = cast.data_type.float_size();
+    let is_valid =
+        (from_layout.is_some() || from_float_size.is_some()) &&
+        (to_layout.is_some() || to_float_size.is_some());
+    if !is_valid
+    {
+        return Err(CompilerError::new(&cast.token, format!(
+            "Can not cast value of type '{}' to '{}'", from_type, cast.data_type)));
+    }
+
+    let value = compile_expression(gen, scope, &cast.value)?;
+    Ok(match (from_layout, from_float_size, to_layout, to_float_size)
+    {
+        (Some((_, is_signed)), _, Some((to_size, _)), _) =>
+            gen.convert_int(value, to_size, is_signed),
+        (Some((_, is_signed)), _, _, Some(to_size)) =>
+            gen.int_to_float(value, to_size, is_signed),
+        (_, Some(_), Some((to_size, is_signed)), _) =>
+            gen.float_to_int(value, to_size, is_signed),
+        (_, Some(_), _, Some(to_size)) =>
+            gen.convert_float(value, to_size),
+        _ => panic!(),
+    })
 }
 
-fn compile_less_than(gen: &mut IRGenorator, scope: &mut Scope,
-                     lhs: &Expression, rhs: &Expression)
+fn compile_struct_equals(gen: &mut IRGenorator, scope: &mut Scope,
+                         operation_type: &OperationType,
+                         lhs: &Expression, rhs: &Expression,
+                         token: &Token)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    // NOTE: Structs are compared with a user defined
+    //       `equals(lhs, rhs) -> bool` overload.
+    let equals_call = Expression::Call(Call
+    {
+        callable: Box::from(Expression::Identifier(
+            token.with_content(TokenType::Identifier, "equals"))),
+        arguments: vec![lhs.clone(), rhs.clone()],
+        type_variable: None,
+    });
+
+    let result_type = derive_data_type(scope, &equals_call)?;
+    if result_type != DataType::Bool
+    {
+        return Err(CompilerError::new(token, format!(
+            "Expected 'equals' to return 'bool', got '{}'", result_type)));
+    }
+
+    let result = compile_expression(gen, scope, &equals_call)?;
+    match operation_type
+    {
+        OperationType::Equals => Ok(result),
+        _ => 
+        {
+            let false_value = gen.emit_bool(false);
+            Ok(gen.equals(result, false_value))
+        },
+    }
+}
+
+fn compile_comparison(gen: &mut IRGenorator, scope: &mut Scope,
+                      operation_type: &OperationType,
+                      lhs: &Expression, rhs: &Expression,
+                      token: &Token)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
+    let (coerced_lhs, coerced_rhs) = coerce_operands(scope, lhs, rhs)?;
+    let lhs = coerced_lhs.as_ref().unwrap_or(lhs);
+    let rhs = coerced_rhs.as_ref().unwrap_or(rhs);
+
+    let lhs_type = derive_data_type(scope, lhs)?;
+    let rhs_type = derive_data_type(scope, rhs)?;
+    let is_equality =
+        *operation_type == OperationType::Equals ||
+        *operation_type == OperationType::NotEquals;
+
+    // NOTE: References can be compared to addresses, like `mem > 0`,
+    //       and to each other whether or not they're mutable.
+    let types_match = match (&lhs_type, &rhs_type)
+    {
+        (DataType::Ref(_) | DataType::MutRef(_), DataType::Int) |
+        (DataType::Int, DataType::Ref(_) | DataType::MutRef(_)) => true,
+
+        (DataType::Ref(lhs_ref_type) | DataType::MutRef(lhs_ref_type),
+         DataType::Ref(rhs_ref_type) | DataType::MutRef(rhs_ref_type)) =>
+            lhs_ref_type == rhs_ref_type,
+
+        (lhs_type, rhs_type) => lhs_type == rhs_type,
+    };
+
+    if !types_match
+    {
+        return Err(CompilerError::new(token, format!(
+            "Can not compare type '{}' with type '{}'", lhs_type, rhs_type)));
+    }
+
+    // NOTE: String literals are only an address into the data section,
+    //       comparing them wouldn't compare their contents.
+    if matches!(lhs, Expression::StringLiteral(_)) || matches!(rhs, Expression::StringLiteral(_))
+    {
+        return Err(CompilerError::new(token,
+            "Can not compare string literals".to_owned()));
+    }
+
+    // NOTE: References to structs are equal when the values they point to are,
+    //       the same as comparing the structs themselves.
+    let is_struct_ref = |data_type: &DataType| match data_type
+    {
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) =>
+            matches!(ref_type.as_ref(), DataType::Struct(_) | DataType::Generic(_, _)),
+        _ => false,
+    };
+
+    if is_equality && is_struct_ref(&lhs_type) && is_struct_ref(&rhs_type)
+    {
+        let deref = |expression: &Expression| Expression::Operation(Operation
+        {
+            operation_type: OperationType::Deref,
+            lhs: Box::from(expression.clone()),
+            rhs: None,
+            token: token.clone(),
+        });
+
+        return compile_struct_equals(gen, scope, operation_type,
+            &deref(lhs), &deref(rhs), token);
+    }
+
+    match &lhs_type
+    {
+        DataType::Struct(_) | DataType::Generic(_, _) if is_equality =>
+            return compile_struct_equals(gen, scope, operation_type, lhs, rhs, token),
+
+        DataType::Char | DataType::Bool |
+        DataType::Ref(_) | DataType::MutRef(_) => {},
+        DataType::Function(_, _) if is_equality => {},
+
+        data_type if data_type.int_layout().is_some() => {},
+        data_type if data_type.float_size().is_some() => {},
+
+        _ =>
+        {
+            return Err(CompilerError::new(token, format!(
+                "Can not compare values of type '{}'", lhs_type)));
+        },
+    }
+
     let lhs_value = compile_expression(gen, scope, lhs)?;
     let rhs_value = compile_expression(gen, scope, rhs)?;
-    Ok(gen.less_than(lhs_value, rhs_value))
+    if lhs_type.int_layout().is_some() || lhs_type.float_size().is_some()
+    {
+        return Ok(typed_operation(gen, lhs_value, rhs_value,
+            ir_operation(operation_type), &lhs_type));
+    }
+
+    Ok(match operation_type
+    {
+        OperationType::GreaterThan => gen.greater_than(lhs_value, rhs_value),
+        OperationType::LessThan => gen.less_than(lhs_value, rhs_value),
+        OperationType::GreaterThanEquals => gen.greater_than_equals(lhs_value, rhs_value),
+        OperationType::LessThanEquals => gen.less_than_equals(lhs_value, rhs_value),
+        OperationType::Equals => gen.equals(lhs_value, rhs_value),
+        OperationType::NotEquals => gen.not_equals(lhs_value, rhs_value),
+        _ => panic!(),
+    })
 }
 
 fn layout_for_typed_struct(gen: &mut IRGenorator,
@@ -146,9 +541,10 @@ fn layout_for_typed_struct(gen: &mut IRGenorator,
 
         let data_type = if is_varable_type { argument } else { field };
         let size = size_of(scope, data_type)?;
-        let value = gen.emit_struct_offset(last_offset, size);
+        last_offset = align_to(last_offset, align_of(scope, data_type)?);
+        let value = gen.emit_struct_offset(last_offset as i32, size);
         layout.insert(name.to_owned(), (value, data_type.clone()));
-        last_offset += size as i32;
+        last_offset += size;
     }
 
     Ok(layout)
@@ -156,7 +552,7 @@ fn layout_for_typed_struct(gen: &mut IRGenorator,
 
 fn field_of(gen: &mut IRGenorator, scope: &mut Scope,
             struct_type: &DataType, field_name_token: &Token)
-    -> Result<Rc<IRValue>, Box<dyn Error>>
+    -> Result<(Rc<IRValue>, DataType), Box<dyn Error>>
 {
     match struct_type
     {
@@ -180,8 +576,7 @@ fn field_of(gen: &mut IRGenorator, scope: &mut Scope,
                     field_name, struct_name)));
             }
 
-            let (field, _) = field_or_none.unwrap();
-            Ok(field.clone())
+            Ok(field_or_none.unwrap().clone())
         },
 
         DataType::Generic(argument, struct_name) =>
@@ -206,18 +601,21 @@ fn field_of(gen: &mut IRGenorator, scope: &mut Scope,
                     field_name, struct_name)));
             }
 
-            let (field, _) = field_or_none.unwrap();
-            Ok(field.clone())
+            Ok(field_or_none.unwrap().clone())
         },
 
         _ => panic!(),
     }
 }
 
-fn compile_access(gen: &mut IRGenorator, scope: &mut Scope,
-                  lhs: &Expression, rhs: &Expression)
-    -> Result<Rc<IRValue>, Box<dyn Error>>
+fn compile_field_ref(gen: &mut IRGenorator, scope: &mut Scope,
+                     lhs: &Expression, rhs: &Expression)
+    -> Result<(Rc<IRValue>, DataType), Box<dyn Error>>
 {
+    if let Expression::IntLiteral(index) = rhs {
+        return compile_tuple_item_ref(gen, scope, lhs, *index);
+    }
+
     let field_name = match rhs
     {
         Expression::Identifier(field_name) => field_name,
@@ -225,43 +623,80 @@ fn compile_access(gen: &mut IRGenorator, scope: &mut Scope,
     };
 
     let lhs_data_type = derive_data_type(scope, lhs)?;
-    let lhs_value = compile_expression(gen, scope, lhs)?;
-    match lhs_data_type
+    let struct_type = match &lhs_data_type
     {
-        DataType::Struct(_) | DataType::Generic(_, _) =>
-        {
-            let field = field_of(gen, scope, &lhs_data_type, field_name)?;
-            let lhs_ref = gen.ref_of(lhs_value);
-            Ok(gen.access(lhs_ref, field))
-        },
+        DataType::Struct(_) | DataType::Generic(_, _) => lhs_data_type.clone(),
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) => *ref_type.clone(),
+        _ => panic!(),
+    };
 
-        DataType::Ref(ref_type) =>
+    let lhs_ref = match lhs_data_type
+    {
+        DataType::Ref(_) | DataType::MutRef(_) => compile_expression(gen, scope, lhs)?,
+        _ => compile_ref(gen, scope, lhs)?,
+    };
+
+    let (field, field_type) = field_of(gen, scope, &struct_type, field_name)?;
+    Ok((gen.field_ref(lhs_ref, field), field_type))
+}
+
+fn compile_tuple_item_ref(gen: &mut IRGenorator, scope: &mut Scope,
+                          lhs: &Expression, index: i32)
+    -> Result<(Rc<IRValue>, DataType), Box<dyn Error>>
+{
+    let lhs_data_type = derive_data_type(scope, lhs)?;
+    let item_type = tuple_item_type(&lhs_data_type, index, lhs.token())?;
+    let (lhs_ref, items) = match lhs_data_type
+    {
+        DataType::Tuple(items) => (compile_ref(gen, scope, lhs)?, items),
+
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) =>
         {
-            let field = field_of(gen, scope, &*ref_type, field_name)?;
-            Ok(gen.access(lhs_value, field))
+            match *ref_type
+            {
+                DataType::Tuple(items) => (compile_expression(gen, scope, lhs)?, items),
+                _ => panic!(),
+            }
         },
 
         _ => panic!(),
-    }
+    };
+
+    let offsets = tuple_item_offsets(scope, &items)?;
+    let item = gen.emit_struct_offset(
+        offsets[index as usize] as i32, size_of(scope, &item_type)?);
+    Ok((gen.field_ref(lhs_ref, item), item_type))
 }
 
-fn compile_indexed(gen: &mut IRGenorator, scope: &mut Scope,
-                   lhs: &Expression, rhs: &Expression)
+fn compile_access(gen: &mut IRGenorator, scope: &mut Scope,
+                  operation: &Operation)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let lhs_value = compile_expression(gen, scope, lhs)?;
-    let rhs_value = compile_expression(gen, scope, rhs)?;
-    
-    let (lhs_ref, item_type) = match derive_data_type(scope, &lhs)?
+    let (lhs, rhs) = (&operation.lhs, operation.rhs.as_ref().unwrap());
+    if let Expression::Call(call) = rhs.as_ref() {
+        return compile_call(gen, scope, &field_call(operation, call));
+    }
+
+    let (field_ref, field_type) = compile_field_ref(gen, scope, lhs, rhs)?;
+    let field_size = size_of(scope, &field_type)?;
+    Ok(gen.deref(field_ref, field_size))
+}
+
+fn compile_indexed_ref(gen: &mut IRGenorator, scope: &mut Scope,
+                       lhs: &Expression, rhs: &Expression)
+    -> Result<(Rc<IRValue>, DataType), Box<dyn Error>>
+{
+    let (lhs_ref, item_type) = match derive_data_type(scope, lhs)?
     {
         DataType::Array(item_type, _) =>
-            (gen.ref_of(lhs_value), item_type),
+            (compile_ref(gen, scope, lhs)?, item_type),
 
-        DataType::Ref(item_type) =>
-            (lhs_value, item_type),
+        DataType::Ref(item_type) | DataType::MutRef(item_type) =>
+            (compile_expression(gen, scope, lhs)?, item_type),
 
         _ => panic!(),
     };
+    let rhs_value = compile_expression(gen, scope, rhs)?;
 
     let item_size = size_of(scope, &item_type)?;
     let address =
@@ -276,15 +711,70 @@ fn compile_indexed(gen: &mut IRGenorator, scope: &mut Scope,
             gen.add(lhs_ref, offset)
         };
 
+    Ok((address, *item_type))
+}
+
+fn compile_indexed(gen: &mut IRGenorator, scope: &mut Scope,
+                   lhs: &Expression, rhs: &Expression)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    let (address, item_type) = compile_indexed_ref(gen, scope, lhs, rhs)?;
+    let item_size = size_of(scope, &item_type)?;
     Ok(gen.deref(address, item_size))
 }
 
+/// Find the address of `expression`. Fields and array items are
+/// referenced in place, so writing through the result updates them.
 fn compile_ref(gen: &mut IRGenorator, scope: &mut Scope,
-               lhs: &Expression)
+               expression: &Expression)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let value = compile_expression(gen, scope, lhs)?;
-    Ok(gen.ref_of(value))
+    match expression
+    {
+        Expression::Operation(operation) =>
+        {
+            match operation.operation_type
+            {
+                // NOTE: A call through a field, like `h.f(4)`, gives a
+                //       value, not the field.
+                OperationType::Access if !matches!(operation.rhs.as_deref(), Some(Expression::Call(_))) =>
+                {
+                    let (field_ref, _) = compile_field_ref(gen, scope,
+                        &operation.lhs, operation.rhs.as_ref().unwrap())?;
+                    Ok(field_ref)
+                },
+
+                OperationType::Indexed =>
+                {
+                    let (item_ref, _) = compile_indexed_ref(gen, scope,
+                        &operation.lhs, operation.rhs.as_ref().unwrap())?;
+                    Ok(item_ref)
+                },
+
+                OperationType::Deref => compile_expression(gen, scope, &operation.lhs),
+
+                _ =>
+                {
+                    let value = compile_expression(gen, scope, expression)?;
+                    Ok(gen.ref_of(value))
+                },
+            }
+        },
+
+        _ =>
+        {
+            let value = compile_expression(gen, scope, expression)?;
+            Ok(gen.ref_of(value))
+        },
+    }
+}
+
+fn compile_mut_ref(gen: &mut IRGenorator, scope: &mut Scope,
+                   expression: &Expression)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    check_mutable(scope, expression)?;
+    compile_ref(gen, scope, expression)
 }
 
 fn compile_deref(gen: &mut IRGenorator, scope: &mut Scope,
@@ -295,7 +785,7 @@ fn compile_deref(gen: &mut IRGenorator, scope: &mut Scope,
     let data_type = derive_data_type(scope, lhs)?;
     let size = match data_type
     {
-        DataType::Ref(ref_type) => size_of(scope, &*ref_type)?,
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) => size_of(scope, &*ref_type)?,
         _ => panic!(),
     };
 
@@ -306,29 +796,227 @@ fn compile_sizeof(gen: &mut IRGenorator, scope: &mut Scope,
                  lhs: &Expression)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
-    let data_type = derive_data_type(scope, lhs)?;
+    // NOTE: `sizeof T` may name a type variable instead of a value.
+    let type_alias = match lhs
+    {
+        Expression::Identifier(name) if scope.lookup_value(name.content()).is_none() =>
+            scope.lookup_type_alias(name.content()),
+        _ => None,
+    };
+
+    let data_type = match type_alias
+    {
+        Some(data_type) => data_type,
+        None => derive_data_type(scope, lhs)?,
+    };
+
     let size = size_of(scope, &data_type)?;
     Ok(gen.emit_int(size as i32))
 }
 
+/// Check `expression` is a bool, pointing at the operator `token`
+/// when the expression has no token of its own, like a literal.
+fn check_is_bool(scope: &mut Scope, expression: &Expression, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    let data_type = derive_data_type(scope, expression)?;
+    if data_type != DataType::Bool
+    {
+        return Err(CompilerError::new_optional_position(expression.token().or(Some(token)), format!(
+            "Expected type 'bool', got '{}'", data_type)));
+    }
+
+    Ok(())
+}
+
+fn compile_logical_operation(gen: &mut IRGenorator, scope: &mut Scope,
+                             operation_type: &OperationType,
+                             lhs: &Expression, rhs: &Expression, token: &Token)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    check_is_bool(scope, lhs, token)?;
+    check_is_bool(scope, rhs, token)?;
+
+    let lhs_value = compile_expression(gen, scope, lhs)?;
+    let compile_rhs = |gen: &mut IRGenorator| compile_expression(gen, scope, rhs);
+    match operation_type
+    {
+        OperationType::And => gen.and(lhs_value, compile_rhs),
+        OperationType::Or => gen.or(lhs_value, compile_rhs),
+        _ => panic!(),
+    }
+}
+
+fn compile_not(gen: &mut IRGenorator, scope: &mut Scope,
+               lhs: &Expression, token: &Token)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    check_is_bool(scope, lhs, token)?;
+    let value = compile_expression(gen, scope, lhs)?;
+    Ok(gen.not(value))
+}
+
+fn check_assignable(lhs: &Expression, rhs: &Expression)
+    -> Result<(), Box<dyn Error>>
+{
+    let is_assignable = match lhs
+    {
+        Expression::Identifier(_) => true,
+        Expression::Operation(operation) =>
+        {
+            matches!(operation.operation_type,
+                OperationType::Access | OperationType::Indexed | OperationType::Deref)
+        },
+        _ => false,
+    };
+
+    if !is_assignable
+    {
+        let token = lhs.token().or(rhs.token());
+        return Err(CompilerError::new_optional_position(token,
+            "Can not assign to this expression".to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Check the value `expression` refers to is allowed to change. It has
+/// to be a mutable variable, or be reached through a `ref mut`.
+fn check_mutable(scope: &mut Scope, expression: &Expression)
+    -> Result<(), Box<dyn Error>>
+{
+    let operation = match expression
+    {
+        Expression::Identifier(name) =>
+        {
+            if !scope.is_value_mutable(name.content())
+            {
+                return Err(CompilerError::new(name, format!(
+                    "Can not change immutable variable '{}'", name.content())));
+            }
+
+            return Ok(());
+        },
+
+        Expression::Operation(operation) => operation,
+
+        // NOTE: Temporary values can always be changed.
+        _ => return Ok(()),
+    };
+
+    match operation.operation_type
+    {
+        OperationType::Access | OperationType::Indexed | OperationType::Deref =>
+        {
+            match derive_data_type(scope, &operation.lhs)?
+            {
+                DataType::MutRef(_) => Ok(()),
+
+                DataType::Ref(_) =>
+                {
+                    Err(CompilerError::new_optional_position(expression.token(),
+                        "Can not change a value through a non-mutable reference".to_owned()))
+                },
+
+                _ => check_mutable(scope, &operation.lhs),
+            }
+        },
+
+        _ => Ok(()),
+    }
+}
+
 fn compile_assign(gen: &mut IRGenorator, scope: &mut Scope,
-                  lhs: &Expression, rhs: &Expression)
+                  lhs: &Expression, rhs: &Expression, token: &Token)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
     let to_data_type = derive_data_type(scope, lhs)?;
+    let coerced_rhs = coerce_literal(rhs, &to_data_type, lhs.token())?;
+    let rhs = coerced_rhs.as_ref().unwrap_or(rhs);
+
     let value_data_type = derive_data_type(scope, rhs)?;
-    if to_data_type != value_data_type
+    if !to_data_type.accepts(&value_data_type)
     {
-        // FIXME: What happens if we don't have a token?
-        return Err(CompilerError::new(rhs.token().unwrap(), format!(
+        return Err(CompilerError::new(token, format!(
             "Can not assign value of type '{:?}' to type '{:?}'",
             value_data_type, to_data_type)));
     }
 
-    let to = compile_expression(gen, scope, lhs)?;
-    let value = compile_expression(gen, scope, rhs)?;
-    gen.mov(to, value);
-    Ok(gen.emit_null())
+    // NOTE: Assignment results in the assigned value, so that
+    //       `a = b = c` works.
+    check_assignable(lhs, rhs)?;
+    check_mutable(scope, lhs)?;
+    match lhs
+    {
+        Expression::Identifier(_) =>
+        {
+            let to = compile_expression(gen, scope, lhs)?;
+            let value = compile_expression(gen, scope, rhs)?;
+            gen.mov(to.clone(), value);
+            Ok(to)
+        },
+
+        _ =>
+        {
+            let to_ref = compile_ref(gen, scope, lhs)?;
+            let value = compile_expression(gen, scope, rhs)?;
+            gen.store(to_ref, value.clone());
+            Ok(value)
+        },
+    }
+}
+
+fn compile_compound_assign(gen: &mut IRGenorator, scope: &mut Scope,
+                           operation_type: &OperationType,
+                           lhs: &Expression, rhs: &Expression,
+                           token: &Token)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    let to_data_type = derive_data_type(scope, lhs)?;
+    let coerced_rhs = coerce_literal(rhs, &to_data_type, lhs.token())?;
+    let rhs = coerced_rhs.as_ref().unwrap_or(rhs);
+
+    let value_data_type = derive_data_type(scope, rhs)?;
+    let is_number = to_data_type.int_layout().is_some() || to_data_type.float_size().is_some();
+    if !is_number || to_data_type != value_data_type
+    {
+        return Err(CompilerError::new(token, format!(
+            "Can not use compound assignment with types '{}' and '{}'",
+            to_data_type, value_data_type)));
+    }
+
+    let operation = ir_operation(operation_type);
+    if to_data_type.float_size().is_some() {
+        check_float_operation(&operation, &to_data_type, token)?;
+    }
+
+    check_assignable(lhs, rhs)?;
+    check_mutable(scope, lhs)?;
+    let size = size_of(scope, &to_data_type)?;
+
+    // NOTE: The left hand side is only compiled once, so things
+    //       like `self.table[index] += 1` don't evaluate twice.
+    match lhs
+    {
+        Expression::Identifier(_) =>
+        {
+            let to = compile_expression(gen, scope, lhs)?;
+            let value = compile_expression(gen, scope, rhs)?;
+            let result = typed_operation(gen, to.clone(), value, operation, &to_data_type);
+            gen.mov(to.clone(), result);
+            Ok(to)
+        },
+
+        _ =>
+        {
+            let to_ref = compile_ref(gen, scope, lhs)?;
+            let current_value = gen.deref(to_ref.clone(), size);
+            let value = compile_expression(gen, scope, rhs)?;
+            let result = typed_operation(gen, current_value, value, operation, &to_data_type);
+            gen.store(to_ref, result.clone());
+            Ok(result)
+        },
+    }
 }
 
 fn compile_operation(gen: &mut IRGenorator, scope: &mut Scope,
@@ -337,23 +1025,123 @@ fn compile_operation(gen: &mut IRGenorator, scope: &mut Scope,
 {
     match operation.operation_type
     {
-        OperationType::Add => compile_add(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
-        OperationType::Subtract => compile_subtract(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
-        OperationType::Multiply => compile_multiply(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
-        OperationType::GreaterThan => compile_greater_than(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
-        OperationType::LessThan => compile_less_than(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
-        OperationType::Access => compile_access(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
+        OperationType::Add |
+        OperationType::Subtract |
+        OperationType::Multiply |
+        OperationType::Divide |
+        OperationType::Modulo |
+        OperationType::BitAnd |
+        OperationType::BitOr |
+        OperationType::BitXor |
+        OperationType::ShiftLeft |
+        OperationType::ShiftRight =>
+            compile_arithmetic_operation(gen, scope, &operation.operation_type, &operation.lhs, operation.rhs.as_ref().unwrap(), &operation.token),
+        OperationType::GreaterThan |
+        OperationType::LessThan |
+        OperationType::GreaterThanEquals |
+        OperationType::LessThanEquals |
+        OperationType::Equals |
+        OperationType::NotEquals =>
+            compile_comparison(gen, scope, &operation.operation_type, &operation.lhs, &operation.rhs.as_ref().unwrap(), &operation.token),
+        OperationType::And |
+        OperationType::Or =>
+            compile_logical_operation(gen, scope, &operation.operation_type, &operation.lhs, operation.rhs.as_ref().unwrap(), &operation.token),
+        OperationType::Not => compile_not(gen, scope, &operation.lhs, &operation.token),
+        OperationType::BitNot => compile_bit_not(gen, scope, &operation.lhs, &operation.token),
+        OperationType::Negate => compile_negate(gen, scope, &operation.lhs, &operation.token),
+        OperationType::Access => compile_access(gen, scope, operation),
         OperationType::Indexed => compile_indexed(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
         OperationType::Ref => compile_ref(gen, scope, &operation.lhs),
+        OperationType::MutRef => compile_mut_ref(gen, scope, &operation.lhs),
         OperationType::Deref => compile_deref(gen, scope, &operation.lhs),
         OperationType::Sizeof => compile_sizeof(gen, scope, &operation.lhs),
-        OperationType::Assign => compile_assign(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap()),
+        OperationType::Assign => compile_assign(gen, scope, &operation.lhs, &operation.rhs.as_ref().unwrap(), &operation.token),
+        OperationType::AddAssign |
+        OperationType::SubtractAssign |
+        OperationType::MultiplyAssign |
+        OperationType::DivideAssign |
+        OperationType::ModuloAssign |
+        OperationType::BitAndAssign |
+        OperationType::BitOrAssign |
+        OperationType::BitXorAssign |
+        OperationType::ShiftLeftAssign |
+        OperationType::ShiftRightAssign =>
+            compile_compound_assign(gen, scope, &operation.operation_type, &operation.lhs, operation.rhs.as_ref().unwrap(), &operation.token),
+    }
+}
+
+fn compile_function_ref(gen: &mut IRGenorator, scope: &mut Scope,
+                        function_ref: &FunctionRef)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    let (signature, _) = find_function_ref(scope, function_ref)?;
+    Ok(gen.emit_function_ref(&signature))
+}
+
+/// Call a function value, checking the arguments against its type
+/// as there's no overload to pick.
+fn compile_indirect_call(gen: &mut IRGenorator, scope: &mut Scope,
+                         call: &Call, function_type: &DataType)
+    -> Result<Rc<IRValue>, Box<dyn Error>>
+{
+    // FIXME: What happens if we don't have a token?
+    let token = call.callable.token().or(call.arguments.iter().find_map(|argument| argument.token()));
+    let (params, return_type) = match function_type
+    {
+        DataType::Function(params, return_type) => (params, return_type),
+        _ =>
+        {
+            return Err(CompilerError::new_optional_position(token, format!(
+                "Can not call value of type '{}'", function_type)));
+        },
+    };
+
+    if params.len() != call.arguments.len()
+    {
+        return Err(CompilerError::new_optional_position(token, format!(
+            "Function of type '{}' takes {} arguments, got {}",
+            function_type, params.len(), call.arguments.len())));
+    }
+
+    let mut arguments = Vec::new();
+    for (argument, param) in call.arguments.iter().zip(params)
+    {
+        let coerced_argument = coerce_literal(argument, param, token)?;
+        let argument = coerced_argument.unwrap_or(argument.clone());
+        let argument_type = derive_data_type(scope, &argument)?;
+        let (matches, _) = DataTypeDescription::Exact(param.clone()).matches(&argument_type, &None);
+        if !matches
+        {
+            return Err(CompilerError::new_optional_position(argument.token().or(token), format!(
+                "Expected argument of type '{}', got '{}'", param, argument_type)));
+        }
+
+        arguments.push(argument);
     }
+
+    let return_size = match return_type
+    {
+        Some(return_type) => size_of(scope, return_type)?,
+        None => 0,
+    };
+
+    let function = compile_expression(gen, scope, &call.callable)?;
+    let compile_argument = |gen: &mut IRGenorator, index: usize| -> Result<_, Box<dyn Error>>
+    {
+        let value = compile_expression(gen, scope, &arguments[index])?;
+        Ok((value, size_of(scope, &params[index])?))
+    };
+
+    gen.call_indirect(function, arguments.len(), compile_argument, return_size)
 }
 
 fn compile_call(gen: &mut IRGenorator, scope: &mut Scope, call: &Call)
     -> Result<Rc<IRValue>, Box<dyn Error>>
 {
+    if let Some(function_type) = indirect_callee_type(scope, call)? {
+        return compile_indirect_call(gen, scope, call, &function_type);
+    }
+
     let function_name_token =
         match call.callable.as_ref()
         {
@@ -400,8 +1188,12 @@ fn compile_extern_call(gen: &mut IRGenorator, scope: &mut Scope, call: &Call)
             "Could not find external function '{}'", function_name)));
     }
 
+    // NOTE: C functions usually give truth values as an int, where any
+    //       non-zero value is true. So read the whole int and normalise it.
+    let returns_bool = call.type_variable == Some(DataType::Bool);
     let return_size = match &call.type_variable
     {
+        Some(_) if returns_bool => size_of(scope, &DataType::Int)?,
         Some(return_type) => size_of(scope, return_type)?,
         None => 0,
     };
@@ -412,11 +1204,32 @@ fn compile_extern_call(gen: &mut IRGenorator, scope: &mut Scope, call: &Call)
         let argument_expression = &call.arguments[index];
         let data_type = derive_data_type(scope, argument_expression)?;
         let value = compile_expression(gen, scope, argument_expression)?;
+
+        // NOTE: Like C, small signed ints are sign extended to an int
+        //       and floats become doubles when passed. Everything else
+        //       is zero extended when pushed.
+        if data_type == DataType::I8 || data_type == DataType::I16 {
+            return Ok((gen.convert_int(value, 4, true), 4));
+        }
+        if data_type == DataType::F32 {
+            return Ok((gen.convert_float(value, 8), 8));
+        }
+
         let size = size_of(scope, &data_type)?;
         Ok((value, size))
     };
 
-    gen.call(function_name, argument_count, compile_argument, return_size)
+    let returns_float = call.type_variable.as_ref()
+        .and_then(DataType::float_size).is_some();
+
+    let result = gen.call(function_name, argument_count, compile_argument, return_size)?;
+    if returns_bool {
+        Ok(gen.int_to_bool(result))
+    } else if returns_float {
+        Ok(gen.extern_float_return(result))
+    } else {
+        Ok(result)
+    }
 }
 
 pub fn compile_expression(gen: &mut IRGenorator, scope: &mut Scope,
@@ -426,15 +1239,24 @@ pub fn compile_expression(gen: &mut IRGenorator, scope: &mut Scope,
     match expression
     {
         Expression::IntLiteral(i) => Ok(gen.emit_int(*i)),
-        Expression::BoolLiteral(b) => Ok(gen.emit_char(if *b { 1u8 } else { 0u8 } as char)),
-        Expression::StringLiteral(s) => Ok(gen.emit_string(s.content())),
-        Expression::CharLiteral(c) => Ok(gen.emit_char(c.content().chars().nth(0).unwrap())),
-        Expression::Identifier(name) => compile_identifier(scope, name),
+        Expression::TypedIntLiteral(bits, data_type) =>
+            Ok(gen.emit_sized_int(*bits, data_type.int_layout().unwrap().0)),
+        Expression::FloatLiteral(value) => Ok(gen.emit_float(*value, 8)),
+        Expression::TypedFloatLiteral(value, data_type) =>
+            Ok(gen.emit_float(*value, data_type.float_size().unwrap())),
+        Expression::BoolLiteral(b) => Ok(gen.emit_bool(*b)),
+        Expression::StringLiteral(s) => Ok(gen.emit_string(&s.unescaped_content())),
+        Expression::CharLiteral(c) => Ok(gen.emit_char(c.unescaped_content()[0] as char)),
+        Expression::Identifier(name) => compile_identifier(gen, scope, name),
         Expression::InitializerList(list) => compile_initilizer_list(gen, scope, list),
+        Expression::VariantLiteral(literal) => compile_variant_literal(gen, scope, literal),
+        Expression::TupleLiteral(items) => compile_tuple_literal(gen, scope, items),
+        Expression::FunctionRef(function_ref) => compile_function_ref(gen, scope, function_ref),
         Expression::ArrayLiteral(array) => compile_array_literal(gen, scope, array),
         Expression::Operation(operation) => compile_operation(gen, scope, operation),
         Expression::Call(call) => compile_call(gen, scope, call),
         Expression::ExternCall(call) => compile_extern_call(gen, scope, call),
+        Expression::Cast(cast) => compile_cast(gen, scope, cast),
     }
 }
 
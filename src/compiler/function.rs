@@ -5,11 +5,13 @@ use super::name_table::{FunctionDescriptionType, CompiledFunction};
 use super::name_table::Scope;
 use super::data_type::{size_of, derive_data_type, resolve_type_aliases};
 use super::data_type::{function_signature, call_signature};
-use super::data_type::{doas_type_exist, type_variable_name};
+use super::data_type::{doas_type_exist, type_variable_name, type_module, resolve_type_names};
+use super::trait_::{check_trait_bounds, check_implements_trait};
 use super::error::CompilerError;
 use crate::tokenizer::Token;
 use crate::ast::{Function, Statement, Call};
-use crate::data_type::DataType;
+use crate::data_type::{DataType, DataTypeDescription};
+use crate::ast::ModuleId;
 use std::rc::Rc;
 use std::collections::HashSet;
 use std::error::Error;
@@ -17,18 +19,21 @@ use std::error::Error;
 fn function_from_description(scope: &mut Scope,
                              description: FunctionDescriptionType,
                              function_name: &str,
-                             call: &Call,
+                             argument_types: &[DataType],
                              type_variable: Option<DataType>)
     -> Result<(String, CompiledFunction), Box<dyn Error>>
 {
-    let params = call.arguments
-        .iter()
-        .map(|argument| derive_data_type(scope, argument))
-        .collect::<Result<Vec<_>, _>>()?;
+    // NOTE: The instance takes the declared param types, so a mutable
+    //       ref passed as a ref doesn't give a separate instance.
+    let params = description.params.iter()
+        .zip(argument_types)
+        .map(|(param, argument_type)| param.param_type(argument_type))
+        .collect::<Vec<_>>();
 
     let type_alias = description.type_variable.as_ref().zip(type_variable.as_ref());
     let return_type = description.return_type.clone();
-    let signature = call_signature(scope, function_name, call, type_alias, &return_type)?;
+    let signature = call_signature(scope, function_name,
+        description.module, &params, type_alias, &return_type)?;
     let function = CompiledFunction
     {
         name: function_name.to_owned(),
@@ -42,25 +47,114 @@ fn function_from_description(scope: &mut Scope,
     Ok((signature, function))
 }
 
+fn add_defining_modules(scope: &Scope,
+                        data_type: &DataType,
+                        modules: &mut HashSet<ModuleId>)
+{
+    match data_type
+    {
+        DataType::Struct(name) =>
+        {
+            if let Some(module) = type_module(scope, name) {
+                modules.insert(module);
+            }
+        },
+
+        DataType::Generic(argument, name) =>
+        {
+            add_defining_modules(scope, argument, modules);
+            add_defining_modules(scope, &DataType::Struct(name.clone()), modules);
+        },
+
+        DataType::Array(item_type, _) => add_defining_modules(scope, item_type, modules),
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) =>
+            add_defining_modules(scope, ref_type, modules),
+        DataType::Tuple(items) =>
+        {
+            for item in items {
+                add_defining_modules(scope, item, modules);
+            }
+        },
+        DataType::Function(params, return_type) =>
+        {
+            for param in params.iter().chain(return_type.as_deref()) {
+                add_defining_modules(scope, param, modules);
+            }
+        },
+        _ => {},
+    }
+}
+
+/// Inside generic instances, functions defined next to the types of the
+/// arguments, or exported from any module, are also visible. This is how
+/// trait functions get found for types the generic function's module
+/// knows nothing about, including ones like `int` that no module defines.
+pub fn is_function_visible(scope: &Scope,
+                           description: &FunctionDescriptionType,
+                           argument_types: &[DataType])
+    -> bool
+{
+    if scope.is_visible(description.module, description.is_exported) {
+        return true;
+    }
+    if !scope.is_generic_instance() {
+        return false;
+    }
+    if description.is_exported {
+        return true;
+    }
+
+    let mut defining_modules = HashSet::new();
+    for argument_type in argument_types {
+        add_defining_modules(scope, argument_type, &mut defining_modules);
+    }
+    defining_modules.contains(&description.module)
+}
+
 pub fn find_function_for_call(scope: &mut Scope,
                               function_name_token: &Token,
                               call: &Call)
     -> Result<(String, CompiledFunction), Box<dyn Error>>
+{
+    let argument_types = call.arguments
+        .iter()
+        .map(|argument| derive_data_type(scope, argument))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let type_variable = match &call.type_variable
+    {
+        Some(type_variable) =>
+            Some(resolve_type_names(scope, type_variable.clone(), Some(function_name_token))?),
+        None => None,
+    };
+
+    find_function(scope, function_name_token, &argument_types, &type_variable)
+}
+
+/// Find the overload of a function that takes arguments of these types,
+/// like a call to it would.
+pub fn find_function(scope: &mut Scope,
+                     function_name_token: &Token,
+                     argument_types: &[DataType],
+                     type_variable: &Option<DataType>)
+    -> Result<(String, CompiledFunction), Box<dyn Error>>
 {
     let function_name = function_name_token.content();
     let possible_functions = scope.lookup_function_descriptions(function_name);
+
+    let mut hidden_function_module = None;
+    let mut unsatisfied_bound_error = None;
+    let mut found_function: Option<(FunctionDescriptionType, Option<DataType>)> = None;
+    let mut ambiguous_function_module = None;
     for function_description in possible_functions
     {
-        if function_description.params.len() != call.arguments.len() {
+        if function_description.params.len() != argument_types.len() {
             continue;
         }
         
-        let param_arguements = function_description.params.iter().zip(&call.arguments);
-        let mut type_variable_value = match &call.type_variable
-        {
-            Some(type_variable) => Some(resolve_type_aliases(scope, type_variable.clone())),
-            None => None,
-        };
+        let param_arguements = function_description.params.iter().zip(argument_types);
+        let mut type_variable_value = type_variable.as_ref()
+            .map(|type_variable| resolve_type_aliases(scope, type_variable.clone()));
 
         let type_variable_name = match &function_description.type_variable
         {
@@ -85,11 +179,25 @@ pub fn find_function_for_call(scope: &mut Scope,
         };
 
         let mut did_match = true;
-        for (param, argument) in param_arguements
+        for (param, argument_type) in param_arguements
         {
-            let argument_type = derive_data_type(scope, argument)?;
+            if let DataTypeDescription::Trait(trait_name) = param
+            {
+                let implements_check = check_implements_trait(
+                    scope, argument_type, trait_name, function_name_token);
+
+                if implements_check.is_err()
+                {
+                    unsatisfied_bound_error = implements_check.err();
+                    did_match = false;
+                    break;
+                }
+
+                continue;
+            }
+
             let (matches, param_type_variable_value) = param.matches(
-                &argument_type, &type_variable_name);
+                argument_type, &type_variable_name);
 
             if !matches ||
                 (type_variable_value.is_some() &&
@@ -107,10 +215,85 @@ pub fn find_function_for_call(scope: &mut Scope,
 
         if did_match
         {
-            return Ok(function_from_description(
-                scope, function_description, function_name, call,
-                type_variable_value)?);
+            // NOTE: A generic instance may also see the module of its
+            //       type variable, like for `default() of T`.
+            let mut defining_types = argument_types.to_vec();
+            defining_types.extend(type_variable_value.clone());
+            if !is_function_visible(scope, &function_description, &defining_types)
+            {
+                hidden_function_module = Some(function_description.module);
+                continue;
+            }
+
+            if let Some(type_variable_value) = &type_variable_value
+            {
+                let bounds = &function_description.type_variable_bounds;
+                let bounds_check = check_trait_bounds(
+                    scope, type_variable_value, bounds, function_name_token);
+
+                if bounds_check.is_err()
+                {
+                    unsatisfied_bound_error = bounds_check.err();
+                    continue;
+                }
+            }
+
+            // NOTE: An overload in the current module hides any that are
+            //       imported, but two imported from different modules
+            //       are ambiguous.
+            if function_description.module == scope.current_module()
+            {
+                return Ok(function_from_description(
+                    scope, function_description, function_name, argument_types,
+                    type_variable_value)?);
+            }
+
+            match &found_function
+            {
+                Some((found_description, _)) =>
+                {
+                    if found_description.module != function_description.module {
+                        ambiguous_function_module = Some(function_description.module);
+                    }
+                },
+
+                None => found_function = Some((function_description, type_variable_value)),
+            }
+        }
+    }
+
+    if let Some((function_description, type_variable_value)) = found_function
+    {
+        if let Some(other_module) = ambiguous_function_module
+        {
+            let module_name = |module| scope.lookup_module(module)
+                .map(|description| description.name)
+                .unwrap_or_default();
+
+            return Err(CompilerError::new(function_name_token, format!(
+                "Call to '{}' is ambiguous, it could be from module '{}' or '{}'",
+                function_name, module_name(function_description.module),
+                module_name(other_module))));
         }
+
+        return function_from_description(
+            scope, function_description, function_name, argument_types,
+            type_variable_value);
+    }
+
+    if let Some(err) = unsatisfied_bound_error {
+        return Err(err);
+    }
+
+    if let Some(module) = hidden_function_module
+    {
+        let module_name = scope.lookup_module(module)
+            .map(|description| description.name)
+            .unwrap_or_default();
+
+        return Err(CompilerError::new(function_name_token, format!(
+            "Function '{}' is not exported from module '{}'",
+            function_name, module_name)));
     }
 
     return Err(CompilerError::new(function_name_token, format!(
@@ -158,7 +341,7 @@ fn compile_params(gen: &mut IRGenorator,
         param_sizes.push(return_size);
     }
 
-    let signature = function_signature(function, &param_types, return_type);
+    let signature = function_signature(scope, function, &param_types, return_type);
     let mut params = gen.start_function(&signature, param_sizes.into_iter());
     let return_to = if is_big_return { params.pop() } else { None };
 
@@ -166,8 +349,12 @@ fn compile_params(gen: &mut IRGenorator,
     for ((value, param), data_type) in param_and_names
     {
         // TODO: Handle duplicate name error here
-        let name = param.name.content();
-        assert!(scope.put_value(name.to_owned(), value, data_type.clone()));
+        let name = param.name.content().to_owned();
+        if param.is_mutable {
+            assert!(scope.put_mutable_value(name, value, data_type.clone()));
+        } else {
+            assert!(scope.put_value(name, value, data_type.clone()));
+        }
     }
 
     Ok(return_to)
@@ -184,6 +371,15 @@ pub fn compile_function(gen: &mut IRGenorator,
     }
 
     let mut local_scope = create_local_scope(scope, function_data);
+    local_scope.set_module(function.module);
+
+    let has_trait_params = function_data.description.params
+        .iter()
+        .any(|param| matches!(param, DataTypeDescription::Trait(_)));
+    if function_data.type_variable.is_some() || has_trait_params {
+        local_scope.set_generic_instance();
+    }
+
     let return_type = match &function.return_type
     {
         Some(return_type) =>
@@ -3,7 +3,7 @@ pub mod value;
 use output::IROutput;
 use value::{IRLocation, IRValue};
 use crate::intermediate::{IR, IROperation, IRProgram, IRFunction};
-use crate::intermediate::IRStorage;
+use crate::intermediate::{IRStorage, argument_size};
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::error::Error;
@@ -61,11 +61,19 @@ impl IRGenorator
         output.emit_ir(instruction);
     }
 
+    fn stored_size(value: &IRValue) -> usize
+    {
+        match &value.location
+        {
+            IRLocation::Storage(_, size) => *size,
+            _ => panic!(),
+        }
+    }
+
     fn ensure_storage(&mut self, value: Rc<IRValue>) -> Rc<IRValue>
     {
         match &value.location
         {
-            IRLocation::Null => panic!(),
             IRLocation::Field(_, _) => panic!(),
             IRLocation::Storage(_, _) => value,
 
@@ -96,7 +104,6 @@ impl IRGenorator
     {
         match &value.location
         {
-            IRLocation::Null => panic!(),
             IRLocation::Field(_, _) => panic!(),
             IRLocation::I32(i) => self.emit_ir(IR::PushI32(*i)),
             IRLocation::I8(i) => self.emit_ir(IR::PushI8(*i)),
@@ -114,19 +121,47 @@ impl IRGenorator
         label
     }
 
-    pub fn emit_null(&mut self) -> Rc<IRValue>
+    pub fn emit_int(&mut self, i: i32) -> Rc<IRValue>
     {
-        self.new_value(IRLocation::Null)
+        self.new_value(IRLocation::I32(i))
     }
 
-    pub fn emit_int(&mut self, i: i32) -> Rc<IRValue>
+    /// An int of `size` bytes, given as its bits.
+    pub fn emit_sized_int(&mut self, bits: u64, size: usize) -> Rc<IRValue>
     {
-        self.new_value(IRLocation::I32(i))
+        match size
+        {
+            1 => return self.new_value(IRLocation::I8(bits as i8)),
+            4 => return self.new_value(IRLocation::I32(bits as i32)),
+            _ => {},
+        }
+
+        let storage = self.allocate(size);
+        match size
+        {
+            2 => self.emit_ir(IR::SetI16(storage.clone(), bits as i16)),
+            8 => self.emit_ir(IR::SetI64(storage.clone(), bits as i64)),
+            _ => panic!(),
+        }
+        self.new_value(IRLocation::Storage(storage, size))
+    }
+
+    /// A float of `size` bytes, either an `f32` or an `f64`.
+    pub fn emit_float(&mut self, value: f64, size: usize) -> Rc<IRValue>
+    {
+        let storage = self.allocate(size);
+        match size
+        {
+            4 => self.emit_ir(IR::SetF32(storage.clone(), value as f32)),
+            8 => self.emit_ir(IR::SetF64(storage.clone(), value)),
+            _ => panic!(),
+        }
+        self.new_value(IRLocation::Storage(storage, size))
     }
 
-    pub fn emit_string(&mut self, s: &str) -> Rc<IRValue>
+    pub fn emit_string(&mut self, s: &[u8]) -> Rc<IRValue>
     {
-        self.new_value(IRLocation::String(s.to_owned()))
+        self.new_value(IRLocation::String(s.to_vec()))
     }
 
     pub fn emit_char(&mut self, c: char) -> Rc<IRValue>
@@ -134,12 +169,26 @@ impl IRGenorator
         self.new_value(IRLocation::I8(c as i8))
     }
 
+    /// Bools are stored in a single byte, as either 0 or 1.
+    pub fn emit_bool(&mut self, b: bool) -> Rc<IRValue>
+    {
+        self.new_value(IRLocation::I8(b as i8))
+    }
+
     pub fn emit_extern(&mut self, name: &str)
     {
         let mut output = self.output.borrow_mut();
         output.add_extern(name.to_owned());
     }
 
+    /// The address of the function called `function_name`.
+    pub fn emit_function_ref(&mut self, function_name: &str) -> Rc<IRValue>
+    {
+        let storage = self.allocate(4);
+        self.emit_ir(IR::SetFunction(storage.clone(), function_name.to_owned()));
+        self.new_value(IRLocation::Storage(storage, 4))
+    }
+
     pub fn emit_struct_offset(&mut self, offset: i32, size: usize) -> Rc<IRValue>
     {
         self.new_value(IRLocation::Field(offset as usize, size))
@@ -197,7 +246,6 @@ impl IRGenorator
     {
         match &to.location
         {
-            IRLocation::Null => panic!(),
             IRLocation::Field(_, _) => panic!(),
             IRLocation::I32(_) => panic!(),
             IRLocation::I8(_) => panic!(),
@@ -207,7 +255,6 @@ impl IRGenorator
             {
                 match &from.location
                 {
-                    IRLocation::Null => panic!(),
                     IRLocation::Field(_, _) => panic!(),
 
                     IRLocation::I32(i) =>
@@ -243,7 +290,6 @@ impl IRGenorator
         let lhs_value = self.ensure_storage(lhs);
         match &rhs.location
         {
-            IRLocation::Null => panic!(),
             IRLocation::Field(_, _) => panic!(),
             IRLocation::String(_) => panic!(),
             IRLocation::I8(_) => panic!(),
@@ -274,9 +320,211 @@ impl IRGenorator
         self.arithmatic_operation(lhs, rhs, IROperation::Multiply)
     }
 
-    pub fn subtract(&mut self, lhs: Rc<IRValue>, rhs: Rc<IRValue>) -> Rc<IRValue>
+    pub fn negate(&mut self, value: Rc<IRValue>) -> Rc<IRValue>
+    {
+        let result = self.allocate(4);
+        let stored_value = self.ensure_storage(value);
+        self.emit_ir(IR::I32Negate(result.clone(), stored_value.storage()));
+        self.new_value(IRLocation::Storage(result, 4))
+    }
+
+    /// Change the size of an int. When it gets bigger, the new bits are
+    /// filled with its sign if it's signed, or zeros otherwise.
+    pub fn convert_int(&mut self, value: Rc<IRValue>, to_size: usize, is_signed: bool)
+        -> Rc<IRValue>
+    {
+        let stored_value = self.ensure_storage(value);
+        let from_size = Self::stored_size(&stored_value);
+        if from_size == to_size {
+            return stored_value;
+        }
+
+        let result = self.allocate(to_size);
+        self.emit_ir(IR::ConvertInt(result.clone(), to_size,
+            stored_value.storage(), from_size, is_signed));
+        self.new_value(IRLocation::Storage(result, to_size))
+    }
+
+    /// Do `operation` on two ints of `size` bytes.
+    pub fn int_operation(&mut self,
+                         lhs: Rc<IRValue>,
+                         rhs: Rc<IRValue>,
+                         operation: IROperation,
+                         size: usize,
+                         is_signed: bool)
+        -> Rc<IRValue>
+    {
+        let is_comparison = operation.is_comparison();
+        match (size, is_signed)
+        {
+            (4, true) if is_comparison => self.comparison_operation(lhs, rhs, operation),
+            (4, true) => self.arithmatic_operation(lhs, rhs, operation),
+
+            (4, false) | (8, _) =>
+            {
+                let result_size = if is_comparison { 1 } else { size };
+                let lhs_value = self.ensure_storage(lhs);
+                let rhs_value = self.ensure_storage(rhs);
+                let result = self.allocate(result_size);
+                let instruction = match size
+                {
+                    4 => IR::U32Operation,
+                    _ if is_signed => IR::I64Operation,
+                    _ => IR::U64Operation,
+                };
+
+                self.emit_ir(instruction(operation, result.clone(),
+                    lhs_value.storage(), rhs_value.storage()));
+                self.new_value(IRLocation::Storage(result, result_size))
+            },
+
+            // NOTE: Smaller ints are worked on as 32-bit ints, which
+            //       gives the same low bits, then cut back down to size.
+            _ =>
+            {
+                let lhs_value = self.convert_int(lhs, 4, is_signed);
+                let rhs_value = self.convert_int(rhs, 4, is_signed);
+                let result = self.int_operation(lhs_value, rhs_value,
+                    operation, 4, is_signed);
+
+                if is_comparison {
+                    result
+                } else {
+                    self.convert_int(result, size, is_signed)
+                }
+            },
+        }
+    }
+
+    pub fn int_negate(&mut self, value: Rc<IRValue>, size: usize) -> Rc<IRValue>
+    {
+        match size
+        {
+            4 => self.negate(value),
+
+            8 =>
+            {
+                let stored_value = self.ensure_storage(value);
+                let result = self.allocate(size);
+                self.emit_ir(IR::I64Negate(result.clone(), stored_value.storage()));
+                self.new_value(IRLocation::Storage(result, size))
+            },
+
+            _ =>
+            {
+                let extended_value = self.convert_int(value, 4, true);
+                let result = self.negate(extended_value);
+                self.convert_int(result, size, true)
+            },
+        }
+    }
+
+    pub fn int_bit_not(&mut self, value: Rc<IRValue>, size: usize, is_signed: bool)
+        -> Rc<IRValue>
+    {
+        let all_bits = self.emit_sized_int(u64::MAX, size);
+        self.int_operation(value, all_bits, IROperation::Xor, size, is_signed)
+    }
+
+    /// Do `operation` on two floats of `size` bytes.
+    pub fn float_operation(&mut self,
+                           lhs: Rc<IRValue>,
+                           rhs: Rc<IRValue>,
+                           operation: IROperation,
+                           size: usize)
+        -> Rc<IRValue>
+    {
+        let result_size = if operation.is_comparison() { 1 } else { size };
+        let lhs_value = self.ensure_storage(lhs);
+        let rhs_value = self.ensure_storage(rhs);
+        let result = self.allocate(result_size);
+        let instruction = match size
+        {
+            4 => IR::F32Operation,
+            8 => IR::F64Operation,
+            _ => panic!(),
+        };
+
+        self.emit_ir(instruction(operation, result.clone(),
+            lhs_value.storage(), rhs_value.storage()));
+        self.new_value(IRLocation::Storage(result, result_size))
+    }
+
+    pub fn float_negate(&mut self, value: Rc<IRValue>, size: usize) -> Rc<IRValue>
+    {
+        let stored_value = self.ensure_storage(value);
+        let result = self.allocate(size);
+        match size
+        {
+            4 => self.emit_ir(IR::F32Negate(result.clone(), stored_value.storage())),
+            8 => self.emit_ir(IR::F64Negate(result.clone(), stored_value.storage())),
+            _ => panic!(),
+        }
+        self.new_value(IRLocation::Storage(result, size))
+    }
+
+    /// Change a float to one of `to_size` bytes.
+    pub fn convert_float(&mut self, value: Rc<IRValue>, to_size: usize) -> Rc<IRValue>
+    {
+        let stored_value = self.ensure_storage(value);
+        let from_size = Self::stored_size(&stored_value);
+        if from_size == to_size {
+            return stored_value;
+        }
+
+        let result = self.allocate(to_size);
+        self.emit_ir(IR::ConvertFloat(result.clone(), to_size,
+            stored_value.storage(), from_size));
+        self.new_value(IRLocation::Storage(result, to_size))
+    }
+
+    /// Convert an int to a float of `to_size` bytes.
+    pub fn int_to_float(&mut self, value: Rc<IRValue>, to_size: usize, is_signed: bool)
+        -> Rc<IRValue>
+    {
+        // NOTE: Only signed 32-bit and any 64-bit ints are converted
+        //       directly. Smaller ints are extended to one of those first.
+        let stored_value = self.ensure_storage(value);
+        let (int_value, int_size, is_signed) = match Self::stored_size(&stored_value)
+        {
+            8 => (stored_value, 8, is_signed),
+            4 if !is_signed => (self.convert_int(stored_value, 8, false), 8, true),
+            _ => (self.convert_int(stored_value, 4, is_signed), 4, true),
+        };
+
+        let result = self.allocate(to_size);
+        self.emit_ir(IR::IntToFloat(result.clone(), to_size,
+            int_value.storage(), int_size, is_signed));
+        self.new_value(IRLocation::Storage(result, to_size))
+    }
+
+    /// Convert a float to an int of `to_size` bytes, rounding towards zero.
+    pub fn float_to_int(&mut self, value: Rc<IRValue>, to_size: usize, is_signed: bool)
+        -> Rc<IRValue>
+    {
+        let stored_value = self.ensure_storage(value);
+        let from_size = Self::stored_size(&stored_value);
+        let (int_size, is_int_signed) = match (to_size, is_signed)
+        {
+            (8, _) => (8, is_signed),
+            (4, false) => (8, true),
+            _ => (4, true),
+        };
+
+        let result = self.allocate(int_size);
+        self.emit_ir(IR::FloatToInt(result.clone(), int_size,
+            stored_value.storage(), from_size, is_int_signed));
+
+        let int_value = self.new_value(IRLocation::Storage(result, int_size));
+        self.convert_int(int_value, to_size, is_signed)
+    }
+
+    /// Move a float returned by a C function into its return value.
+    pub fn extern_float_return(&mut self, value: Rc<IRValue>) -> Rc<IRValue>
     {
-        self.arithmatic_operation(lhs, rhs, IROperation::Subtract)
+        let size = Self::stored_size(&value);
+        self.emit_ir(IR::ExternFloatReturn(value.storage(), size));
+        value
     }
 
     fn comparison_operation(&mut self,
@@ -287,24 +535,42 @@ impl IRGenorator
     {
         let result = self.allocate(1);
         let lhs_value = self.ensure_storage(lhs);
-        match &rhs.location
+        let lhs_size = match &lhs_value.location
         {
-            IRLocation::Null => panic!(),
-            IRLocation::Field(_, _) => panic!(),
-            IRLocation::String(_) => panic!(),
-            IRLocation::I8(_) => panic!(),
+            IRLocation::Storage(_, size) => *size,
+            _ => panic!(),
+        };
 
-            IRLocation::I32(i) =>
+        match (&rhs.location, lhs_size)
+        {
+            (IRLocation::Field(_, _), _) => panic!(),
+            (IRLocation::String(_), _) => panic!(),
+
+            (IRLocation::I32(i), 4) =>
             {
                 self.emit_ir(IR::I32ConstantOperation(
                     operation, result.clone(), lhs_value.storage(), *i))
             },
 
-            IRLocation::Storage(rhs_storage, _) =>
+            (IRLocation::I8(i), 1) =>
+            {
+                self.emit_ir(IR::I8ConstantOperation(
+                    operation, result.clone(), lhs_value.storage(), *i))
+            },
+
+            (IRLocation::Storage(rhs_storage, 4), 4) =>
             {
                 self.emit_ir(IR::I32Operation(
                     operation, result.clone(), lhs_value.storage(), rhs_storage.clone()))
             },
+
+            (IRLocation::Storage(rhs_storage, 1), 1) =>
+            {
+                self.emit_ir(IR::I8Operation(
+                    operation, result.clone(), lhs_value.storage(), rhs_storage.clone()))
+            },
+
+            _ => panic!(),
         }
 
         self.new_value(IRLocation::Storage(result, 1))
@@ -320,6 +586,77 @@ impl IRGenorator
         self.comparison_operation(lhs, rhs, IROperation::LessThan)
     }
 
+    pub fn greater_than_equals(&mut self, lhs: Rc<IRValue>, rhs: Rc<IRValue>) -> Rc<IRValue>
+    {
+        self.comparison_operation(lhs, rhs, IROperation::GreaterThanEquals)
+    }
+
+    pub fn less_than_equals(&mut self, lhs: Rc<IRValue>, rhs: Rc<IRValue>) -> Rc<IRValue>
+    {
+        self.comparison_operation(lhs, rhs, IROperation::LessThanEquals)
+    }
+
+    pub fn equals(&mut self, lhs: Rc<IRValue>, rhs: Rc<IRValue>) -> Rc<IRValue>
+    {
+        self.comparison_operation(lhs, rhs, IROperation::Equals)
+    }
+
+    pub fn not_equals(&mut self, lhs: Rc<IRValue>, rhs: Rc<IRValue>) -> Rc<IRValue>
+    {
+        self.comparison_operation(lhs, rhs, IROperation::NotEquals)
+    }
+
+    pub fn not(&mut self, value: Rc<IRValue>) -> Rc<IRValue>
+    {
+        let false_value = self.emit_bool(false);
+        self.comparison_operation(value, false_value, IROperation::Equals)
+    }
+
+    /// Turn an int, where any non-zero value is true, into a bool.
+    pub fn int_to_bool(&mut self, value: Rc<IRValue>) -> Rc<IRValue>
+    {
+        let zero = self.emit_int(0);
+        self.comparison_operation(value, zero, IROperation::NotEquals)
+    }
+
+    /// Only compile the right hand side if the left hand side does not
+    /// already decide the result, jumping over it otherwise.
+    fn short_circuit_operation<F>(&mut self, lhs: Rc<IRValue>,
+                                  compile_rhs: F, is_and: bool)
+            -> Result<Rc<IRValue>, Box<dyn Error>>
+        where F: FnOnce(&mut Self) -> Result<Rc<IRValue>, Box<dyn Error>>
+    {
+        let result = self.allocate(1);
+        let result_value = self.new_value(IRLocation::Storage(result, 1));
+        self.mov(result_value.clone(), lhs);
+
+        let end_label = self.create_label("short_circuit_end");
+        if is_and {
+            self.goto_if_not(&end_label, result_value.clone());
+        } else {
+            self.goto_if(&end_label, result_value.clone());
+        }
+
+        let rhs = compile_rhs(self)?;
+        self.mov(result_value.clone(), rhs);
+        self.emit_label(&end_label);
+        Ok(result_value)
+    }
+
+    pub fn and<F>(&mut self, lhs: Rc<IRValue>, compile_rhs: F)
+            -> Result<Rc<IRValue>, Box<dyn Error>>
+        where F: FnOnce(&mut Self) -> Result<Rc<IRValue>, Box<dyn Error>>
+    {
+        self.short_circuit_operation(lhs, compile_rhs, true)
+    }
+
+    pub fn or<F>(&mut self, lhs: Rc<IRValue>, compile_rhs: F)
+            -> Result<Rc<IRValue>, Box<dyn Error>>
+        where F: FnOnce(&mut Self) -> Result<Rc<IRValue>, Box<dyn Error>>
+    {
+        self.short_circuit_operation(lhs, compile_rhs, false)
+    }
+
     pub fn ref_of(&mut self, value: Rc<IRValue>) -> Rc<IRValue>
     {
         let result = self.allocate(4);
@@ -334,20 +671,31 @@ impl IRGenorator
         self.new_value(IRLocation::Storage(result, size))
     }
 
-    pub fn access(&mut self, ref_value: Rc<IRValue>, field: Rc<IRValue>) -> Rc<IRValue>
+    /// Write `value` to the address held in `ref_value`.
+    pub fn store(&mut self, ref_value: Rc<IRValue>, value: Rc<IRValue>)
+    {
+        let stored_value = self.ensure_storage(value);
+        let size = match &stored_value.location
+        {
+            IRLocation::Storage(_, size) => *size,
+            _ => panic!(),
+        };
+
+        self.emit_ir(IR::Store(ref_value.storage(), stored_value.storage(), size));
+    }
+
+    pub fn field_ref(&mut self, ref_value: Rc<IRValue>, field: Rc<IRValue>) -> Rc<IRValue>
     {
-        let (field_offset, field_size) = match &field.location
+        let field_offset = match &field.location
         {
-            IRLocation::Field(offset, size) => (*offset, *size),
+            IRLocation::Field(offset, _) => *offset,
             _ => panic!(),
         };
 
+        let result = self.allocate(4);
         self.emit_ir(IR::I32ConstantOperation(IROperation::Add,
-            ref_value.storage(), ref_value.storage(), field_offset as i32));
-
-        let result = self.allocate(field_size);
-        self.emit_ir(IR::Deref(result.clone(), ref_value.storage(), field_size));
-        self.new_value(IRLocation::Storage(result, field_size))
+            result.clone(), ref_value.storage(), field_offset as i32));
+        self.new_value(IRLocation::Storage(result, 4))
     }
 
     pub fn ret(&mut self, value: Rc<IRValue>, size: usize)
@@ -361,17 +709,52 @@ impl IRGenorator
         self.emit_ir(IR::Goto(label.to_owned()));
     }
 
+    pub fn goto_if(&mut self, label: &str, condition: Rc<IRValue>)
+    {
+        let stored_condition = self.ensure_storage(condition);
+        self.emit_ir(IR::GotoIf(label.to_owned(), stored_condition.storage()));
+    }
+
     pub fn goto_if_not(&mut self, label: &str, condition: Rc<IRValue>)
     {
-        self.emit_ir(IR::GotoIfNot(label.to_owned(), condition.storage()));
+        let stored_condition = self.ensure_storage(condition);
+        self.emit_ir(IR::GotoIfNot(label.to_owned(), stored_condition.storage()));
     }
 
     pub fn call<F>(&mut self,
                function_name: &str,
                argument_count: usize,
-               mut compile_argument: F,
+               compile_argument: F,
                return_size: usize) -> Result<Rc<IRValue>, Box<dyn Error>>
         where F: FnMut(&mut Self, usize) -> Result<(Rc<IRValue>, usize), Box<dyn Error>>
+    {
+        self.emit_call(argument_count, compile_argument, return_size,
+            |return_storage| IR::Call(function_name.to_owned(), return_storage, return_size))
+    }
+
+    /// Call the function whose address is in `function`.
+    pub fn call_indirect<F>(&mut self,
+                            function: Rc<IRValue>,
+                            argument_count: usize,
+                            compile_argument: F,
+                            return_size: usize) -> Result<Rc<IRValue>, Box<dyn Error>>
+        where F: FnMut(&mut Self, usize) -> Result<(Rc<IRValue>, usize), Box<dyn Error>>
+    {
+        // NOTE: `function` has to stay alive until after the call,
+        //       so only its storage is moved into the instruction.
+        let function = self.ensure_storage(function);
+        let function_storage = function.storage();
+        self.emit_call(argument_count, compile_argument, return_size,
+            |return_storage| IR::CallIndirect(function_storage, return_storage, return_size))
+    }
+
+    fn emit_call<F, C>(&mut self,
+                       argument_count: usize,
+                       mut compile_argument: F,
+                       return_size: usize,
+                       call_instruction: C) -> Result<Rc<IRValue>, Box<dyn Error>>
+        where F: FnMut(&mut Self, usize) -> Result<(Rc<IRValue>, usize), Box<dyn Error>>,
+              C: FnOnce(IRStorage) -> IR
     {
         let big_return_storage = 
             if return_size > 4 { Some(self.allocate(return_size)) }
@@ -382,12 +765,11 @@ impl IRGenorator
         {
             let (argument, size) = compile_argument(self, i)?;
             self.push(argument);
-            total_argument_size += size;
+            total_argument_size += argument_size(size);
         }
 
         let return_storage = big_return_storage.unwrap_or_else(|| self.allocate(return_size));
-        self.emit_ir(IR::Call(
-            function_name.to_owned(), return_storage.clone(), return_size));
+        self.emit_ir(call_instruction(return_storage.clone()));
 
         self.emit_ir(IR::Pop(total_argument_size));
         Ok(self.new_value(IRLocation::Storage(return_storage, return_size)))
@@ -420,7 +802,7 @@ impl IRGenorator
         {
             param_values.push(self.new_value(IRLocation::Storage(
                 IRStorage::Param(last_offset), size)));
-            last_offset += size;
+            last_offset += argument_size(size);
         }
         param_values
     }
@@ -446,5 +828,13 @@ impl IRGenorator
         self.new_value(IRLocation::Storage(IRStorage::Local(local), size))
     }
 
+    pub fn allocate_zeroed_local(&mut self, size: usize)
+        -> Rc<IRValue>
+    {
+        let local = self.allocate_local(size);
+        self.emit_ir(IR::Zero(local.storage(), size));
+        local
+    }
+
 }
 
@@ -6,10 +6,9 @@ use std::collections::HashSet;
 
 pub enum IRLocation
 {
-    Null,
     I32(i32),
     I8(i8),
-    String(String),
+    String(Vec<u8>),
     Field(usize, usize),
     Storage(IRStorage, usize),
 }
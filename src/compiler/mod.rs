@@ -1,37 +1,98 @@
 mod intermediate;
 mod name_table;
 mod data_type;
-mod error;
+pub mod error;
 mod function;
 mod expression;
 mod statement;
+mod trait_;
+mod const_;
 use intermediate::IRGenorator;
-use name_table::{Scope, CompiledFunction, FunctionDescriptionType, TypedStructType};
-use data_type::{size_of, doas_type_exist, type_variable_name};
+use name_table::{Scope, CompiledFunction, FunctionDescriptionType, TypedStructType, EnumType};
+use name_table::ModuleDescription;
+use data_type::{size_of, align_of, align_to, doas_type_exist, type_variable_name};
+use data_type::{type_key, resolve_type_names};
 use function::compile_function;
-use crate::ast::SourceFile;
-use crate::ast::{Function, Struct};
+use trait_::{register_trait, check_trait, resolve_trait_name, check_struct_traits};
+use const_::register_const;
+use error::CompilerError;
+use crate::ast::{SourceFile, ROOT_MODULE};
+use crate::ast::{Function, Struct, Enum, Trait};
+use crate::data_type::{DataType, DataTypeDescription};
 use crate::intermediate::IRProgram;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 
-fn register_typed_struct(scope: &mut Scope,
-                         struct_: &Struct)
+fn register_modules(scope: &mut Scope,
+                    ast: &SourceFile)
+{
+    for (id, module) in ast.modules.iter().enumerate()
+    {
+        scope.put_module(id, ModuleDescription
+        {
+            name: module.name.clone(),
+            visible_modules: ast.visible_modules(id),
+        });
+    }
+}
+
+fn register_trait_module(scope: &mut Scope,
+                         trait_: &Trait)
+    -> Result<(), Box<dyn Error>>
+{
+    let name = trait_.name.content();
+    if !scope.put_struct_module(name.to_owned(), trait_.module, trait_.is_exported)
+    {
+        return Err(CompilerError::new(&trait_.name, format!(
+            "Trait '{}' is already defined", name)));
+    }
+
+    Ok(())
+}
+
+fn register_struct_module(scope: &mut Scope,
+                          struct_: &Struct)
     -> Result<(), Box<dyn Error>>
 {
+    let name = struct_.name.content();
+    if !scope.put_struct_module(name.to_owned(), struct_.module, struct_.is_exported)
+    {
+        return Err(CompilerError::new(&struct_.name, format!(
+            "Struct '{}' is already defined", name)));
+    }
+
+    Ok(())
+}
+
+fn resolve_field_types(scope: &Scope,
+                       struct_: &Struct)
+    -> Result<Vec<(String, DataType)>, Box<dyn Error>>
+{
+    let mut struct_scope = Scope::new(Some(scope));
+    struct_scope.set_module(struct_.module);
+
     let mut fields = Vec::new();
     for field in &struct_.fields
     {
         let name = field.name.content();
-        let data_type = field.data_type.clone();
+        let data_type = resolve_type_names(&struct_scope,
+            field.data_type.clone(), Some(&field.name))?;
         fields.push((name.to_owned(), data_type));
     }
 
+    Ok(fields)
+}
+
+fn register_typed_struct(scope: &mut Scope,
+                         struct_: &Struct,
+                         fields: Vec<(String, DataType)>)
+    -> Result<(), Box<dyn Error>>
+{
     let type_variable = struct_.type_variable.clone().unwrap();
-    let name = struct_.name.content();
+    let name = type_key(scope, struct_.name.content(), struct_.module);
     assert!(!doas_type_exist(scope, &type_variable));
 
-    scope.put_typed_struct(name.to_owned(), TypedStructType
+    scope.put_typed_struct(name, TypedStructType
     {
         variable: type_variable_name(&type_variable).to_owned(),
         fields,
@@ -43,25 +104,116 @@ fn register_struct(gen: &mut IRGenorator, scope: &mut Scope,
                                struct_: &Struct)
         -> Result<(), Box<dyn Error>>
 {
+    let fields = resolve_field_types(scope, struct_)?;
     if struct_.type_variable.is_some() {
-        return register_typed_struct(scope, struct_);
+        return register_typed_struct(scope, struct_, fields);
     }
 
     let mut last_offset = 0;
     let mut struct_layout = HashMap::new();
-    for field in &struct_.fields
+    for (name, data_type) in fields
     {
-        let name = field.name.content();
-        let data_type = field.data_type.clone();
         let size = size_of(scope, &data_type)?;
 
-        let value = gen.emit_struct_offset(last_offset, size);
-        last_offset += size as i32;
-        struct_layout.insert(name.to_owned(), (value, data_type));
+        last_offset = align_to(last_offset, align_of(scope, &data_type)?);
+        let value = gen.emit_struct_offset(last_offset as i32, size);
+        last_offset += size;
+        struct_layout.insert(name, (value, data_type));
+    }
+
+    let name = type_key(scope, struct_.name.content(), struct_.module);
+    scope.put_struct(name, struct_layout);
+    Ok(())
+}
+
+fn register_enum_module(scope: &mut Scope,
+                        enum_: &Enum)
+    -> Result<(), Box<dyn Error>>
+{
+    let name = enum_.name.content();
+    if !scope.put_struct_module(name.to_owned(), enum_.module, enum_.is_exported)
+    {
+        return Err(CompilerError::new(&enum_.name, format!(
+            "Enum '{}' is already defined", name)));
+    }
+
+    Ok(())
+}
+
+fn register_enum(scope: &mut Scope,
+                 enum_: &Enum)
+    -> Result<(), Box<dyn Error>>
+{
+    let mut enum_scope = Scope::new(Some(scope));
+    enum_scope.set_module(enum_.module);
+
+    let name = enum_.name.content();
+    let mut variants = Vec::<(String, Vec<DataType>)>::new();
+    for variant in &enum_.variants
+    {
+        let variant_name = variant.name.content();
+        if variants.iter().any(|(x, _)| x == variant_name)
+        {
+            return Err(CompilerError::new(&variant.name, format!(
+                "Variant '{}' is already defined in enum '{}'",
+                variant_name, name)));
+        }
+
+        let fields = variant.fields
+            .iter()
+            .map(|field| resolve_type_names(&enum_scope, field.clone(), Some(&variant.name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        variants.push((variant_name.to_owned(), fields));
+    }
+
+    let variable = enum_.type_variable
+        .as_ref()
+        .map(|type_variable| type_variable_name(type_variable).to_owned());
+
+    let name = type_key(scope, name, enum_.module);
+    scope.put_enum(name, EnumType
+    {
+        variable,
+        variants,
+    });
+    Ok(())
+}
+
+/// Resolve the type names in a function's signature to the types they
+/// refer to from its module. Parameters typed by a trait are parsed as
+/// plain struct types, as the parser doesn't know which names are traits.
+fn resolve_signature_types(scope: &Scope,
+                           function: &mut Function)
+    -> Result<(), Box<dyn Error>>
+{
+    let mut function_scope = Scope::new(Some(scope));
+    function_scope.set_module(function.module);
+
+    for param in &mut function.params
+    {
+        let data_type = match &param.data_type_description
+        {
+            DataTypeDescription::Exact(data_type) => data_type.clone(),
+            _ => continue,
+        };
+
+        let data_type = resolve_type_names(&function_scope, data_type, Some(&param.name))?;
+        param.data_type_description = match data_type
+        {
+            DataType::Struct(name) if scope.lookup_trait(&name).is_some() =>
+                DataTypeDescription::Trait(name),
+            data_type => DataTypeDescription::Exact(data_type),
+        };
+    }
+
+    let token = Some(&function.name);
+    if let Some(return_type) = function.return_type.take() {
+        function.return_type = Some(resolve_type_names(&function_scope, return_type, token)?);
+    }
+    if let Some(type_variable) = function.type_variable.take() {
+        function.type_variable = Some(resolve_type_names(&function_scope, type_variable, token)?);
     }
 
-    let name = struct_.name.content();
-    scope.put_struct(name.to_owned(), struct_layout);
     Ok(())
 }
 
@@ -69,6 +221,13 @@ fn register_function(scope: &mut Scope,
                             function: &Function)
     -> Result<(), Box<dyn Error>>
 {
+    let mut function_scope = Scope::new(Some(scope));
+    function_scope.set_module(function.module);
+    let type_variable_bounds = function.type_variable_bounds
+        .iter()
+        .map(|bound| resolve_trait_name(&function_scope, bound.content(), bound))
+        .collect::<Result<Vec<_>, _>>()?;
+
     let return_type = function.return_type.clone();
     let params = function.params
         .iter()
@@ -82,6 +241,9 @@ fn register_function(scope: &mut Scope,
         params,
         type_variable,
         return_type,
+        type_variable_bounds,
+        module: function.module,
+        is_exported: function.is_exported,
     });
     Ok(())
 }
@@ -94,13 +256,35 @@ fn register_extern(scope: &mut Scope,
     Ok(())
 }
 
-pub fn compile(ast: SourceFile)
+pub fn compile(mut ast: SourceFile)
     -> Result<IRProgram, Box<dyn Error>>
 {
     let mut scope = Scope::new(None);
+    register_modules(&mut scope, &ast);
+    for trait_ in &ast.traits {
+        register_trait_module(&mut scope, trait_)?;
+    }
+    for struct_ in &ast.structs {
+        register_struct_module(&mut scope, struct_)?;
+    }
+    for enum_ in &ast.enums {
+        register_enum_module(&mut scope, enum_)?;
+    }
+    for trait_ in &ast.traits {
+        register_trait(&mut scope, trait_)?;
+    }
+    for trait_ in &ast.traits {
+        check_trait(&scope, trait_)?;
+    }
+    for function in &mut ast.functions {
+        resolve_signature_types(&scope, function)?;
+    }
     for function in &ast.functions {
         register_function(&mut scope, function)?;
     }
+    for const_ in &ast.consts {
+        register_const(&mut scope, const_)?;
+    }
     for extern_ in &ast.externs {
         register_extern(&mut scope, extern_.content().to_owned())?;
     }
@@ -115,6 +299,9 @@ pub fn compile(ast: SourceFile)
             params: Vec::new(),
             type_variable: None,
             return_type: None,
+            type_variable_bounds: Vec::new(),
+            module: ROOT_MODULE,
+            is_exported: false,
         },
         params: Vec::new(),
         type_variable: None,
@@ -122,9 +309,18 @@ pub fn compile(ast: SourceFile)
     });
 
     let mut gen = IRGenorator::new();
+
+    // NOTE: Enums are registered first, as working out the
+    //       layout of a struct may need the size of one.
+    for enum_ in &ast.enums {
+        register_enum(&mut scope, enum_)?;
+    }
     for struct_ in &ast.structs {
         register_struct(&mut gen, &mut scope, struct_)?;
     }
+    for struct_ in &ast.structs {
+        check_struct_traits(&scope, struct_)?;
+    }
 
     while functions_to_compile.len() > 0
     {
@@ -132,7 +328,8 @@ pub fn compile(ast: SourceFile)
         let function = ast.find_function(
             &function_data.name,
             &function_data.description.params,
-            &function_data.description.type_variable);
+            &function_data.description.type_variable,
+            function_data.description.module);
 
         let functions_used = compile_function(
             &mut gen,
@@ -1,5 +1,6 @@
 use super::intermediate::value::IRValue;
 use crate::data_type::{DataType, DataTypeDescription};
+use crate::ast::{ModuleId, ROOT_MODULE, Expression};
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
@@ -9,6 +10,42 @@ pub struct FunctionDescriptionType
     pub params: Vec<DataTypeDescription>,
     pub type_variable: Option<DataType>,
     pub return_type: Option<DataType>,
+    pub type_variable_bounds: Vec<String>,
+    pub module: ModuleId,
+    pub is_exported: bool,
+}
+
+#[derive(Clone)]
+pub struct TraitFunctionType
+{
+    pub name: String,
+    pub params: Vec<DataType>,
+    pub return_type: Option<DataType>,
+}
+
+#[derive(Clone)]
+pub struct TraitType
+{
+    pub type_variables: Vec<(String, Vec<String>)>,
+    pub supertraits: Vec<DataType>,
+    pub functions: Vec<TraitFunctionType>,
+}
+
+#[derive(Clone)]
+pub struct ConstantType
+{
+    // NOTE: This is `None` when the value could not be worked
+    //       out at compile time.
+    pub value: Option<Expression>,
+    pub module: ModuleId,
+    pub is_exported: bool,
+}
+
+#[derive(Clone)]
+pub struct ModuleDescription
+{
+    pub name: String,
+    pub visible_modules: HashSet<ModuleId>,
 }
 
 #[derive(Clone)]
@@ -18,6 +55,13 @@ pub struct TypedStructType
     pub fields: Vec<(String, DataType)>,
 }
 
+#[derive(Clone)]
+pub struct EnumType
+{
+    pub variable: Option<String>,
+    pub variants: Vec<(String, Vec<DataType>)>,
+}
+
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct CompiledFunction
 {
@@ -31,9 +75,21 @@ pub struct CompiledFunction
 pub struct Scope<'a>
 {
     parent: Option<&'a Scope<'a>>,
+    module: Option<ModuleId>,
+    is_generic_instance: bool,
+    modules: HashMap<ModuleId, ModuleDescription>,
+
+    // Every module defining a struct, enum or trait by each name, and
+    // whether it's exported from there.
+    struct_modules: HashMap<String, Vec<(ModuleId, bool)>>,
+
     values: HashMap<String, (Rc<IRValue>, DataType)>,
+    mutable_values: HashSet<String>,
     structs: HashMap<String, HashMap<String, (Rc<IRValue>, DataType)>>,
     typed_structs: HashMap<String, TypedStructType>,
+    enums: HashMap<String, EnumType>,
+    traits: HashMap<String, TraitType>,
+    constants: HashMap<String, Vec<ConstantType>>,
     function_descriptions: HashMap<String, Vec<FunctionDescriptionType>>,
     type_aliases: HashMap<String, DataType>,
 
@@ -52,9 +108,17 @@ impl<'a> Scope<'a>
         Self
         {
             parent,
+            module: None,
+            is_generic_instance: false,
+            modules: Default::default(),
+            struct_modules: Default::default(),
             values: Default::default(),
+            mutable_values: Default::default(),
             structs: Default::default(),
             typed_structs: Default::default(),
+            enums: Default::default(),
+            traits: Default::default(),
+            constants: Default::default(),
             function_descriptions: Default::default(),
             type_aliases: Default::default(),
 
@@ -72,10 +136,38 @@ impl<'a> Scope<'a>
         self.used_functions.clone()
     }
 
+    pub fn set_module(&mut self, module: ModuleId)
+    {
+        self.module = Some(module);
+    }
+    pub fn set_generic_instance(&mut self)
+    {
+        self.is_generic_instance = true;
+    }
+    pub fn put_module(&mut self, module: ModuleId, description: ModuleDescription)
+    {
+        self.modules.insert(module, description);
+    }
+    pub fn put_struct_module(&mut self, name: String, module: ModuleId, is_exported: bool) -> bool
+    {
+        let modules = self.struct_modules.entry(name).or_default();
+        if modules.iter().any(|(other, _)| *other == module) {
+            return false;
+        }
+
+        modules.push((module, is_exported));
+        true
+    }
+
     pub fn put_value(&mut self, name: String, value: Rc<IRValue>, data_type: DataType) -> bool
     {
         self.values.insert(name, (value, data_type)).is_none()
     }
+    pub fn put_mutable_value(&mut self, name: String, value: Rc<IRValue>, data_type: DataType) -> bool
+    {
+        self.mutable_values.insert(name.clone());
+        self.put_value(name, value, data_type)
+    }
     pub fn put_struct(&mut self, name: String, value: HashMap<String, (Rc<IRValue>, DataType)>) -> bool
     {
         self.structs.insert(name, value).is_none()
@@ -84,6 +176,24 @@ impl<'a> Scope<'a>
     {
         self.typed_structs.insert(name, value).is_none()
     }
+    pub fn put_enum(&mut self, name: String, value: EnumType) -> bool
+    {
+        self.enums.insert(name, value).is_none()
+    }
+    pub fn put_trait(&mut self, name: String, value: TraitType) -> bool
+    {
+        self.traits.insert(name, value).is_none()
+    }
+    pub fn put_constant(&mut self, name: String, value: ConstantType) -> bool
+    {
+        let constants = self.constants.entry(name).or_default();
+        if constants.iter().any(|constant| constant.module == value.module) {
+            return false;
+        }
+
+        constants.push(value);
+        true
+    }
     pub fn put_function_description(&mut self, name: String, value: FunctionDescriptionType)
     {
         match self.function_descriptions.get_mut(&name)
@@ -116,10 +226,55 @@ impl<'a> Scope<'a>
         }
     }
 
+    pub fn current_module(&self) -> ModuleId
+    {
+        self.lookup("", |s, _| s.module)
+            .unwrap_or(ROOT_MODULE)
+    }
+    pub fn lookup_module(&self, module: ModuleId) -> Option<ModuleDescription>
+    {
+        self.lookup("", |s, _| s.modules.get(&module).cloned())
+    }
+    pub fn lookup_struct_modules(&self, name: &str) -> Vec<(ModuleId, bool)>
+    {
+        self.lookup(name, |s, n| s.struct_modules.get(n).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Can an item defined in `module` be referenced from the module
+    /// currently being compiled.
+    pub fn is_visible(&self, module: ModuleId, is_exported: bool) -> bool
+    {
+        let current_module = self.current_module();
+        if module == current_module {
+            return true;
+        }
+
+        match self.lookup_module(current_module)
+        {
+            Some(description) =>
+                is_exported && description.visible_modules.contains(&module),
+            None => false,
+        }
+    }
+
+    /// Are we compiling an instance of a generic function, where type
+    /// variables have been replaced by concrete types.
+    pub fn is_generic_instance(&self) -> bool
+    {
+        self.lookup("", |s, _| if s.is_generic_instance { Some(()) } else { None })
+            .is_some()
+    }
+
     pub fn lookup_value(&self, name: &str) -> Option<(Rc<IRValue>, DataType)>
     {
         self.lookup(name, |s, n| s.values.get(n).cloned())
     }
+    pub fn is_value_mutable(&self, name: &str) -> bool
+    {
+        self.lookup(name, |s, n| s.values.get(n).map(|_| s.mutable_values.contains(n)))
+            .unwrap_or(false)
+    }
     pub fn lookup_struct(&self, name: &str) -> Option<HashMap<String, (Rc<IRValue>, DataType)>>
     {
         self.lookup(name, |s, n| s.structs.get(n).cloned())
@@ -128,6 +283,19 @@ impl<'a> Scope<'a>
     {
         self.lookup(name, |s, n| s.typed_structs.get(n).cloned())
     }
+    pub fn lookup_enum(&self, name: &str) -> Option<EnumType>
+    {
+        self.lookup(name, |s, n| s.enums.get(n).cloned())
+    }
+    pub fn lookup_trait(&self, name: &str) -> Option<TraitType>
+    {
+        self.lookup(name, |s, n| s.traits.get(n).cloned())
+    }
+    pub fn lookup_constants(&self, name: &str) -> Vec<ConstantType>
+    {
+        self.lookup(name, |s, n| s.constants.get(n).cloned())
+            .unwrap_or_default()
+    }
     pub fn lookup_function_descriptions(&self, name: &str) -> Vec<FunctionDescriptionType>
     {
         self.lookup(name, |s, n| s.function_descriptions.get(n).cloned())
@@ -1,24 +1,192 @@
 use super::intermediate::IRGenorator;
 use super::intermediate::value::IRValue;
 use super::name_table::Scope;
-use super::data_type::{size_of, derive_data_type};
+use super::data_type::{size_of, derive_data_type, resolve_type_aliases};
+use super::data_type::{resolve_type_names, doas_type_exist, coerce_literal};
+use super::data_type::{enum_variants, variant_field_offsets, ENUM_TAG_SIZE};
+use super::data_type::tuple_item_offsets;
 use super::error::CompilerError;
 use super::expression::compile_expression;
-use crate::ast::{Expression, Let, If, Statement};
+use crate::ast::{Expression, Let, LetTuple, If, For, Iterable, Match, Statement};
+use crate::ast::{Operation, OperationType, Call};
+use crate::tokenizer::{Token, TokenType};
 use crate::data_type::DataType;
 use std::rc::Rc;
+use std::collections::HashSet;
 use std::error::Error;
 
+/// Where `break` and `continue` jump to in the inner most loop.
+#[derive(Clone, Copy)]
+pub struct LoopLabels<'a>
+{
+    continue_label: &'a str,
+    end_label: &'a str,
+}
+
+impl<'a> LoopLabels<'a>
+{
+
+    fn new(continue_label: &'a str, end_label: &'a str) -> Self
+    {
+        Self
+        {
+            continue_label,
+            end_label,
+        }
+    }
+
+}
+
+fn check_let_value_type(scope: &mut Scope, name: &Token,
+                        data_type: &DataType, value: &Expression)
+    -> Result<(), Box<dyn Error>>
+{
+    // NOTE: Allow `0` as the null value for refs.
+    let is_null_ref = matches!(
+        (data_type, value),
+        (DataType::Ref(_) | DataType::MutRef(_), Expression::IntLiteral(0)));
+
+    let value_data_type = derive_data_type(scope, value)?;
+    if !data_type.accepts(&value_data_type) && !is_null_ref
+    {
+        return Err(CompilerError::new(name, format!(
+            "Can not assign value of type '{}' to variable of type '{}'",
+            value_data_type, data_type)));
+    }
+
+    Ok(())
+}
+
 fn compile_let(gen: &mut IRGenorator, scope: &mut Scope, let_: &Let)
     -> Result<(), Box<dyn Error>>
 {
-    let data_type = derive_data_type(scope, &let_.value)?;
-    let local = gen.allocate_local(size_of(scope, &data_type)?);
-    let value = compile_expression(gen, scope, &let_.value)?;
-    gen.mov(local.clone(), value);
+    let data_type = match (&let_.data_type, &let_.value)
+    {
+        (Some(data_type), value) =>
+        {
+            let data_type = resolve_type_names(scope, data_type.clone(), Some(&let_.name))?;
+            let data_type = resolve_type_aliases(scope, data_type);
+            if !doas_type_exist(scope, &data_type)
+            {
+                return Err(CompilerError::new(&let_.name, format!(
+                    "Could not find type '{}'", data_type)));
+            }
+
+            if let Some(value) = value
+            {
+                let coerced_value = coerce_literal(value, &data_type, Some(&let_.name))?;
+                check_let_value_type(scope, &let_.name, &data_type,
+                    coerced_value.as_ref().unwrap_or(value))?;
+            }
+            data_type
+        },
+
+        (None, Some(value)) => derive_data_type(scope, value)?,
+
+        // NOTE: The parser only allows leaving out the value when
+        //       there's a type.
+        (None, None) => panic!(),
+    };
+
+    let size = size_of(scope, &data_type)?;
+    let local = match &let_.value
+    {
+        Some(value) =>
+        {
+            let coerced_value = coerce_literal(value, &data_type, Some(&let_.name))?;
+            let local = gen.allocate_local(size);
+            let value = compile_expression(gen, scope,
+                coerced_value.as_ref().unwrap_or(value))?;
+            gen.mov(local.clone(), value);
+            local
+        },
+
+        None => gen.allocate_zeroed_local(size),
+    };
 
     // TODO: Proper error handling here.
-    assert!(scope.put_value(let_.name.content().to_owned(), local, data_type));
+    let name = let_.name.content().to_owned();
+    if let_.is_mutable {
+        assert!(scope.put_mutable_value(name, local, data_type));
+    } else {
+        assert!(scope.put_value(name, local, data_type));
+    }
+
+    Ok(())
+}
+
+fn compile_let_tuple(gen: &mut IRGenorator, scope: &mut Scope, let_tuple: &LetTuple)
+    -> Result<(), Box<dyn Error>>
+{
+    // NOTE: The parser makes sure there's at least one binding.
+    let first_name = &let_tuple.bindings[0].name;
+    let coerced_value = match &let_tuple.data_type
+    {
+        Some(data_type) =>
+        {
+            let data_type = resolve_type_names(scope, data_type.clone(), Some(first_name))?;
+            let data_type = resolve_type_aliases(scope, data_type);
+            if !doas_type_exist(scope, &data_type)
+            {
+                return Err(CompilerError::new(first_name, format!(
+                    "Could not find type '{}'", data_type)));
+            }
+
+            let coerced_value = coerce_literal(&let_tuple.value, &data_type, Some(first_name))?;
+            check_let_value_type(scope, first_name, &data_type,
+                coerced_value.as_ref().unwrap_or(&let_tuple.value))?;
+            coerced_value
+        },
+
+        None => None,
+    };
+
+    let value = coerced_value.as_ref().unwrap_or(&let_tuple.value);
+    let data_type = derive_data_type(scope, value)?;
+    let items = match &data_type
+    {
+        DataType::Tuple(items) if items.len() == let_tuple.bindings.len() => items,
+        _ =>
+        {
+            return Err(CompilerError::new(first_name, format!(
+                "Can not unpack value of type '{}' into {} variables",
+                data_type, let_tuple.bindings.len())));
+        },
+    };
+
+    let mut bound_names = HashSet::new();
+    for binding in &let_tuple.bindings
+    {
+        if !bound_names.insert(binding.name.content())
+        {
+            return Err(CompilerError::new(&binding.name, format!(
+                "'{}' is already bound in this let", binding.name.content())));
+        }
+    }
+
+    let tuple = gen.allocate_local(size_of(scope, &data_type)?);
+    let tuple_value = compile_expression(gen, scope, value)?;
+    gen.mov(tuple.clone(), tuple_value);
+
+    let offsets = tuple_item_offsets(scope, items)?;
+    for ((binding, item_type), offset) in let_tuple.bindings.iter().zip(items).zip(offsets)
+    {
+        let size = size_of(scope, item_type)?;
+        let tuple_ref = gen.ref_of(tuple.clone());
+        let item = gen.emit_struct_offset(offset as i32, size);
+        let item_ref = gen.field_ref(tuple_ref, item);
+        let item_value = gen.deref(item_ref, size);
+
+        let local = gen.allocate_local(size);
+        gen.mov(local.clone(), item_value);
+
+        let name = binding.name.content().to_owned();
+        if binding.is_mutable {
+            scope.put_mutable_value(name, local, item_type.clone());
+        } else {
+            scope.put_value(name, local, item_type.clone());
+        }
+    }
 
     Ok(())
 }
@@ -30,13 +198,24 @@ fn compile_return(gen: &mut IRGenorator,
                   return_to: Option<Rc<IRValue>>)
     -> Result<(), Box<dyn Error>>
 {
+    let return_type = match return_type
+    {
+        Some(return_type) => return_type,
+        None =>
+        {
+            return Err(CompilerError::new_optional_position(expression.token(),
+                "Can not return a value from a function with no return type".to_owned()));
+        },
+    };
+
+    let coerced_expression = coerce_literal(expression, return_type, None)?;
+    let expression = coerced_expression.as_ref().unwrap_or(expression);
     let data_type = derive_data_type(scope, &expression)?;
-    if return_type.is_some() && return_type.unwrap() != &data_type
+    if !return_type.accepts(&data_type)
     {
-        // FIXME: What happens if we don't have a token?
-        return Err(CompilerError::new(expression.token().unwrap(), format!(
+        return Err(CompilerError::new_optional_position(expression.token(), format!(
             "Can not return type '{:?}' from a function with return type '{:?}'",
-            data_type, return_type.unwrap())));
+            data_type, return_type)));
     }
 
     let value = compile_expression(gen, scope, expression)?;
@@ -56,14 +235,14 @@ fn compile_block(gen: &mut IRGenorator,
                  block: &Vec<Statement>,
                  return_type: Option<&DataType>,
                  return_to: Option<Rc<IRValue>>,
-                 end_label: Option<&str>)
+                 loop_labels: Option<LoopLabels>)
     -> Result<(), Box<dyn Error>>
 {
     let mut local_scope = Scope::new(Some(scope));
     for statement in block
     {
         compile_statement(gen, &mut local_scope,
-            statement, return_type, return_to.clone(), end_label)?;
+            statement, return_type, return_to.clone(), loop_labels)?;
     }
 
     for function in local_scope.used_functions() {
@@ -77,7 +256,7 @@ fn compile_if(gen: &mut IRGenorator,
               if_: &If,
               return_type: Option<&DataType>,
               return_to: Option<Rc<IRValue>>,
-              end_label: Option<&str>)
+              loop_labels: Option<LoopLabels>)
     -> Result<(), Box<dyn Error>>
 {
     let else_label = gen.create_label("else");
@@ -87,7 +266,7 @@ fn compile_if(gen: &mut IRGenorator,
     gen.goto_if_not(&else_label, condition_value);
 
     compile_block(gen, scope, &if_.block,
-        return_type, return_to.clone(), end_label)?;
+        return_type, return_to.clone(), loop_labels)?;
     gen.goto(&end_if_label);
 
     gen.emit_label(&else_label);
@@ -96,7 +275,7 @@ fn compile_if(gen: &mut IRGenorator,
         Some(block) =>
         {
             compile_block(gen, scope, block,
-                return_type, return_to, end_label)?
+                return_type, return_to, loop_labels)?
         },
         None => {},
     }
@@ -117,7 +296,7 @@ fn compile_loop(gen: &mut IRGenorator,
 
     gen.emit_label(&start_label);
     compile_block(gen, scope, block,
-        return_type, return_to, Some(&end_label))?;
+        return_type, return_to, Some(LoopLabels::new(&start_label, &end_label)))?;
     gen.goto(&start_label);
     gen.emit_label(&end_label);
 
@@ -141,17 +320,433 @@ fn compile_while(gen: &mut IRGenorator,
     gen.goto_if_not(&end_label, condition_value);
 
     compile_block(gen, scope, block,
-        return_type, return_to, Some(&end_label))?;
+        return_type, return_to, Some(LoopLabels::new(&start_label, &end_label)))?;
+    gen.goto(&start_label);
+    gen.emit_label(&end_label);
+
+    Ok(())
+}
+
+fn compile_range_for(gen: &mut IRGenorator,
+                     scope: &mut Scope,
+                     for_: &For,
+                     start: &Expression,
+                     end: &Expression,
+                     return_type: Option<&DataType>,
+                     return_to: Option<Rc<IRValue>>)
+    -> Result<(), Box<dyn Error>>
+{
+    let variable = &for_.variable;
+    for bound in [start, end]
+    {
+        let data_type = derive_data_type(scope, bound)?;
+        if data_type != DataType::Int
+        {
+            return Err(CompilerError::new(bound.token().unwrap_or(variable), format!(
+                "Range bounds must be of type 'int', got '{}'", data_type)));
+        }
+    }
+
+    let start_label = gen.create_label("for_start");
+    let next_label = gen.create_label("for_next");
+    let end_label = gen.create_label("for_end");
+
+    let index = gen.allocate_local(size_of(scope, &DataType::Int)?);
+    let start_value = compile_expression(gen, scope, start)?;
+    gen.mov(index.clone(), start_value);
+
+    let end_local = gen.allocate_local(size_of(scope, &DataType::Int)?);
+    let end_value = compile_expression(gen, scope, end)?;
+    gen.mov(end_local.clone(), end_value);
+
+    gen.emit_label(&start_label);
+    let condition = gen.less_than(index.clone(), end_local);
+    gen.goto_if_not(&end_label, condition);
+
+    let mut for_scope = Scope::new(Some(scope));
+    for_scope.put_value(variable.content().to_owned(), index.clone(), DataType::Int);
+    compile_block(gen, &mut for_scope, &for_.block,
+        return_type, return_to, Some(LoopLabels::new(&next_label, &end_label)))?;
+    for function in for_scope.used_functions() {
+        scope.put_used_function(function);
+    }
+
+    gen.emit_label(&next_label);
+    let one = gen.emit_int(1);
+    let next_index = gen.add(index.clone(), one);
+    gen.mov(index, next_index);
+    gen.goto(&start_label);
+    gen.emit_label(&end_label);
+
+    Ok(())
+}
+
+fn call_expression(name: &Token, function_name: &str, arguments: Vec<Expression>)
+    -> Expression
+{
+    Expression::Call(Call
+    {
+        callable: Box::from(Expression::Identifier(
+            name.with_content(TokenType::Identifier, function_name))),
+        arguments,
+        type_variable: None,
+    })
+}
+
+/// Iterate over anything with a length and an indexer. Arrays are
+/// indexed directly, anything else needs a `len` and `get` function.
+fn compile_collection_for(gen: &mut IRGenorator,
+                          scope: &mut Scope,
+                          for_: &For,
+                          collection: &Expression,
+                          return_type: Option<&DataType>,
+                          return_to: Option<Rc<IRValue>>)
+    -> Result<(), Box<dyn Error>>
+{
+    let variable = &for_.variable;
+    let data_type = derive_data_type(scope, collection)?;
+    let mut for_scope = Scope::new(Some(scope));
+
+    // NOTE: These names can't be written in source code, so
+    //       will never clash with a user defined variable.
+    let index_expression = Expression::Identifier(
+        variable.with_content(TokenType::Identifier, "for.index"));
+    let collection_expression = match collection
+    {
+        Expression::Identifier(_) => collection.clone(),
+        _ =>
+        {
+            let local = gen.allocate_local(size_of(&for_scope, &data_type)?);
+            let value = compile_expression(gen, &mut for_scope, collection)?;
+            gen.mov(local.clone(), value);
+            for_scope.put_value("for.collection".to_owned(), local, data_type.clone());
+            Expression::Identifier(
+                variable.with_content(TokenType::Identifier, "for.collection"))
+        },
+    };
+
+    let (length, item) = match &data_type
+    {
+        DataType::Array(_, size) =>
+        {
+            let item = Expression::Operation(Operation
+            {
+                operation_type: OperationType::Indexed,
+                lhs: Box::from(collection_expression),
+                rhs: Some(Box::from(index_expression.clone())),
+                token: variable.clone(),
+            });
+
+            (Expression::IntLiteral(*size as i32), item)
+        },
+
+        _ =>
+        {
+            let argument = match &data_type
+            {
+                DataType::Ref(_) | DataType::MutRef(_) => collection_expression,
+                _ =>
+                {
+                    Expression::Operation(Operation
+                    {
+                        operation_type: OperationType::Ref,
+                        lhs: Box::from(collection_expression),
+                        rhs: None,
+                        token: variable.clone(),
+                    })
+                },
+            };
+
+            let length = call_expression(variable, "len", vec![argument.clone()]);
+            let item = call_expression(variable, "get", vec![argument, index_expression.clone()]);
+            (length, item)
+        },
+    };
+
+    let start_label = gen.create_label("for_start");
+    let next_label = gen.create_label("for_next");
+    let end_label = gen.create_label("for_end");
+
+    let index = gen.allocate_local(size_of(&for_scope, &DataType::Int)?);
+    let zero = gen.emit_int(0);
+    gen.mov(index.clone(), zero);
+    for_scope.put_value("for.index".to_owned(), index.clone(), DataType::Int);
+
+    let length_local = gen.allocate_local(size_of(&for_scope, &DataType::Int)?);
+    let length_value = compile_expression(gen, &mut for_scope, &length)?;
+    gen.mov(length_local.clone(), length_value);
+
+    gen.emit_label(&start_label);
+    let condition = gen.less_than(index.clone(), length_local);
+    gen.goto_if_not(&end_label, condition);
+
+    {
+        let mut item_scope = Scope::new(Some(&for_scope));
+        compile_let(gen, &mut item_scope, &Let
+        {
+            name: variable.clone(),
+            is_mutable: false,
+            data_type: None,
+            value: Some(item),
+        })?;
+
+        compile_block(gen, &mut item_scope, &for_.block,
+            return_type, return_to, Some(LoopLabels::new(&next_label, &end_label)))?;
+        for function in item_scope.used_functions() {
+            for_scope.put_used_function(function);
+        }
+    }
+
+    gen.emit_label(&next_label);
+    let one = gen.emit_int(1);
+    let next_index = gen.add(index.clone(), one);
+    gen.mov(index, next_index);
     gen.goto(&start_label);
     gen.emit_label(&end_label);
 
+    for function in for_scope.used_functions() {
+        scope.put_used_function(function);
+    }
+    Ok(())
+}
+
+fn compile_for(gen: &mut IRGenorator,
+               scope: &mut Scope,
+               for_: &For,
+               return_type: Option<&DataType>,
+               return_to: Option<Rc<IRValue>>)
+    -> Result<(), Box<dyn Error>>
+{
+    match &for_.iterable
+    {
+        Iterable::Range(start, end) =>
+        {
+            compile_range_for(gen, scope, for_, start, end,
+                return_type, return_to)
+        },
+
+        Iterable::Collection(collection) =>
+        {
+            compile_collection_for(gen, scope, for_, collection,
+                return_type, return_to)
+        },
+    }
+}
+
+/// Every arm has to name a variant of the enum once, with a binding for
+/// each value it holds. Unless there's an `else`, every variant has to
+/// have an arm.
+fn check_match_arms(match_: &Match,
+                    enum_type: &DataType,
+                    variants: &[(String, Vec<DataType>)])
+    -> Result<(), Box<dyn Error>>
+{
+    let mut matched_variants = HashSet::new();
+    for arm in &match_.arms
+    {
+        let variant_name = arm.variant.content();
+        let variant_or_none = variants.iter().find(|(name, _)| name == variant_name);
+        if variant_or_none.is_none()
+        {
+            return Err(CompilerError::new(&arm.variant, format!(
+                "Could not find variant '{}' in enum '{}'", variant_name, enum_type)));
+        }
+
+        if !matched_variants.insert(variant_name)
+        {
+            return Err(CompilerError::new(&arm.variant, format!(
+                "Variant '{}' is already matched", variant_name)));
+        }
+
+        let (_, fields) = variant_or_none.unwrap();
+        match &arm.bindings
+        {
+            Some(bindings) if bindings.len() != fields.len() =>
+            {
+                return Err(CompilerError::new(&arm.variant, format!(
+                    "Variant '{}' holds {} values, got {}",
+                    variant_name, fields.len(), bindings.len())));
+            },
+            _ => {},
+        }
+    }
+
+    let missing_variants = variants
+        .iter()
+        .filter(|(name, _)| !matched_variants.contains(name.as_str()))
+        .map(|(name, _)| format!("'{}'", name))
+        .collect::<Vec<_>>();
+    if match_.else_block.is_none() && !missing_variants.is_empty()
+    {
+        return Err(CompilerError::new(&match_.token, format!(
+            "Match on '{}' does not handle {}",
+            enum_type, missing_variants.join(", "))));
+    }
+
     Ok(())
 }
 
-fn compile_break(gen: &mut IRGenorator, loop_end: Option<&str>)
+/// Copy the values held by the matched variant into new variables.
+fn compile_match_bindings(gen: &mut IRGenorator,
+                          scope: &mut Scope,
+                          value_ref: &Expression,
+                          bindings: &[Token],
+                          fields: &[DataType])
+    -> Result<(), Box<dyn Error>>
 {
-    assert!(loop_end.is_some());
-    gen.goto(loop_end.unwrap());
+    let offsets = variant_field_offsets(scope, fields)?;
+    for ((binding, field_type), offset) in bindings.iter().zip(fields).zip(offsets)
+    {
+        let size = size_of(scope, field_type)?;
+        let enum_ref = compile_expression(gen, scope, value_ref)?;
+        let field = gen.emit_struct_offset(offset as i32, size);
+        let field_ref = gen.field_ref(enum_ref, field);
+        let value = gen.deref(field_ref, size);
+
+        let local = gen.allocate_local(size);
+        gen.mov(local.clone(), value);
+        if !scope.put_value(binding.content().to_owned(), local, field_type.clone())
+        {
+            return Err(CompilerError::new(binding, format!(
+                "'{}' is already bound in this arm", binding.content())));
+        }
+    }
+
+    Ok(())
+}
+
+fn compile_match(gen: &mut IRGenorator,
+                 scope: &mut Scope,
+                 match_: &Match,
+                 return_type: Option<&DataType>,
+                 return_to: Option<Rc<IRValue>>,
+                 loop_labels: Option<LoopLabels>)
+    -> Result<(), Box<dyn Error>>
+{
+    // NOTE: Like field access, a ref to an enum can be matched on directly.
+    let data_type = derive_data_type(scope, &match_.value)?;
+    let enum_type = match &data_type
+    {
+        DataType::Ref(ref_type) | DataType::MutRef(ref_type) => *ref_type.clone(),
+        _ => data_type.clone(),
+    };
+
+    let variants_or_none = enum_variants(scope, &enum_type);
+    if variants_or_none.is_none()
+    {
+        return Err(CompilerError::new(&match_.token, format!(
+            "Can not match on value of type '{}'", data_type)));
+    }
+
+    let variants = variants_or_none.unwrap();
+    check_match_arms(match_, &enum_type, &variants)?;
+
+    // NOTE: The value is only worked out once. Like `for.collection`,
+    //       this name can't be written in source code.
+    let mut match_scope = Scope::new(Some(scope));
+    let value_expression = match &match_.value
+    {
+        Expression::Identifier(_) => match_.value.clone(),
+        _ =>
+        {
+            let local = gen.allocate_local(size_of(&match_scope, &data_type)?);
+            let value = compile_expression(gen, &mut match_scope, &match_.value)?;
+            gen.mov(local.clone(), value);
+            match_scope.put_value("match.value".to_owned(), local, data_type.clone());
+            Expression::Identifier(
+                match_.token.with_content(TokenType::Identifier, "match.value"))
+        },
+    };
+
+    let value_ref = match &data_type
+    {
+        DataType::Ref(_) | DataType::MutRef(_) => value_expression,
+        _ =>
+        {
+            Expression::Operation(Operation
+            {
+                operation_type: OperationType::Ref,
+                lhs: Box::from(value_expression),
+                rhs: None,
+                token: match_.token.clone(),
+            })
+        },
+    };
+
+    let end_label = gen.create_label("match_end");
+    for arm in &match_.arms
+    {
+        let next_label = gen.create_label("match_next");
+        let (tag, (_, fields)) = variants
+            .iter()
+            .enumerate()
+            .find(|(_, (name, _))| name == arm.variant.content())
+            .unwrap();
+
+        let enum_ref = compile_expression(gen, &mut match_scope, &value_ref)?;
+        let value_tag = gen.deref(enum_ref, ENUM_TAG_SIZE);
+        let arm_tag = gen.emit_int(tag as i32);
+        let condition = gen.equals(value_tag, arm_tag);
+        gen.goto_if_not(&next_label, condition);
+
+        {
+            let mut arm_scope = Scope::new(Some(&match_scope));
+            if let Some(bindings) = &arm.bindings {
+                compile_match_bindings(gen, &mut arm_scope, &value_ref, bindings, fields)?;
+            }
+
+            compile_block(gen, &mut arm_scope, &arm.block,
+                return_type, return_to.clone(), loop_labels)?;
+            for function in arm_scope.used_functions() {
+                match_scope.put_used_function(function);
+            }
+        }
+
+        gen.goto(&end_label);
+        gen.emit_label(&next_label);
+    }
+
+    if let Some(block) = &match_.else_block
+    {
+        compile_block(gen, &mut match_scope, block,
+            return_type, return_to, loop_labels)?;
+    }
+    gen.emit_label(&end_label);
+
+    for function in match_scope.used_functions() {
+        scope.put_used_function(function);
+    }
+    Ok(())
+}
+
+fn compile_break(gen: &mut IRGenorator, token: &Token, loop_labels: Option<LoopLabels>)
+    -> Result<(), Box<dyn Error>>
+{
+    match loop_labels
+    {
+        Some(loop_labels) =>
+        {
+            gen.goto(loop_labels.end_label);
+            Ok(())
+        },
+
+        None => Err(CompilerError::new(token, "Can not break outside of a loop".to_owned())),
+    }
+}
+
+fn compile_continue(gen: &mut IRGenorator, token: &Token, loop_labels: Option<LoopLabels>)
+    -> Result<(), Box<dyn Error>>
+{
+    match loop_labels
+    {
+        Some(loop_labels) =>
+        {
+            gen.goto(loop_labels.continue_label);
+            Ok(())
+        },
+
+        None => Err(CompilerError::new(token, "Can not continue outside of a loop".to_owned())),
+    }
 }
 
 pub fn compile_statement(gen: &mut IRGenorator,
@@ -159,7 +754,7 @@ pub fn compile_statement(gen: &mut IRGenorator,
                          statement: &Statement,
                          return_type: Option<&DataType>,
                          return_to: Option<Rc<IRValue>>,
-                         loop_end: Option<&str>)
+                         loop_labels: Option<LoopLabels>)
     -> Result<(), Box<dyn Error>>
 {
     match statement
@@ -170,8 +765,11 @@ pub fn compile_statement(gen: &mut IRGenorator,
         Statement::Let(let_) =>
             compile_let(gen, scope, let_)?,
 
+        Statement::LetTuple(let_tuple) =>
+            compile_let_tuple(gen, scope, let_tuple)?,
+
         Statement::If(if_) =>
-            compile_if(gen, scope, if_, return_type, return_to, loop_end)?,
+            compile_if(gen, scope, if_, return_type, return_to, loop_labels)?,
 
         Statement::Return(expression) =>
             compile_return(gen, scope, expression, return_type, return_to)?,
@@ -182,8 +780,17 @@ pub fn compile_statement(gen: &mut IRGenorator,
         Statement::While(condition, block) =>
             compile_while(gen, scope, condition, block, return_type, return_to)?,
 
-        Statement::Break =>
-            compile_break(gen, loop_end),
+        Statement::For(for_) =>
+            compile_for(gen, scope, for_, return_type, return_to)?,
+
+        Statement::Match(match_) =>
+            compile_match(gen, scope, match_, return_type, return_to, loop_labels)?,
+
+        Statement::Break(token) =>
+            compile_break(gen, token, loop_labels)?,
+
+        Statement::Continue(token) =>
+            compile_continue(gen, token, loop_labels)?,
     };
 
     Ok(())
@@ -0,0 +1,427 @@
+use super::name_table::{Scope, FunctionDescriptionType, TraitType, TraitFunctionType};
+use super::data_type::{doas_type_exist, type_variable_name};
+use super::data_type::{type_key, resolve_type_name, resolve_type_names};
+use super::function::is_function_visible;
+use super::error::CompilerError;
+use crate::tokenizer::Token;
+use crate::ast::{Trait, Struct};
+use crate::data_type::{DataType, DataTypeDescription};
+use std::collections::HashMap;
+use std::error::Error;
+
+fn type_arguments(data_type: &DataType) -> Vec<DataType>
+{
+    match data_type
+    {
+        DataType::Generic(argument, name) =>
+        {
+            let mut arguments = type_arguments(argument);
+            arguments.push(DataType::Struct(name.clone()));
+            arguments
+        },
+
+        other => vec![other.clone()],
+    }
+}
+
+/// Split a reference to a trait, like `int T Indexable`, into
+/// the name of the trait and its type arguments.
+pub fn trait_reference(data_type: &DataType) -> Option<(String, Vec<DataType>)>
+{
+    match data_type
+    {
+        DataType::Struct(name) => Some((name.clone(), Vec::new())),
+        DataType::Generic(argument, name) => Some((name.clone(), type_arguments(argument))),
+        _ => None,
+    }
+}
+
+fn substitute(data_type: &DataType,
+              trait_name: &str,
+              self_type: &DataType,
+              variables: &HashMap<String, DataType>)
+    -> DataType
+{
+    let substitute_inner = |inner: &DataType|
+        Box::from(substitute(inner, trait_name, self_type, variables));
+
+    match data_type
+    {
+        DataType::Struct(name) if name == trait_name => self_type.clone(),
+        DataType::Generic(_, name) if name == trait_name => self_type.clone(),
+
+        DataType::Struct(name) =>
+        {
+            match variables.get(name)
+            {
+                Some(value) => value.clone(),
+                None => data_type.clone(),
+            }
+        },
+
+        DataType::Generic(argument, name) =>
+            DataType::Generic(substitute_inner(argument), name.clone()),
+
+        DataType::Array(item_type, size) =>
+            DataType::Array(substitute_inner(item_type), *size),
+
+        DataType::Ref(ref_type) =>
+            DataType::Ref(substitute_inner(ref_type)),
+
+        DataType::MutRef(ref_type) =>
+            DataType::MutRef(substitute_inner(ref_type)),
+
+        DataType::Tuple(items) =>
+        {
+            DataType::Tuple(items
+                .iter()
+                .map(|item| substitute(item, trait_name, self_type, variables))
+                .collect())
+        },
+
+        DataType::Function(params, return_type) =>
+        {
+            DataType::Function(params
+                .iter()
+                .map(|param| substitute(param, trait_name, self_type, variables))
+                .collect(),
+                return_type.as_deref().map(substitute_inner))
+        },
+
+        other => other.clone(),
+    }
+}
+
+fn generic_type_variable_name<'a>(scope: &Scope,
+                                  description: &'a FunctionDescriptionType)
+    -> Option<&'a str>
+{
+    match &description.type_variable
+    {
+        Some(DataType::Struct(name)) if !doas_type_exist(scope, &DataType::Struct(name.clone())) =>
+            Some(name),
+        _ => None,
+    }
+}
+
+fn provides_function(scope: &Scope,
+                     name: &str,
+                     params: &Vec<DataType>,
+                     return_type: &Option<DataType>)
+    -> bool
+{
+    scope.lookup_function_descriptions(name)
+        .iter()
+        .any(|description|
+    {
+        if description.params.len() != params.len() {
+            return false;
+        }
+        let mut defining_types = params.clone();
+        defining_types.extend(return_type.clone());
+        if !is_function_visible(scope, description, &defining_types) {
+            return false;
+        }
+
+        let type_variable = generic_type_variable_name(scope, description);
+        let params_match = description.params
+            .iter()
+            .zip(params)
+            .all(|(param, expected)| param.matches(expected, &type_variable).0);
+
+        let return_type_matches = match (&description.return_type, return_type)
+        {
+            (None, None) => true,
+            (Some(actual), Some(expected)) =>
+            {
+                DataTypeDescription::Exact(actual.clone())
+                    .matches(expected, &type_variable).0
+            },
+            _ => false,
+        };
+
+        params_match && return_type_matches
+    })
+}
+
+fn show_trait_function(function: &TraitFunctionType,
+                       params: &[DataType],
+                       return_type: &Option<DataType>)
+    -> String
+{
+    let params = params
+        .iter()
+        .map(|param| param.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match return_type
+    {
+        Some(return_type) => format!("fun {}({}) -> {}", function.name, params, return_type),
+        None => format!("fun {}({})", function.name, params),
+    }
+}
+
+fn find_missing_functions(scope: &Scope,
+                          data_type: &DataType,
+                          trait_name: &str,
+                          arguments: &[DataType],
+                          missing: &mut Vec<String>)
+    -> Result<(), Box<dyn Error>>
+{
+    let trait_or_none = scope.lookup_trait(trait_name);
+    if trait_or_none.is_none()
+    {
+        return Err(CompilerError::new_no_position(format!(
+            "Could not find trait '{}'", trait_name)));
+    }
+
+    // NOTE: Type variables not given an argument can be anything.
+    let trait_ = trait_or_none.unwrap();
+    let mut variables = HashMap::new();
+    for (index, (variable, bounds)) in trait_.type_variables.iter().enumerate()
+    {
+        let value = arguments.get(index).cloned().unwrap_or(DataType::Any);
+        if value != DataType::Any
+        {
+            for bound in bounds {
+                find_missing_functions(scope, &value, bound, &Vec::new(), missing)?;
+            }
+        }
+
+        variables.insert(variable.clone(), value);
+    }
+
+    for function in &trait_.functions
+    {
+        let params = function.params
+            .iter()
+            .map(|param| substitute(param, trait_name, data_type, &variables))
+            .collect::<Vec<_>>();
+
+        let return_type = function.return_type
+            .as_ref()
+            .map(|return_type| substitute(return_type, trait_name, data_type, &variables));
+
+        if !provides_function(scope, &function.name, &params, &return_type) {
+            missing.push(show_trait_function(function, &params, &return_type));
+        }
+    }
+
+    for supertrait in &trait_.supertraits
+    {
+        let supertrait = substitute(supertrait, trait_name, data_type, &variables);
+        let (supertrait_name, supertrait_arguments) = trait_reference(&supertrait).unwrap();
+        find_missing_functions(scope, data_type,
+            &supertrait_name, &supertrait_arguments, missing)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the signatures of every function `data_type` would need to
+/// implement the trait `trait_name`.
+pub fn missing_trait_functions(scope: &Scope,
+                               data_type: &DataType,
+                               trait_name: &str,
+                               arguments: &[DataType])
+    -> Result<Vec<String>, Box<dyn Error>>
+{
+    let mut missing = Vec::new();
+    find_missing_functions(scope, data_type, trait_name, arguments, &mut missing)?;
+    missing.dedup();
+    Ok(missing)
+}
+
+pub fn check_implements_trait(scope: &Scope,
+                              data_type: &DataType,
+                              trait_name: &str,
+                              token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    let missing = missing_trait_functions(scope, data_type, trait_name, &Vec::new())?;
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    // NOTE: A reference to a type implementing a trait can be
+    //       used anywhere that trait is expected.
+    if let DataType::Ref(ref_type) | DataType::MutRef(ref_type) = data_type
+    {
+        if missing_trait_functions(scope, ref_type, trait_name, &Vec::new())?.is_empty() {
+            return Ok(());
+        }
+    }
+
+    Err(CompilerError::new(token, format!(
+        "Type '{}' does not implement trait '{}', missing '{}'",
+        data_type, trait_name, missing.join("', '"))))
+}
+
+pub fn check_trait_bounds(scope: &Scope,
+                          data_type: &DataType,
+                          bounds: &Vec<String>,
+                          token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    for bound in bounds {
+        check_implements_trait(scope, data_type, bound, token)?;
+    }
+
+    Ok(())
+}
+
+/// Find the trait `name` refers to from the current module, giving its key.
+pub fn resolve_trait_name(scope: &Scope, name: &str, token: &Token)
+    -> Result<String, Box<dyn Error>>
+{
+    let key = resolve_type_name(scope, name, Some(token))?;
+    if scope.lookup_trait(&key).is_none()
+    {
+        return Err(CompilerError::new(token, format!(
+            "Could not find trait '{}'", name)));
+    }
+
+    Ok(key)
+}
+
+pub fn register_trait(scope: &mut Scope, trait_: &Trait)
+    -> Result<(), Box<dyn Error>>
+{
+    let mut trait_scope = Scope::new(Some(scope));
+    trait_scope.set_module(trait_.module);
+
+    let mut type_variables = Vec::new();
+    for variable in &trait_.type_variables
+    {
+        let bounds = variable.bounds
+            .iter()
+            .map(|bound| resolve_type_name(&trait_scope, bound.content(), Some(bound)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        type_variables.push((variable.name.content().to_owned(), bounds));
+    }
+
+    let mut functions = Vec::new();
+    for function in &trait_.functions
+    {
+        let token = Some(&function.name);
+        let params = function.params
+            .iter()
+            .map(|param| resolve_type_names(&trait_scope, param.clone(), token))
+            .collect::<Result<Vec<_>, _>>()?;
+        let return_type = match &function.return_type
+        {
+            Some(return_type) => Some(resolve_type_names(&trait_scope, return_type.clone(), token)?),
+            None => None,
+        };
+
+        functions.push(TraitFunctionType
+        {
+            name: function.name.content().to_owned(),
+            params,
+            return_type,
+        });
+    }
+
+    let supertraits = trait_.supertraits
+        .iter()
+        .map(|supertrait| resolve_type_names(&trait_scope, supertrait.clone(), Some(&trait_.name)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let name = type_key(&trait_scope, trait_.name.content(), trait_.module);
+    scope.put_trait(name, TraitType
+    {
+        type_variables,
+        supertraits,
+        functions,
+    });
+    Ok(())
+}
+
+pub fn check_trait(scope: &Scope, trait_: &Trait)
+    -> Result<(), Box<dyn Error>>
+{
+    let mut trait_scope = Scope::new(Some(scope));
+    trait_scope.set_module(trait_.module);
+
+    for supertrait in &trait_.supertraits
+    {
+        match trait_reference(supertrait)
+        {
+            Some((name, _)) =>
+            {
+                resolve_trait_name(&trait_scope, &name, &trait_.name)?;
+            },
+
+            None =>
+            {
+                return Err(CompilerError::new(&trait_.name, format!(
+                    "'{}' is not a trait", supertrait)));
+            },
+        }
+    }
+
+    for variable in &trait_.type_variables
+    {
+        for bound in &variable.bounds {
+            resolve_trait_name(&trait_scope, bound.content(), bound)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks a struct provides every function of the traits it
+/// declares it implements.
+pub fn check_struct_traits(scope: &Scope, struct_: &Struct)
+    -> Result<(), Box<dyn Error>>
+{
+    let mut struct_scope = Scope::new(Some(scope));
+    struct_scope.set_module(struct_.module);
+
+    let name = type_key(scope, struct_.name.content(), struct_.module);
+    let mut variables = HashMap::new();
+    let self_type = match &struct_.type_variable
+    {
+        Some(type_variable) =>
+        {
+            variables.insert(type_variable_name(type_variable).to_owned(), DataType::Any);
+            DataType::Generic(Box::from(DataType::Any), name.clone())
+        },
+
+        None => DataType::Struct(name.clone()),
+    };
+
+    for implemented_trait in &struct_.traits
+    {
+        let reference_or_none = trait_reference(implemented_trait);
+        if reference_or_none.is_none()
+        {
+            return Err(CompilerError::new(&struct_.name, format!(
+                "'{}' is not a trait", implemented_trait)));
+        }
+
+        let (trait_name, arguments) = reference_or_none.unwrap();
+        let trait_name = resolve_trait_name(&struct_scope, &trait_name, &struct_.name)?;
+
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| resolve_type_names(&struct_scope, argument, Some(&struct_.name)))
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .map(|argument| substitute(argument, &name, &self_type, &variables))
+            .collect::<Vec<_>>();
+
+        let missing = missing_trait_functions(
+            &struct_scope, &self_type, &trait_name, &arguments)?;
+        if !missing.is_empty()
+        {
+            return Err(CompilerError::new(&struct_.name, format!(
+                "Struct '{}' does not implement trait '{}', missing '{}'",
+                name, trait_name, missing.join("', '"))));
+        }
+    }
+
+    Ok(())
+}
@@ -1,29 +1,210 @@
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DataType
 {
-    Null,
+    Any,
     Int,
+    I8,
+    I16,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
     Char,
     Bool,
     Struct(String),
     Array(Box<DataType>, usize),
+
+    // An array whose size is a named constant, like `int[CAPACITY]`.
+    // The optimizer replaces these with `Array` once constants are known.
+    ConstSizedArray(Box<DataType>, String),
+
     Ref(Box<DataType>),
+
+    // A ref that can be used to change the value it points to.
+    MutRef(Box<DataType>),
+
     Generic(Box<DataType>, String),
+
+    // A fixed group of values, like `(int, bool)`. Laid out like a
+    // struct with a field for each item.
+    Tuple(Vec<DataType>),
+
+    // The address of a function taking params of these types, like
+    // `fun(int) -> bool`. Functions without a return type have `None`.
+    Function(Vec<DataType>, Option<Box<DataType>>),
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Debug)]
 pub enum DataTypeDescription
 {
     Exact(DataType),
+    Trait(String),
     Any,
 }
 
+impl fmt::Display for DataType
+{
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self
+        {
+            Self::Any => write!(f, "any"),
+            Self::Int => write!(f, "int"),
+            Self::I8 => write!(f, "i8"),
+            Self::I16 => write!(f, "i16"),
+            Self::I64 => write!(f, "i64"),
+            Self::U8 => write!(f, "u8"),
+            Self::U16 => write!(f, "u16"),
+            Self::U32 => write!(f, "u32"),
+            Self::U64 => write!(f, "u64"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+            Self::Char => write!(f, "char"),
+            Self::Bool => write!(f, "bool"),
+            Self::Struct(name) => write!(f, "{}", name),
+            Self::Array(data_type, size) => write!(f, "{}[{}]", data_type, size),
+            Self::ConstSizedArray(data_type, size) => write!(f, "{}[{}]", data_type, size),
+            Self::Ref(data_type) => write!(f, "ref {}", data_type),
+            Self::MutRef(data_type) => write!(f, "ref mut {}", data_type),
+            Self::Generic(argument, name) => write!(f, "{} {}", argument, name),
+
+            Self::Tuple(items) =>
+            {
+                let items = items
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "({})", items.join(", "))
+            },
+
+            Self::Function(params, return_type) =>
+            {
+                let params = params
+                    .iter()
+                    .map(|param| param.to_string())
+                    .collect::<Vec<_>>();
+                write!(f, "fun({})", params.join(", "))?;
+                match return_type
+                {
+                    Some(return_type) => write!(f, " -> {}", return_type),
+                    None => Ok(()),
+                }
+            },
+        }
+    }
+
+}
+
+impl DataType
+{
+
+    /// The size in bytes of an integer type, and whether it's signed.
+    pub fn int_layout(&self) -> Option<(usize, bool)>
+    {
+        match self
+        {
+            Self::Int => Some((4, true)),
+            Self::I8 => Some((1, true)),
+            Self::I16 => Some((2, true)),
+            Self::I64 => Some((8, true)),
+            Self::U8 => Some((1, false)),
+            Self::U16 => Some((2, false)),
+            Self::U32 => Some((4, false)),
+            Self::U64 => Some((8, false)),
+            _ => None,
+        }
+    }
+
+    /// The size in bytes of a floating point type.
+    pub fn float_size(&self) -> Option<usize>
+    {
+        match self
+        {
+            Self::F32 => Some(4),
+            Self::F64 => Some(8),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of `data_type` can be used where this type is
+    /// expected. Like for arguments, a mutable ref can be used as a ref.
+    pub fn accepts(&self, data_type: &DataType) -> bool
+    {
+        data_type_matches(self, data_type, &None).0
+    }
+
+}
+
+/// The type an argument has once it's passed as `expected`, so a mutable
+/// ref passed as a ref is only a ref inside the function.
+fn coerce_refs(expected: &DataType, data_type: &DataType) -> DataType
+{
+    match (expected, data_type)
+    {
+        (DataType::Ref(expected_ref_type), DataType::Ref(ref_type) | DataType::MutRef(ref_type)) =>
+            DataType::Ref(Box::from(coerce_refs(expected_ref_type, ref_type))),
+        (DataType::MutRef(expected_ref_type), DataType::MutRef(ref_type)) =>
+            DataType::MutRef(Box::from(coerce_refs(expected_ref_type, ref_type))),
+        (DataType::Array(expected_item_type, _), DataType::Array(item_type, size)) =>
+            DataType::Array(Box::from(coerce_refs(expected_item_type, item_type)), *size),
+        (DataType::Generic(expected_argument, _), DataType::Generic(argument, name)) =>
+            DataType::Generic(Box::from(coerce_refs(expected_argument, argument)), name.clone()),
+        (DataType::Tuple(expected_items), DataType::Tuple(items)) =>
+        {
+            DataType::Tuple(expected_items.iter().zip(items)
+                .map(|(expected_item, item)| coerce_refs(expected_item, item))
+                .collect())
+        },
+        _ => data_type.clone(),
+    }
+}
+
+/// Match each type in `expected` against the one in the same place in
+/// `data_types`, where the type variable has to stand for the same type
+/// in every one it's used in.
+fn data_types_match<'a>(expected: impl Iterator<Item = &'a DataType>,
+                        data_types: impl Iterator<Item = &'a DataType>,
+                        type_variable: &Option<&str>)
+    -> (bool, Option<DataType>)
+{
+    let mut type_variable_value = None;
+    for (expected_item, item) in expected.zip(data_types)
+    {
+        let (matches, item_type_variable_value) =
+            data_type_matches(expected_item, item, type_variable);
+        if !matches {
+            return (false, None);
+        }
+
+        if let Some(value) = item_type_variable_value
+        {
+            if type_variable_value.is_some() && type_variable_value.as_ref() != Some(&value) {
+                return (false, None);
+            }
+            type_variable_value = Some(value);
+        }
+    }
+
+    (true, type_variable_value)
+}
+
 fn data_type_matches(expected: &DataType, data_type: &DataType, type_variable: &Option<&str>)
     -> (bool, Option<DataType>)
 {
+    if data_type == &DataType::Any {
+        return (true, None);
+    }
+
     match expected
     {
+        DataType::Any => (true, None),
+
         DataType::Struct(expected_name) =>
         {
             if type_variable.is_some() &&
@@ -55,11 +236,23 @@ fn data_type_matches(expected: &DataType, data_type: &DataType, type_variable: &
             }
         },
 
+        // NOTE: A mutable ref can be used anywhere a ref is expected,
+        //       but not the other way around.
         DataType::Ref(expected_ref_type) =>
         {
             match data_type
             {
-                DataType::Ref(ref_type) =>
+                DataType::Ref(ref_type) | DataType::MutRef(ref_type) =>
+                    data_type_matches(expected_ref_type, ref_type, type_variable),
+                _ => (false, None),
+            }
+        },
+
+        DataType::MutRef(expected_ref_type) =>
+        {
+            match data_type
+            {
+                DataType::MutRef(ref_type) =>
                     data_type_matches(expected_ref_type, ref_type, type_variable),
                 _ => (false, None),
             }
@@ -91,6 +284,35 @@ fn data_type_matches(expected: &DataType, data_type: &DataType, type_variable: &
             }
         },
 
+        DataType::Tuple(expected_items) =>
+        {
+            match data_type
+            {
+                DataType::Tuple(items) if items.len() == expected_items.len() =>
+                    data_types_match(expected_items.iter(), items.iter(), type_variable),
+                _ => (false, None),
+            }
+        },
+
+        DataType::Function(expected_params, expected_return_type) =>
+        {
+            let (params, return_type) = match data_type
+            {
+                DataType::Function(params, return_type)
+                    if params.len() == expected_params.len() &&
+                       return_type.is_some() == expected_return_type.is_some() =>
+                {
+                    (params, return_type)
+                },
+                _ => return (false, None),
+            };
+
+            data_types_match(
+                expected_params.iter().chain(expected_return_type.as_deref()),
+                params.iter().chain(return_type.as_deref()),
+                type_variable)
+        },
+
         other => (other == data_type, None),
     }
 }
@@ -106,6 +328,21 @@ impl DataTypeDescription
             DataTypeDescription::Exact(expected) =>
                 data_type_matches(expected, data_type, type_variable),
             DataTypeDescription::Any => (true, None),
+
+            // NOTE: Only the compiler knows which types implement a
+            //       trait, so it has to check these itself.
+            DataTypeDescription::Trait(_) => (false, None),
+        }
+    }
+
+    /// The type a param has in the instance of a function called with
+    /// an argument of `data_type`, which has to match it.
+    pub fn param_type(&self, data_type: &DataType) -> DataType
+    {
+        match self
+        {
+            DataTypeDescription::Exact(expected) => coerce_refs(expected, data_type),
+            _ => data_type.clone(),
         }
     }
 
@@ -27,14 +27,33 @@ impl fmt::Display for IRStorage
 
 }
 
+/// The number of bytes an argument takes up on the stack. Like cdecl,
+/// each one is padded to a multiple of 4 bytes, so small values can be
+/// passed to C functions and params stay aligned.
+pub fn argument_size(size: usize) -> usize
+{
+    size.next_multiple_of(4)
+}
+
 #[derive(Clone)]
 pub enum IROperation
 {
     Add,
     Subtract,
     Multiply,
+    Divide,
+    Modulo,
+    And,
+    Or,
+    Xor,
+    ShiftLeft,
+    ShiftRight,
     GreaterThan,
     LessThan,
+    GreaterThanEquals,
+    LessThanEquals,
+    Equals,
+    NotEquals,
 }
 
 impl fmt::Display for IROperation
@@ -47,39 +66,102 @@ impl fmt::Display for IROperation
             Self::Add => write!(f, "add"),
             Self::Subtract => write!(f, "subtract"),
             Self::Multiply => write!(f, "multiply"),
+            Self::Divide => write!(f, "divide"),
+            Self::Modulo => write!(f, "modulo"),
+            Self::And => write!(f, "and"),
+            Self::Or => write!(f, "or"),
+            Self::Xor => write!(f, "xor"),
+            Self::ShiftLeft => write!(f, "shift left"),
+            Self::ShiftRight => write!(f, "shift right"),
             Self::GreaterThan => write!(f, "greater than"),
             Self::LessThan => write!(f, "less than"),
+            Self::GreaterThanEquals => write!(f, "greater than equals"),
+            Self::LessThanEquals => write!(f, "less than equals"),
+            Self::Equals => write!(f, "equals"),
+            Self::NotEquals => write!(f, "not equals"),
         }
     }
 
 }
 
+impl IROperation
+{
+
+    /// Comparisons give a bool, rather than a value of their operands' type.
+    pub fn is_comparison(&self) -> bool
+    {
+        matches!(self,
+            Self::GreaterThan | Self::LessThan |
+            Self::GreaterThanEquals | Self::LessThanEquals |
+            Self::Equals | Self::NotEquals)
+    }
+
+}
+
 #[derive(Clone)]
 pub enum IR
 {
     AllocateRegister(IRRegister, usize),
     FreeRegister(IRRegister),
 
+    SetI64(IRStorage, i64),
     SetI32(IRStorage, i32),
+    SetI16(IRStorage, i16),
     SetI8(IRStorage, i8),
-    SetString(IRStorage, String),
+    SetF64(IRStorage, f64),
+    SetF32(IRStorage, f32),
+    SetString(IRStorage, Vec<u8>),
     SetRef(IRStorage, IRStorage),
+    SetFunction(IRStorage, String),
     Deref(IRStorage, IRStorage, usize),
+    Store(IRStorage, IRStorage, usize),
     Move(IRStorage, IRStorage, usize),
     MoveToOffset(usize, IRStorage, IRStorage, usize),
+    Zero(IRStorage, usize),
+
+    // Resize an int, extending the sign if it's signed.
+    ConvertInt(IRStorage, usize, IRStorage, usize, bool),
+
+    // Convert between `f32` and `f64`, or between a float and a 32 or
+    // 64-bit int, which is signed or not. Floats become ints by
+    // rounding towards zero.
+    ConvertFloat(IRStorage, usize, IRStorage, usize),
+    IntToFloat(IRStorage, usize, IRStorage, usize, bool),
+    FloatToInt(IRStorage, usize, IRStorage, usize, bool),
+
     // MoveFromOffset(IRStorage, usize, IRStorage, usize),
     PushI32(i32),
     PushI8(i8),
-    PushString(String),
+    PushString(Vec<u8>),
     Push(IRStorage, usize),
     Pop(usize),
 
     I32ConstantOperation(IROperation, IRStorage, IRStorage, i32),
     I32Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    I8ConstantOperation(IROperation, IRStorage, IRStorage, i8),
+    I8Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    I32Negate(IRStorage, IRStorage),
+    U32Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    I64Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    U64Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    I64Negate(IRStorage, IRStorage),
+    F32Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    F64Operation(IROperation, IRStorage, IRStorage, IRStorage),
+    F32Negate(IRStorage, IRStorage),
+    F64Negate(IRStorage, IRStorage),
 
     Call(String, IRStorage, usize),
+
+    // Call the function whose address is held in the first storage.
+    CallIndirect(IRStorage, IRStorage, usize),
+
+    // C functions give floats back somewhere other values aren't,
+    // so they need moving into the return value after the call.
+    ExternFloatReturn(IRStorage, usize),
+
     Label(String),
     Goto(String),
+    GotoIf(String, IRStorage),
     GotoIfNot(String, IRStorage),
     Return(IRStorage, usize),
 }
@@ -93,24 +175,56 @@ impl fmt::Display for IR
         {
             Self::AllocateRegister(register, size) => write!(f, "allocate {}, {}", register, size),
             Self::FreeRegister(register) => write!(f, "free {}", register),
+            Self::SetI64(storage, i) => write!(f, "set {}, {}", storage, i),
             Self::SetI32(storage, i) => write!(f, "set {}, {}", storage, i),
+            Self::SetI16(storage, i) => write!(f, "set {}, {}", storage, i),
             Self::SetI8(storage, i) => write!(f, "set {}, {}", storage, i),
-            Self::SetString(storage, s) => write!(f, "set {}, {}", storage, s),
+            Self::SetF64(storage, x) => write!(f, "set {}, {:?}", storage, x),
+            Self::SetF32(storage, x) => write!(f, "set {}, {:?}", storage, x),
+            Self::SetString(storage, s) => write!(f, "set {}, {}", storage, String::from_utf8_lossy(s)),
             Self::SetRef(to, value) => write!(f, "set {}, ref {}", to, value),
+            Self::SetFunction(to, function) => write!(f, "set {}, fun {}", to, function),
             Self::Deref(to, value, size) => write!(f, "set {}, deref {} {}", to, value, size),
+            Self::Store(address, value, size) => write!(f, "set deref {}, {} {}", address, value, size),
             Self::Move(from, to, size) => write!(f, "move {}, {}, {}", from, to, size),
             Self::MoveToOffset(offset, from, to, size) => write!(f, "move {}[{}], {}, {}", from, offset, to, size),
+            Self::Zero(to, size) => write!(f, "zero {}, {}", to, size),
+            Self::ConvertInt(to, to_size, from, from_size, is_signed) =>
+                write!(f, "convert {} {}, {} {}{}", to, to_size, from, from_size,
+                    if *is_signed { " signed" } else { "" }),
+            Self::ConvertFloat(to, to_size, from, from_size) =>
+                write!(f, "convert float {} {}, {} {}", to, to_size, from, from_size),
+            Self::IntToFloat(to, to_size, from, from_size, is_signed) =>
+                write!(f, "convert int to float {} {}, {} {}{}", to, to_size, from, from_size,
+                    if *is_signed { " signed" } else { "" }),
+            Self::FloatToInt(to, to_size, from, from_size, is_signed) =>
+                write!(f, "convert float to int {} {}, {} {}{}", to, to_size, from, from_size,
+                    if *is_signed { " signed" } else { "" }),
             // Self::MoveFromOffset(from, offset, to, size) => write!(f, "move {}, {}[{}], {}", from, to, offset, size),
             Self::PushI32(value) => write!(f, "push {}", value),
             Self::PushI8(value) => write!(f, "push {}", value),
-            Self::PushString(value) => write!(f, "push '{}'", value),
+            Self::PushString(value) => write!(f, "push '{}'", String::from_utf8_lossy(value)),
             Self::Push(value, size) => write!(f, "push {}, {}", value, size),
             Self::Pop(count) => write!(f, "pop {}", count),
             Self::I32ConstantOperation(op, to, lhs, rhs) => write!(f, "{} {}, {}, {}", op, to, lhs, rhs),
             Self::I32Operation(op, to, lhs, rhs) => write!(f, "{} {}, {}, {}", op, to, lhs, rhs),
+            Self::I8ConstantOperation(op, to, lhs, rhs) => write!(f, "{} {}, {}, {}", op, to, lhs, rhs),
+            Self::I8Operation(op, to, lhs, rhs) => write!(f, "{} {}, {}, {}", op, to, lhs, rhs),
+            Self::I32Negate(to, value) => write!(f, "negate {}, {}", to, value),
+            Self::U32Operation(op, to, lhs, rhs) => write!(f, "unsigned {} {}, {}, {}", op, to, lhs, rhs),
+            Self::I64Operation(op, to, lhs, rhs) => write!(f, "{} {}, {}, {}", op, to, lhs, rhs),
+            Self::U64Operation(op, to, lhs, rhs) => write!(f, "unsigned {} {}, {}, {}", op, to, lhs, rhs),
+            Self::I64Negate(to, value) => write!(f, "negate {}, {}", to, value),
+            Self::F32Operation(op, to, lhs, rhs) => write!(f, "float {} {}, {}, {}", op, to, lhs, rhs),
+            Self::F64Operation(op, to, lhs, rhs) => write!(f, "float {} {}, {}, {}", op, to, lhs, rhs),
+            Self::F32Negate(to, value) => write!(f, "float negate {}, {}", to, value),
+            Self::F64Negate(to, value) => write!(f, "float negate {}, {}", to, value),
             Self::Call(function, return_value, size) => write!(f, "call {}, {}, {}", function, return_value, size),
+            Self::CallIndirect(function, return_value, size) => write!(f, "call deref {}, {}, {}", function, return_value, size),
+            Self::ExternFloatReturn(return_value, size) => write!(f, "extern float return {}, {}", return_value, size),
             Self::Label(label) => write!(f, "{}:", label),
             Self::Goto(label) => write!(f, "goto {}", label),
+            Self::GotoIf(label, condition) => write!(f, "goto if {}, {}", label, condition),
             Self::GotoIfNot(label, condition) => write!(f, "goto if not {}, {}", label, condition),
             Self::Return(value, size) => write!(f, "return {}, {}", value, size),
         }
@@ -0,0 +1,214 @@
+use crate::parser::parse;
+use crate::tokenizer::Token;
+use crate::ast::{SourceFile, Use, Module, ModuleId};
+use std::fmt;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::error::Error;
+
+const MODULE_EXTENSION: &str = "fun";
+
+#[derive(Debug)]
+struct LoaderError
+{
+    token: Token,
+    message: String,
+}
+
+impl LoaderError
+{
+
+    fn new(token: &Token, message: String) -> Box<Self>
+    {
+        Box::from(Self
+        {
+            token: token.clone(),
+            message,
+        })
+    }
+
+}
+
+impl fmt::Display for LoaderError
+{
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        writeln!(f, "{}: {}", self.token.position(), self.message)?;
+        write!(f, "{}", self.token.show())
+    }
+
+}
+
+impl Error for LoaderError {}
+
+struct Loader
+{
+    loaded: HashMap<PathBuf, ModuleId>,
+    loading: Vec<PathBuf>,
+    output: SourceFile,
+}
+
+fn module_name(use_: &Use) -> String
+{
+    use_.path
+        .iter()
+        .map(|token| token.content())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// The directory `use std::...` paths fall back to when they can't be
+/// found next to the importing file. This is the closest directory above
+/// the compiler's executable with a `std` directory in it, and can be
+/// overridden with `FUN_ROOT`.
+fn std_root() -> Option<PathBuf>
+{
+    if let Ok(root) = env::var("FUN_ROOT") {
+        return Some(PathBuf::from(root));
+    }
+
+    let executable = env::current_exe().ok()?;
+    executable
+        .ancestors()
+        .skip(1)
+        .find(|directory| directory.join("std").is_dir())
+        .map(Path::to_owned)
+}
+
+fn resolve_module(importer: &Path, use_: &Use)
+    -> Result<PathBuf, Box<dyn Error>>
+{
+    let mut relative_path = PathBuf::new();
+    for token in &use_.path {
+        relative_path.push(token.content());
+    }
+    relative_path.set_extension(MODULE_EXTENSION);
+
+    let importer_directory = importer.parent().unwrap_or(Path::new("."));
+    for directory in std::iter::once(importer_directory.to_owned()).chain(std_root())
+    {
+        let module_path = directory.join(&relative_path);
+        if module_path.is_file() {
+            return Ok(module_path);
+        }
+    }
+
+    Err(LoaderError::new(&use_.path[0], format!(
+        "Could not find module '{}'", module_name(use_))))
+}
+
+impl Loader
+{
+
+    fn append(&mut self, module: ModuleId, source_file: SourceFile)
+    {
+        for mut function in source_file.functions
+        {
+            function.module = module;
+            self.output.functions.push(function);
+        }
+
+        for mut struct_ in source_file.structs
+        {
+            struct_.module = module;
+            self.output.structs.push(struct_);
+        }
+
+        for mut enum_ in source_file.enums
+        {
+            enum_.module = module;
+            self.output.enums.push(enum_);
+        }
+
+        for mut trait_ in source_file.traits
+        {
+            trait_.module = module;
+            self.output.traits.push(trait_);
+        }
+
+        for mut const_ in source_file.consts
+        {
+            const_.module = module;
+            self.output.consts.push(const_);
+        }
+
+        for extern_ in source_file.externs
+        {
+            let is_declared = self.output.externs
+                .iter()
+                .any(|x| x.content() == extern_.content());
+
+            if !is_declared {
+                self.output.externs.push(extern_);
+            }
+        }
+    }
+
+    fn load_module(&mut self, name: String, module_path: PathBuf)
+        -> Result<ModuleId, Box<dyn Error>>
+    {
+        let source_file = parse(&module_path)?;
+        let canonical_path = module_path.canonicalize()?;
+        let module = self.output.modules.len();
+        self.output.modules.push(Module
+        {
+            name,
+            imports: Vec::new(),
+            exports: Vec::new(),
+        });
+
+        self.loading.push(canonical_path.clone());
+        for use_ in &source_file.uses
+        {
+            let dependency_path = resolve_module(&module_path, use_)?;
+            let canonical_dependency_path = dependency_path.canonicalize()?;
+            if self.loading.contains(&canonical_dependency_path)
+            {
+                let cycle = self.loading
+                    .iter()
+                    .skip_while(|path| path != &&canonical_dependency_path)
+                    .chain(std::iter::once(&canonical_dependency_path))
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>();
+
+                return Err(LoaderError::new(&use_.path[0], format!(
+                    "Import cycle detected: {}", cycle.join(" -> "))));
+            }
+
+            let dependency = match self.loaded.get(&canonical_dependency_path)
+            {
+                Some(dependency) => *dependency,
+                None => self.load_module(module_name(use_), dependency_path)?,
+            };
+
+            self.output.modules[module].imports.push(dependency);
+            if use_.is_exported {
+                self.output.modules[module].exports.push(dependency);
+            }
+        }
+
+        self.loading.pop();
+        self.loaded.insert(canonical_path, module);
+        self.append(module, source_file);
+        Ok(module)
+    }
+
+}
+
+pub fn load(source_file_path: impl AsRef<Path>)
+    -> Result<SourceFile, Box<dyn Error>>
+{
+    let mut loader = Loader
+    {
+        loaded: HashMap::new(),
+        loading: Vec::new(),
+        output: SourceFile::default(),
+    };
+
+    let root_path = source_file_path.as_ref();
+    let root_name = root_path.file_stem().unwrap().to_string_lossy();
+    loader.load_module(root_name.into_owned(), root_path.to_owned())?;
+    Ok(loader.output)
+}
@@ -2,11 +2,12 @@ mod tokenizer;
 mod data_type;
 mod ast;
 mod parser;
+mod loader;
 mod intermediate;
 mod compiler;
 mod optimizer;
 mod code_generator;
-use parser::parse;
+use loader::load;
 use compiler::compile;
 use optimizer::optimize;
 use code_generator::x86;
@@ -25,14 +26,18 @@ fn main()
     }
 
     let source_file_path = &args[1];
-    let ast_or_error = parse(source_file_path);
+    let ast_or_error = load(source_file_path);
     if ast_or_error.is_err() {
         eprintln!("Error: {}", ast_or_error.unwrap_err());
         exit(1);
     }
 
     let mut ast = ast_or_error.unwrap();
-    optimize(&mut ast);
+    if let Err(err) = optimize(&mut ast)
+    {
+        eprintln!("Error: {}", err);
+        exit(1);
+    }
 
     match compile(ast)
     {
@@ -1,53 +1,235 @@
-use crate::ast::{SourceFile, Function, Statement, If};
-use crate::ast::{Expression, Operation, OperationType};
+use crate::ast::{SourceFile, Function, Statement, Let, LetTuple, If, For, Iterable, Match, Const};
+use crate::ast::{Expression, Operation, OperationType, Call, ModuleId};
+use crate::compiler::error::CompilerError;
+use crate::tokenizer::Token;
+use crate::data_type::{DataType, DataTypeDescription};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
 
+#[derive(Clone, Copy)]
 enum PreComputedValue
 {
     Int(i32),
     Bool(bool),
+
+    // The value of a constant declared with a sized type, like
+    // `const X: u8 = 0`. It's left for the compiler to fold, so
+    // the value keeps that type.
+    TypedInt(i32),
+
     Unkown,
 }
 
-fn pre_compute_operation(operation: &mut Operation) -> PreComputedValue
+type Constants = HashMap<String, PreComputedValue>;
+
+fn pre_compute_operation(operation: &mut Operation, constants: &Constants, token: &Token)
+    -> Result<PreComputedValue, Box<dyn Error>>
 {
+    if operation.operation_type == OperationType::Not ||
+       operation.operation_type == OperationType::BitNot ||
+       operation.operation_type == OperationType::Negate
+    {
+        let value = pre_compute_expression(&mut operation.lhs, constants, token)?;
+        return Ok(match (&operation.operation_type, value)
+        {
+            (OperationType::Not, PreComputedValue::Bool(b)) => PreComputedValue::Bool(!b),
+            (OperationType::BitNot, PreComputedValue::Int(i)) => PreComputedValue::Int(!i),
+            (OperationType::Negate, PreComputedValue::Int(i)) => PreComputedValue::Int(i.wrapping_neg()),
+            _ => PreComputedValue::Unkown,
+        });
+    }
+
     if operation.rhs.is_none() {
-        return PreComputedValue::Unkown;
+        return Ok(PreComputedValue::Unkown);
+    }
+
+    let token = operation.lhs.token().unwrap_or(token).clone();
+    if operation.operation_type == OperationType::Access
+    {
+        // NOTE: The field name isn't a value, so can't be a constant.
+        pre_compute_expression(&mut operation.lhs, constants, &token)?;
+        return Ok(PreComputedValue::Unkown);
+    }
+
+    let is_assignment =
+        operation.operation_type == OperationType::Assign ||
+        operation.operation_type == OperationType::AddAssign ||
+        operation.operation_type == OperationType::SubtractAssign ||
+        operation.operation_type == OperationType::MultiplyAssign ||
+        operation.operation_type == OperationType::DivideAssign ||
+        operation.operation_type == OperationType::ModuloAssign ||
+        operation.operation_type == OperationType::BitAndAssign ||
+        operation.operation_type == OperationType::BitOrAssign ||
+        operation.operation_type == OperationType::BitXorAssign ||
+        operation.operation_type == OperationType::ShiftLeftAssign ||
+        operation.operation_type == OperationType::ShiftRightAssign;
+
+    let pre_computed_lhs = match operation.lhs.as_ref()
+    {
+        Expression::Identifier(_) if is_assignment => PreComputedValue::Unkown,
+        _ => pre_compute_expression(&mut operation.lhs, constants, &token)?,
+    };
+    let pre_computed_rhs = pre_compute_expression(operation.rhs.as_mut().unwrap(), constants, &token)?;
+
+    let is_division =
+        operation.operation_type == OperationType::Divide ||
+        operation.operation_type == OperationType::Modulo ||
+        operation.operation_type == OperationType::DivideAssign ||
+        operation.operation_type == OperationType::ModuloAssign;
+    let is_zero =
+        matches!(pre_computed_rhs, PreComputedValue::Int(0) | PreComputedValue::TypedInt(0)) ||
+        matches!(operation.rhs.as_deref(), Some(Expression::TypedIntLiteral(0, _)));
+    if is_division && is_zero
+    {
+        return Err(CompilerError::new(&operation.token,
+            "Division by zero".to_owned()));
     }
 
-    let pre_computed_lhs = pre_compute_expression(&mut operation.lhs);
-    let pre_computed_rhs = pre_compute_expression(operation.rhs.as_mut().unwrap());
-    match pre_computed_lhs
+    Ok(match (pre_computed_lhs, pre_computed_rhs)
     {
-        PreComputedValue::Int(lhs) =>
+        (PreComputedValue::Int(lhs), PreComputedValue::Int(rhs)) =>
         {
-            match pre_computed_rhs
+            match operation.operation_type
             {
-                PreComputedValue::Int(rhs) =>
-                {
-                    match operation.operation_type
-                    {
-                        OperationType::Add => PreComputedValue::Int(lhs + rhs),
-                        OperationType::Subtract => PreComputedValue::Int(lhs - rhs),
-                        OperationType::GreaterThan => PreComputedValue::Bool(lhs > rhs),
-                        OperationType::LessThan => PreComputedValue::Bool(lhs < rhs),
-                        _ => PreComputedValue::Unkown,
-                    }
-                },
+                OperationType::Add => PreComputedValue::Int(lhs.wrapping_add(rhs)),
+                OperationType::Subtract => PreComputedValue::Int(lhs.wrapping_sub(rhs)),
+                OperationType::Multiply => PreComputedValue::Int(lhs.wrapping_mul(rhs)),
+                OperationType::Divide => PreComputedValue::Int(lhs.wrapping_div(rhs)),
+                OperationType::Modulo => PreComputedValue::Int(lhs.wrapping_rem(rhs)),
+                OperationType::BitAnd => PreComputedValue::Int(lhs & rhs),
+                OperationType::BitOr => PreComputedValue::Int(lhs | rhs),
+                OperationType::BitXor => PreComputedValue::Int(lhs ^ rhs),
+                OperationType::ShiftLeft => PreComputedValue::Int(lhs.wrapping_shl(rhs as u32)),
+                OperationType::ShiftRight => PreComputedValue::Int(lhs.wrapping_shr(rhs as u32)),
+                OperationType::GreaterThan => PreComputedValue::Bool(lhs > rhs),
+                OperationType::LessThan => PreComputedValue::Bool(lhs < rhs),
+                OperationType::GreaterThanEquals => PreComputedValue::Bool(lhs >= rhs),
+                OperationType::LessThanEquals => PreComputedValue::Bool(lhs <= rhs),
+                OperationType::Equals => PreComputedValue::Bool(lhs == rhs),
+                OperationType::NotEquals => PreComputedValue::Bool(lhs != rhs),
+                _ => PreComputedValue::Unkown,
+            }
+        },
 
+        (PreComputedValue::Bool(lhs), PreComputedValue::Bool(rhs)) =>
+        {
+            match operation.operation_type
+            {
+                OperationType::Equals => PreComputedValue::Bool(lhs == rhs),
+                OperationType::NotEquals => PreComputedValue::Bool(lhs != rhs),
+                OperationType::And => PreComputedValue::Bool(lhs && rhs),
+                OperationType::Or => PreComputedValue::Bool(lhs || rhs),
                 _ => PreComputedValue::Unkown,
             }
         },
 
         _ => PreComputedValue::Unkown,
+    })
+}
+
+/// Replace `-1.5` with a negative float literal, so it can be used
+/// wherever a literal can.
+fn fold_negative_float_literal(expression: &mut Expression)
+{
+    let negative_literal = match expression
+    {
+        Expression::Operation(operation) if operation.operation_type == OperationType::Negate =>
+        {
+            match operation.lhs.as_ref()
+            {
+                Expression::FloatLiteral(f) => Some(Expression::FloatLiteral(-f)),
+                Expression::TypedFloatLiteral(f, data_type) =>
+                    Some(Expression::TypedFloatLiteral(-f, data_type.clone())),
+                _ => None,
+            }
+        },
+        _ => None,
+    };
+
+    if let Some(negative_literal) = negative_literal {
+        *expression = negative_literal;
+    }
+}
+
+/// Replace `true && x` and `false || x` with just `x`.
+fn skip_known_condition(expression: &mut Expression)
+{
+    let rhs = match expression
+    {
+        Expression::Operation(operation) =>
+        {
+            match (&operation.operation_type, operation.lhs.as_ref())
+            {
+                (OperationType::And, Expression::BoolLiteral(true)) |
+                (OperationType::Or, Expression::BoolLiteral(false)) =>
+                    operation.rhs.take(),
+                _ => None,
+            }
+        },
+        _ => None,
+    };
+
+    if let Some(rhs) = rhs {
+        *expression = *rhs;
     }
 }
 
-fn pre_compute_expression(expression: &mut Expression) -> PreComputedValue
+fn pre_compute_expression(expression: &mut Expression, constants: &Constants, token: &Token)
+    -> Result<PreComputedValue, Box<dyn Error>>
 {
     let value = match expression
     {
-        Expression::Operation(operation) => pre_compute_operation(operation),
+        Expression::Operation(operation) => pre_compute_operation(operation, constants, token)?,
         Expression::IntLiteral(i) => PreComputedValue::Int(*i),
+        Expression::BoolLiteral(b) => PreComputedValue::Bool(*b),
+
+        // NOTE: Only the value being cast is folded, the compiler
+        //       does the conversion itself.
+        Expression::Cast(cast) =>
+        {
+            pre_compute_expression(&mut cast.value, constants, token)?;
+            PreComputedValue::Unkown
+        },
+
+        Expression::Identifier(name) =>
+        {
+            constants.get(name.content())
+                .copied()
+                .unwrap_or(PreComputedValue::Unkown)
+        },
+
+        Expression::Call(call) | Expression::ExternCall(call) =>
+        {
+            for argument in &mut call.arguments {
+                pre_compute_expression(argument, constants, token)?;
+            }
+            PreComputedValue::Unkown
+        },
+
+        Expression::ArrayLiteral(items) | Expression::TupleLiteral(items) =>
+        {
+            for item in items {
+                pre_compute_expression(item, constants, token)?;
+            }
+            PreComputedValue::Unkown
+        },
+
+        Expression::InitializerList(initializer_list) =>
+        {
+            for (_, value) in &mut initializer_list.list {
+                pre_compute_expression(value, constants, token)?;
+            }
+            PreComputedValue::Unkown
+        },
+
+        Expression::VariantLiteral(literal) =>
+        {
+            for argument in &mut literal.arguments {
+                pre_compute_expression(argument, constants, token)?;
+            }
+            PreComputedValue::Unkown
+        },
+
         _ => PreComputedValue::Unkown,
     };
 
@@ -55,72 +237,519 @@ fn pre_compute_expression(expression: &mut Expression) -> PreComputedValue
     {
         PreComputedValue::Int(i) => *expression = Expression::IntLiteral(i),
         PreComputedValue::Bool(b) => *expression = Expression::BoolLiteral(b),
-        _ => {},
+        PreComputedValue::TypedInt(_) => {},
+        PreComputedValue::Unkown =>
+        {
+            skip_known_condition(expression);
+            fold_negative_float_literal(expression);
+        },
     };
-    value
+    Ok(value)
 }
 
-fn optimize_expression(expression: &mut Expression)
+/// The value of every constant, by the module it's defined in.
+type ModuleValues = HashMap<(ModuleId, String), PreComputedValue>;
+
+/// The constants `module` can see, which are its own and any exported by
+/// the modules it imports. Those exported by more than one are left for
+/// the compiler to report.
+fn visible_constants(values: &ModuleValues,
+                     exported: &HashSet<(ModuleId, String)>,
+                     module: ModuleId,
+                     visible_modules: &HashSet<ModuleId>)
+    -> Constants
 {
-    pre_compute_expression(expression);
+    let mut constants = Constants::new();
+    let mut ambiguous_names = Vec::new();
+    for ((defining_module, name), value) in values
+    {
+        let key = (*defining_module, name.clone());
+        if *defining_module == module ||
+            !visible_modules.contains(defining_module) ||
+            !exported.contains(&key)
+        {
+            continue;
+        }
+
+        if constants.insert(name.clone(), *value).is_some() {
+            ambiguous_names.push(name.clone());
+        }
+    }
+
+    for name in ambiguous_names {
+        constants.insert(name, PreComputedValue::Unkown);
+    }
+    for ((defining_module, name), value) in values
+    {
+        if *defining_module == module {
+            constants.insert(name.clone(), *value);
+        }
+    }
+    constants
 }
 
-fn optimize_block(block: &mut Vec<Statement>)
+/// Fold the value of every constant. Constants may refer to each other
+/// in any order, so keep going until no more can be worked out. Those
+/// left over are recorded as unknown. Gives the constants visible from
+/// each module.
+fn pre_compute_constants(consts: &mut [Const], visible_modules: &[HashSet<ModuleId>])
+    -> Result<Vec<Constants>, Box<dyn Error>>
 {
-    for statement in block {
-        optimize_statement(statement);
+    let exported = consts
+        .iter()
+        .filter(|const_| const_.is_exported)
+        .map(|const_| (const_.module, const_.name.content().to_owned()))
+        .collect::<HashSet<_>>();
+
+    let mut values = ModuleValues::new();
+    loop
+    {
+        let mut has_changed = false;
+        for const_ in consts.iter_mut()
+        {
+            let key = (const_.module, const_.name.content().to_owned());
+            if values.contains_key(&key) {
+                continue;
+            }
+
+            let constants = visible_constants(
+                &values, &exported, const_.module, &visible_modules[const_.module]);
+            match pre_compute_expression(&mut const_.value, &constants, &const_.name)?
+            {
+                PreComputedValue::Unkown => {},
+                value =>
+                {
+                    values.insert(key, value);
+                    has_changed = true;
+                },
+            }
+        }
+
+        if !has_changed {
+            break;
+        }
+    }
+
+    for const_ in consts.iter()
+    {
+        values.entry((const_.module, const_.name.content().to_owned()))
+            .or_insert(PreComputedValue::Unkown);
     }
+
+    Ok(visible_modules
+        .iter()
+        .enumerate()
+        .map(|(module, visible_modules)|
+            visible_constants(&values, &exported, module, visible_modules))
+        .collect())
 }
 
-fn optimize_if(if_: &mut If)
+fn resolve_array_size(data_type: &mut DataType, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
 {
-    optimize_expression(&mut if_.condition);
-    optimize_block(&mut if_.block);
-    if if_.else_block.is_some() {
-        optimize_block(if_.else_block.as_mut().unwrap());
+    let size = match data_type
+    {
+        DataType::ConstSizedArray(item_type, name) =>
+        {
+            resolve_array_size(item_type, constants, token)?;
+            match constants.get(name.as_str())
+            {
+                Some(PreComputedValue::Int(size)) |
+                Some(PreComputedValue::TypedInt(size)) if *size >= 0 => *size as usize,
+
+                Some(PreComputedValue::Int(_)) |
+                Some(PreComputedValue::TypedInt(_)) |
+                Some(PreComputedValue::Bool(_)) =>
+                {
+                    return Err(CompilerError::new(token, format!(
+                        "Constant '{}' is not a valid array size", name)));
+                },
+
+                Some(PreComputedValue::Unkown) =>
+                {
+                    return Err(CompilerError::new(token, format!(
+                        "Value of constant '{}' can not be known at compile time", name)));
+                },
+
+                None =>
+                {
+                    return Err(CompilerError::new(token, format!(
+                        "Could not find constant '{}'", name)));
+                },
+            }
+        },
+
+        DataType::Array(item_type, _) |
+        DataType::Ref(item_type) |
+        DataType::MutRef(item_type) |
+        DataType::Generic(item_type, _) =>
+            return resolve_array_size(item_type, constants, token),
+
+        DataType::Tuple(items) =>
+        {
+            for item in items {
+                resolve_array_size(item, constants, token)?;
+            }
+            return Ok(());
+        },
+
+        DataType::Function(params, return_type) =>
+        {
+            for param in params {
+                resolve_array_size(param, constants, token)?;
+            }
+            if let Some(return_type) = return_type {
+                resolve_array_size(return_type, constants, token)?;
+            }
+            return Ok(());
+        },
+
+        _ => return Ok(()),
+    };
+
+    if let DataType::ConstSizedArray(item_type, _) = data_type
+    {
+        let item_type = item_type.clone();
+        *data_type = DataType::Array(item_type, size);
+    }
+    Ok(())
+}
+
+fn resolve_optional_array_size(data_type: &mut Option<DataType>,
+                               constants: &Constants,
+                               token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    match data_type
+    {
+        Some(data_type) => resolve_array_size(data_type, constants, token),
+        None => Ok(()),
+    }
+}
+
+fn resolve_call_array_sizes(call: &mut Call, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    let token = call.callable.token().unwrap_or(token).clone();
+    resolve_expression_array_sizes(&mut call.callable, constants, &token)?;
+    resolve_optional_array_size(&mut call.type_variable, constants, &token)?;
+    for argument in &mut call.arguments {
+        resolve_expression_array_sizes(argument, constants, &token)?;
+    }
+
+    Ok(())
+}
+
+fn resolve_expression_array_sizes(expression: &mut Expression,
+                                  constants: &Constants,
+                                  token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    match expression
+    {
+        Expression::Operation(operation) =>
+        {
+            resolve_expression_array_sizes(&mut operation.lhs, constants, token)?;
+            if let Some(rhs) = &mut operation.rhs {
+                resolve_expression_array_sizes(rhs, constants, token)?;
+            }
+            Ok(())
+        },
+
+        Expression::Call(call) => resolve_call_array_sizes(call, constants, token),
+        Expression::ExternCall(call) => resolve_call_array_sizes(call, constants, token),
+        Expression::Cast(cast) => resolve_expression_array_sizes(&mut cast.value, constants, token),
+
+        Expression::InitializerList(initializer_list) =>
+        {
+            let token = initializer_list.list
+                .first()
+                .map(|(name, _)| name.clone())
+                .unwrap_or(token.clone());
+
+            resolve_array_size(&mut initializer_list.data_type, constants, &token)?;
+            for (_, value) in &mut initializer_list.list {
+                resolve_expression_array_sizes(value, constants, &token)?;
+            }
+            Ok(())
+        },
+
+        Expression::VariantLiteral(literal) =>
+        {
+            let token = literal.variant.clone();
+            resolve_array_size(&mut literal.data_type, constants, &token)?;
+            for argument in &mut literal.arguments {
+                resolve_expression_array_sizes(argument, constants, &token)?;
+            }
+            Ok(())
+        },
+
+        Expression::ArrayLiteral(items) | Expression::TupleLiteral(items) =>
+        {
+            for item in items {
+                resolve_expression_array_sizes(item, constants, token)?;
+            }
+            Ok(())
+        },
+
+        Expression::FunctionRef(function_ref) =>
+        {
+            let token = function_ref.name.clone();
+            for param in &mut function_ref.params {
+                resolve_array_size(param, constants, &token)?;
+            }
+            resolve_optional_array_size(&mut function_ref.type_variable, constants, &token)
+        },
+
+        _ => Ok(()),
+    }
+}
+
+fn optimize_expression(expression: &mut Expression, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    resolve_expression_array_sizes(expression, constants, token)?;
+    pre_compute_expression(expression, constants, token)?;
+    Ok(())
+}
+
+fn optimize_block(block: &mut Vec<Statement>, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    // NOTE: A local shadows any constant with its name from where
+    //       it's declared to the end of the block.
+    let mut constants = constants.clone();
+    for statement in block
+    {
+        optimize_statement(statement, &constants, token)?;
+        match statement
+        {
+            Statement::Let(let_) =>
+            {
+                constants.remove(let_.name.content());
+            },
+
+            Statement::LetTuple(let_tuple) =>
+            {
+                for binding in &let_tuple.bindings {
+                    constants.remove(binding.name.content());
+                }
+            },
+
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
+
+fn optimize_if(if_: &mut If, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    optimize_expression(&mut if_.condition, constants, token)?;
+    optimize_block(&mut if_.block, constants, token)?;
+    if let Some(else_block) = &mut if_.else_block {
+        optimize_block(else_block, constants, token)?;
     }
+
+    Ok(())
 }
 
-fn optimize_loop(block: &mut Vec<Statement>)
+fn optimize_loop(block: &mut Vec<Statement>, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
 {
-    optimize_block(block);
+    optimize_block(block, constants, token)
 }
 
-fn optimize_while(condition: &mut Expression, block: &mut Vec<Statement>)
+fn optimize_while(condition: &mut Expression, block: &mut Vec<Statement>,
+                  constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
 {
-    optimize_expression(condition);
-    optimize_block(block);
+    optimize_expression(condition, constants, token)?;
+    optimize_block(block, constants, token)
 }
 
-fn optimize_statement(statement: &mut Statement)
+fn optimize_for(for_: &mut For, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    match &mut for_.iterable
+    {
+        Iterable::Range(start, end) =>
+        {
+            optimize_expression(start, constants, token)?;
+            optimize_expression(end, constants, token)?;
+        },
+
+        Iterable::Collection(collection) =>
+            optimize_expression(collection, constants, token)?,
+    }
+
+    let mut block_constants = constants.clone();
+    block_constants.remove(for_.variable.content());
+    optimize_block(&mut for_.block, &block_constants, token)
+}
+
+fn optimize_match(match_: &mut Match, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    optimize_expression(&mut match_.value, constants, token)?;
+    for arm in &mut match_.arms
+    {
+        let mut arm_constants = constants.clone();
+        for binding in arm.bindings.iter().flatten() {
+            arm_constants.remove(binding.content());
+        }
+        optimize_block(&mut arm.block, &arm_constants, token)?;
+    }
+    if let Some(else_block) = &mut match_.else_block {
+        optimize_block(else_block, constants, token)?;
+    }
+
+    Ok(())
+}
+
+fn optimize_let(let_: &mut Let, constants: &Constants)
+    -> Result<(), Box<dyn Error>>
+{
+    resolve_optional_array_size(&mut let_.data_type, constants, &let_.name)?;
+    match &mut let_.value
+    {
+        Some(value) => optimize_expression(value, constants, &let_.name),
+        None => Ok(()),
+    }
+}
+
+fn optimize_let_tuple(let_tuple: &mut LetTuple, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
+{
+    let token = let_tuple.bindings
+        .first()
+        .map(|binding| binding.name.clone())
+        .unwrap_or(token.clone());
+
+    resolve_optional_array_size(&mut let_tuple.data_type, constants, &token)?;
+    optimize_expression(&mut let_tuple.value, constants, &token)
+}
+
+fn optimize_statement(statement: &mut Statement, constants: &Constants, token: &Token)
+    -> Result<(), Box<dyn Error>>
 {
     match statement
     {
-        Statement::Expression(expression) => optimize_expression(expression),
-        Statement::Return(expression) => optimize_expression(expression),
-        Statement::Let(let_) => optimize_expression(&mut let_.value),
-        Statement::If(if_) => optimize_if(if_),
-        Statement::Loop(block) => optimize_loop(block),
-        Statement::While(condition, block) => optimize_while(condition, block),
-        Statement::Break => {},
+        Statement::Expression(expression) => optimize_expression(expression, constants, token),
+        Statement::Return(expression) => optimize_expression(expression, constants, token),
+        Statement::Let(let_) => optimize_let(let_, constants),
+        Statement::LetTuple(let_tuple) => optimize_let_tuple(let_tuple, constants, token),
+        Statement::If(if_) => optimize_if(if_, constants, token),
+        Statement::Loop(block) => optimize_loop(block, constants, token),
+        Statement::While(condition, block) => optimize_while(condition, block, constants, token),
+        Statement::For(for_) => optimize_for(for_, constants, token),
+        Statement::Match(match_) => optimize_match(match_, constants, token),
+        Statement::Break(_) => Ok(()),
+        Statement::Continue(_) => Ok(()),
     }
 }
 
-fn optimize_function(function: &mut Function)
+fn optimize_function(function: &mut Function,
+                     constants: &Constants,
+                     typed_constants: &HashSet<String>)
+    -> Result<(), Box<dyn Error>>
 {
+    let token = function.name.clone();
+    for param in &mut function.params
+    {
+        if let DataTypeDescription::Exact(data_type) = &mut param.data_type_description {
+            resolve_array_size(data_type, constants, &param.name)?;
+        }
+    }
+    resolve_optional_array_size(&mut function.type_variable, constants, &token)?;
+    resolve_optional_array_size(&mut function.return_type, constants, &token)?;
+
     if function.body.is_none() {
-        return;
+        return Ok(());
     }
 
-    for statement in function.body.as_mut().unwrap() {
-        optimize_statement(statement);
+    let mut body_constants = constants.clone();
+    for name in typed_constants
+    {
+        if let Some(value) = body_constants.get_mut(name)
+        {
+            *value = match value
+            {
+                PreComputedValue::Int(i) => PreComputedValue::TypedInt(*i),
+                _ => PreComputedValue::Unkown,
+            };
+        }
+    }
+    for param in &function.params {
+        body_constants.remove(param.name.content());
     }
+
+    optimize_block(function.body.as_mut().unwrap(), &body_constants, &token)
 }
 
 pub fn optimize(ast: &mut SourceFile)
+    -> Result<(), Box<dyn Error>>
 {
-    for function in &mut ast.functions {
-        optimize_function(function);
+    let visible_modules = (0..ast.modules.len())
+        .map(|module| ast.visible_modules(module))
+        .collect::<Vec<_>>();
+
+    let module_constants = pre_compute_constants(&mut ast.consts, &visible_modules)?;
+    for const_ in &mut ast.consts
+    {
+        let constants = &module_constants[const_.module];
+        resolve_optional_array_size(&mut const_.data_type, constants, &const_.name)?;
     }
-}
 
+    for struct_ in &mut ast.structs
+    {
+        let constants = &module_constants[struct_.module];
+        for field in &mut struct_.fields {
+            resolve_array_size(&mut field.data_type, constants, &field.name)?;
+        }
+    }
+
+    for enum_ in &mut ast.enums
+    {
+        let constants = &module_constants[enum_.module];
+        for variant in &mut enum_.variants
+        {
+            for field in &mut variant.fields {
+                resolve_array_size(field, constants, &variant.name)?;
+            }
+        }
+    }
+
+    for trait_ in &mut ast.traits
+    {
+        let constants = &module_constants[trait_.module];
+        for function in &mut trait_.functions
+        {
+            for param in &mut function.params {
+                resolve_array_size(param, constants, &function.name)?;
+            }
+            resolve_optional_array_size(&mut function.return_type, constants, &function.name)?;
+        }
+    }
+
+    // NOTE: Constants declared with a type other than `int` or `bool`
+    //       keep it, so aren't folded into function bodies.
+    let typed_constants = ast.consts
+        .iter()
+        .filter(|const_| match &const_.data_type
+        {
+            Some(data_type) => *data_type != DataType::Int && *data_type != DataType::Bool,
+            None => false,
+        })
+        .map(|const_| const_.name.content().to_owned())
+        .collect::<HashSet<_>>();
+
+    for function in &mut ast.functions
+    {
+        let constants = &module_constants[function.module];
+        optimize_function(function, constants, &typed_constants)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use super::{TokenStream, UnexpectedError};
+use super::expression::parse_expression;
+use super::data_type::parse_data_type;
+use crate::tokenizer::{Token, TokenType};
+use crate::ast::{Const, ROOT_MODULE};
+use std::iter::Peekable;
+use std::error::Error;
+
+pub fn parse_const(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Const, Box<dyn Error>>
+{
+    let name = tokens.expect(TokenType::Identifier)?;
+    let mut data_type = None;
+    if tokens.is_next(TokenType::Colon)
+    {
+        tokens.expect(TokenType::Colon)?;
+        data_type = Some(parse_data_type(tokens)?);
+    }
+
+    let equals = tokens.expect(TokenType::Equals)?;
+    let value_or_none = parse_expression(tokens)?;
+    if value_or_none.is_none()
+    {
+        return Err(Box::from(UnexpectedError
+        {
+            expected: TokenType::IntLiteral,
+            got: Some(equals),
+        }));
+    }
+
+    Ok(Const
+    {
+        name,
+        data_type,
+        value: value_or_none.unwrap(),
+        module: ROOT_MODULE,
+        is_exported: false,
+    })
+}
@@ -1,8 +1,97 @@
 use super::TokenStream;
 use crate::tokenizer::{Token, TokenType};
 use crate::data_type::{DataType, DataTypeDescription};
+use crate::ast::TypeVariable;
 use std::iter::Peekable;
 use std::error::Error;
+use std::convert::TryFrom;
+
+/// The type named by a single keyword, like `int` or `u8`.
+pub fn primitive_type(token_type: &TokenType) -> Option<DataType>
+{
+    match token_type
+    {
+        TokenType::Int => Some(DataType::Int),
+        TokenType::I8 => Some(DataType::I8),
+        TokenType::I16 => Some(DataType::I16),
+        TokenType::I64 => Some(DataType::I64),
+        TokenType::U8 => Some(DataType::U8),
+        TokenType::U16 => Some(DataType::U16),
+        TokenType::U32 => Some(DataType::U32),
+        TokenType::U64 => Some(DataType::U64),
+        TokenType::F32 => Some(DataType::F32),
+        TokenType::F64 => Some(DataType::F64),
+        TokenType::Char => Some(DataType::Char),
+        TokenType::Bool => Some(DataType::Bool),
+        TokenType::Any => Some(DataType::Any),
+        _ => None,
+    }
+}
+
+/// A tuple type, like `(int, bool)`. A single type in brackets is
+/// just that type, so it can be used to group generic types.
+fn parse_tuple_type(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<DataType, Box<dyn Error>>
+{
+    let mut items = Vec::new();
+    loop
+    {
+        items.push(parse_data_type(tokens)?);
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseBracket)?;
+
+    if items.len() == 1 {
+        Ok(items.pop().unwrap())
+    } else {
+        Ok(DataType::Tuple(items))
+    }
+}
+
+/// The types of a list of params, like `(int, ref char)`.
+pub fn parse_param_types(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Vec<DataType>, Box<dyn Error>>
+{
+    tokens.expect(TokenType::OpenBracket)?;
+    let mut params = Vec::new();
+    loop
+    {
+        if tokens.is_next(TokenType::CloseBracket) {
+            break;
+        }
+
+        params.push(parse_data_type(tokens)?);
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseBracket)?;
+
+    Ok(params)
+}
+
+/// A function type, like `fun(int, int) -> bool`.
+fn parse_function_type(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<DataType, Box<dyn Error>>
+{
+    let params = parse_param_types(tokens)?;
+    let return_type =
+        if tokens.is_next(TokenType::Arrow)
+        {
+            tokens.expect(TokenType::Arrow)?;
+            Some(Box::from(parse_data_type(tokens)?))
+        }
+        else
+        {
+            None
+        };
+
+    Ok(DataType::Function(params, return_type))
+}
 
 pub fn parse_data_type(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<DataType, Box<dyn Error>>
@@ -15,22 +104,38 @@ pub fn parse_data_type(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     let next = next_or_none.unwrap();
     let mut data_type = match next.token_type()
     {
-        TokenType::Int => DataType::Int,
-        TokenType::Char => DataType::Char,
-        TokenType::Bool => DataType::Bool,
+        token_type if primitive_type(token_type).is_some() =>
+            primitive_type(token_type).unwrap(),
+
         TokenType::Identifier => DataType::Struct(next.content().to_owned()),
+        TokenType::Ref if tokens.is_next(TokenType::Mut) =>
+        {
+            tokens.expect(TokenType::Mut)?;
+            DataType::MutRef(Box::from(parse_data_type(tokens)?))
+        },
+
         TokenType::Ref => DataType::Ref(Box::from(parse_data_type(tokens)?)),
+        TokenType::OpenBracket => parse_tuple_type(tokens)?,
+        TokenType::Fun => parse_function_type(tokens)?,
         _ => panic!(),
     };
 
     while tokens.is_next(TokenType::OpenSquare)
     {
         tokens.expect(TokenType::OpenSquare)?;
-        let size_token = tokens.expect(TokenType::IntLiteral)?;
+        if tokens.is_next(TokenType::Identifier)
+        {
+            let size_token = tokens.expect(TokenType::Identifier)?;
+            let size = size_token.content().to_owned();
+            data_type = DataType::ConstSizedArray(Box::from(data_type), size);
+        }
+        else
+        {
+            let size_token = tokens.expect(TokenType::IntLiteral)?;
+            let size = usize::try_from(size_token.int_bits())?;
+            data_type = DataType::Array(Box::from(data_type), size);
+        }
         tokens.expect(TokenType::CloseSquare)?;
-
-        let size = size_token.content().parse::<usize>()?;
-        data_type = DataType::Array(Box::from(data_type), size);
     }
 
     while tokens.is_next(TokenType::Identifier)
@@ -47,15 +152,10 @@ pub fn parse_data_type(tokens: &mut Peekable<impl Iterator<Item = Token>>)
 pub fn parse_data_type_description(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<DataTypeDescription, Box<dyn Error>>
 {
-    let next = tokens.peek();
-    if next.is_none() {
-        panic!();
-    }
-
-    match next.unwrap().token_type()
+    match parse_data_type(tokens)?
     {
-        TokenType::Any => { tokens.next(); Ok(DataTypeDescription::Any) },
-        _ => Ok(DataTypeDescription::Exact(parse_data_type(tokens)?)),
+        DataType::Any => Ok(DataTypeDescription::Any),
+        data_type => Ok(DataTypeDescription::Exact(data_type)),
     }
 }
 
@@ -70,3 +170,88 @@ pub fn parse_type_variable(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     Ok(Some(parse_data_type(tokens)?))
 }
 
+
+fn parse_bounded_type(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<(DataType, Vec<Token>), Box<dyn Error>>
+{
+    if !tokens.is_next(TokenType::Identifier) {
+        return Ok((parse_data_type(tokens)?, Vec::new()));
+    }
+
+    // NOTE: In `Hashable Defaultable T`, every name but the last
+    //       is a trait the type variable has to implement.
+    let mut bounds = Vec::new();
+    while tokens.is_next(TokenType::Identifier) {
+        bounds.push(tokens.next().unwrap());
+    }
+
+    let name = bounds.pop().unwrap();
+    Ok((DataType::Struct(name.content().to_owned()), bounds))
+}
+
+pub fn parse_bounded_type_variable(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<(Option<DataType>, Vec<Token>), Box<dyn Error>>
+{
+    if !tokens.is_next(TokenType::Of) {
+        return Ok((None, Vec::new()));
+    }
+
+    tokens.expect(TokenType::Of)?;
+    let (type_variable, bounds) = parse_bounded_type(tokens)?;
+    Ok((Some(type_variable), bounds))
+}
+
+pub fn parse_type_variable_list(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Vec<TypeVariable>, Box<dyn Error>>
+{
+    if !tokens.is_next(TokenType::Of) {
+        return Ok(Vec::new());
+    }
+
+    tokens.expect(TokenType::Of)?;
+    let mut type_variables = Vec::new();
+    loop
+    {
+        let mut bounds = Vec::new();
+        let mut name = tokens.expect(TokenType::Identifier)?;
+        while tokens.is_next(TokenType::Identifier)
+        {
+            bounds.push(name);
+            name = tokens.next().unwrap();
+        }
+
+        type_variables.push(TypeVariable
+        {
+            name,
+            bounds,
+        });
+
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+
+    Ok(type_variables)
+}
+
+pub fn parse_implemented_traits(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Vec<DataType>, Box<dyn Error>>
+{
+    let mut traits = Vec::new();
+    if !tokens.is_next(TokenType::Colon) {
+        return Ok(traits);
+    }
+
+    tokens.expect(TokenType::Colon)?;
+    loop
+    {
+        traits.push(parse_data_type(tokens)?);
+        if !tokens.is_next(TokenType::Plus) {
+            break;
+        }
+        tokens.expect(TokenType::Plus)?;
+    }
+
+    Ok(traits)
+}
@@ -0,0 +1,72 @@
+use super::TokenStream;
+use super::data_type::{parse_data_type, parse_type_variable};
+use crate::tokenizer::{Token, TokenType};
+use crate::ast::{Enum, Variant, ROOT_MODULE};
+use crate::data_type::DataType;
+use std::iter::Peekable;
+use std::error::Error;
+
+fn parse_variant_fields(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Vec<DataType>, Box<dyn Error>>
+{
+    let mut fields = Vec::new();
+    if !tokens.is_next(TokenType::OpenBracket) {
+        return Ok(fields);
+    }
+
+    tokens.expect(TokenType::OpenBracket)?;
+    loop
+    {
+        if tokens.is_next(TokenType::CloseBracket) {
+            break;
+        }
+
+        fields.push(parse_data_type(tokens)?);
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseBracket)?;
+
+    Ok(fields)
+}
+
+pub fn parse_enum(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Enum, Box<dyn Error>>
+{
+    let name = tokens.expect(TokenType::Identifier)?;
+    let type_variable = parse_type_variable(tokens)?;
+
+    tokens.expect(TokenType::OpenSquiggly)?;
+    let mut variants = Vec::new();
+    loop
+    {
+        if !tokens.is_next(TokenType::Identifier) {
+            break;
+        }
+
+        let variant_name = tokens.next().unwrap();
+        let fields = parse_variant_fields(tokens)?;
+        variants.push(Variant
+        {
+            name: variant_name,
+            fields,
+        });
+
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseSquiggly)?;
+
+    Ok(Enum
+    {
+        name,
+        type_variable,
+        variants,
+        module: ROOT_MODULE,
+        is_exported: false,
+    })
+}
@@ -1,8 +1,9 @@
-use super::TokenStream;
-use super::data_type::parse_data_type;
-use crate::tokenizer::{Token, TokenType};
+use super::{TokenStream, UnexpectedError};
+use super::data_type::{parse_data_type, parse_param_types, parse_type_variable, primitive_type};
+use crate::tokenizer::{self, Token, TokenType};
 use crate::ast::{Expression, Operation, OperationType};
-use crate::ast::{Call, InitializerList};
+use crate::ast::{Call, Cast, InitializerList, VariantLiteral, FunctionRef};
+use crate::data_type::DataType;
 use std::iter::Peekable;
 use std::error::Error;
 
@@ -12,31 +13,105 @@ const TERM_OPERATIONS: [(TokenType, OperationType); 2] =
     (TokenType::OpenSquare, OperationType::Indexed),
 ];
 
-const ARITHMATIC_OPERATIONS: [(TokenType, OperationType); 3] =
-[
-    (TokenType::Plus, OperationType::Add),
-    (TokenType::Star, OperationType::Multiply),
-    (TokenType::Minus, OperationType::Subtract),
-];
-
-const LOGIC_OPERATIONS: [(TokenType, OperationType); 2] =
-[
-    (TokenType::GreaterThan, OperationType::GreaterThan),
-    (TokenType::LessThan, OperationType::LessThan),
-];
+#[derive(Clone, Copy, PartialEq)]
+enum Associativity
+{
+    Left,
+    Right,
+}
 
-const EXPRESSION_OPERATIONS: [(TokenType, OperationType); 1] =
+/// Binary operators and how tightly they bind, where a higher
+/// precedence binds tighter. Unary operators (`-` `!` `~` `ref`
+/// `deref` `sizeof`), the term operations above and then casts
+/// (`as`) bind tighter than all of these.
+///
+/// | Precedence | Operators                                                | Associativity |
+/// |------------|----------------------------------------------------------|---------------|
+/// | 1          | `=` `+=` `-=` `*=` `/=` `%=` `&=` `\|=` `^=` `<<=` `>>=` | Right         |
+/// | 2          | `\|\|`                                                   | Left          |
+/// | 3          | `&&`                                                     | Left          |
+/// | 4          | `\|`                                                     | Left          |
+/// | 5          | `^`                                                      | Left          |
+/// | 6          | `&`                                                      | Left          |
+/// | 7          | `==` `!=`                                                | Left          |
+/// | 8          | `<` `>` `<=` `>=`                                        | Left          |
+/// | 9          | `<<` `>>`                                                | Left          |
+/// | 10         | `+` `-`                                                  | Left          |
+/// | 11         | `*` `/` `%`                                              | Left          |
+const BINARY_OPERATIONS: [(TokenType, OperationType, u8, Associativity); 29] =
 [
-    (TokenType::Equals, OperationType::Assign),
+    (TokenType::Equals, OperationType::Assign, 1, Associativity::Right),
+    (TokenType::PlusEquals, OperationType::AddAssign, 1, Associativity::Right),
+    (TokenType::MinusEquals, OperationType::SubtractAssign, 1, Associativity::Right),
+    (TokenType::StarEquals, OperationType::MultiplyAssign, 1, Associativity::Right),
+    (TokenType::SlashEquals, OperationType::DivideAssign, 1, Associativity::Right),
+    (TokenType::PercentEquals, OperationType::ModuloAssign, 1, Associativity::Right),
+    (TokenType::AmpersandEquals, OperationType::BitAndAssign, 1, Associativity::Right),
+    (TokenType::PipeEquals, OperationType::BitOrAssign, 1, Associativity::Right),
+    (TokenType::CaretEquals, OperationType::BitXorAssign, 1, Associativity::Right),
+    (TokenType::ShiftLeftEquals, OperationType::ShiftLeftAssign, 1, Associativity::Right),
+    (TokenType::ShiftRightEquals, OperationType::ShiftRightAssign, 1, Associativity::Right),
+
+    (TokenType::DoublePipe, OperationType::Or, 2, Associativity::Left),
+    (TokenType::DoubleAmpersand, OperationType::And, 3, Associativity::Left),
+
+    (TokenType::Pipe, OperationType::BitOr, 4, Associativity::Left),
+    (TokenType::Caret, OperationType::BitXor, 5, Associativity::Left),
+    (TokenType::Ampersand, OperationType::BitAnd, 6, Associativity::Left),
+
+    (TokenType::DoubleEquals, OperationType::Equals, 7, Associativity::Left),
+    (TokenType::NotEquals, OperationType::NotEquals, 7, Associativity::Left),
+
+    (TokenType::GreaterThan, OperationType::GreaterThan, 8, Associativity::Left),
+    (TokenType::LessThan, OperationType::LessThan, 8, Associativity::Left),
+    (TokenType::GreaterThanEquals, OperationType::GreaterThanEquals, 8, Associativity::Left),
+    (TokenType::LessThanEquals, OperationType::LessThanEquals, 8, Associativity::Left),
+
+    (TokenType::ShiftLeft, OperationType::ShiftLeft, 9, Associativity::Left),
+    (TokenType::ShiftRight, OperationType::ShiftRight, 9, Associativity::Left),
+
+    (TokenType::Plus, OperationType::Add, 10, Associativity::Left),
+    (TokenType::Minus, OperationType::Subtract, 10, Associativity::Left),
+
+    (TokenType::Star, OperationType::Multiply, 11, Associativity::Left),
+    (TokenType::Slash, OperationType::Divide, 11, Associativity::Left),
+    (TokenType::Percent, OperationType::Modulo, 11, Associativity::Left),
 ];
 
 fn parse_unary(tokens: &mut Peekable<impl Iterator<Item = Token>>,
                operation_type: OperationType)
     -> Result<Option<Expression>, Box<dyn Error>>
 {
-    tokens.next();
+    let token = tokens.next().unwrap();
+    parse_unary_operand(tokens, operation_type, token)
+}
+
+fn parse_ref(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Option<Expression>, Box<dyn Error>>
+{
+    let token = tokens.expect(TokenType::Ref)?;
+    if !tokens.is_next(TokenType::Mut) {
+        return parse_unary_operand(tokens, OperationType::Ref, token);
+    }
+
+    tokens.expect(TokenType::Mut)?;
+    parse_unary_operand(tokens, OperationType::MutRef, token)
+}
+
+fn parse_unary_operand(tokens: &mut Peekable<impl Iterator<Item = Token>>,
+                       operation_type: OperationType,
+                       token: Token)
+    -> Result<Option<Expression>, Box<dyn Error>>
+{
+    // NOTE: A negated int literal is folded into the literal, so
+    //       the smallest value of a type, like `-128i8`, fits.
+    if operation_type == OperationType::Negate && tokens.is_next(TokenType::IntLiteral)
+    {
+        let literal = tokens.next().unwrap();
+        return Ok(Some(parse_int_literal(literal, Some(&token))?));
+    }
 
-    let value = parse_expression(tokens)?;
+    let value = parse_term(tokens)?;
     if value.is_none() {
         return Ok(None);
     }
@@ -46,15 +121,14 @@ fn parse_unary(tokens: &mut Peekable<impl Iterator<Item = Token>>,
         operation_type,
         lhs: Box::from(value.unwrap()),
         rhs: None,
+        token,
     })))
 }
 
-fn parse_initializer_list(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+fn parse_initializer_list(tokens: &mut Peekable<impl Iterator<Item = Token>>,
+                          data_type: DataType)
     -> Result<Expression, Box<dyn Error>>
 {
-    tokens.expect(TokenType::New)?;
-    let data_type = parse_data_type(tokens)?;
-
     tokens.expect(TokenType::OpenSquiggly)?;
     let mut initializer_list = Vec::new();
     loop
@@ -86,6 +160,55 @@ fn parse_initializer_list(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     }))
 }
 
+fn parse_variant_literal(tokens: &mut Peekable<impl Iterator<Item = Token>>,
+                         data_type: DataType)
+    -> Result<Expression, Box<dyn Error>>
+{
+    tokens.expect(TokenType::Dot)?;
+    let variant = tokens.expect(TokenType::Identifier)?;
+
+    let mut arguments = Vec::new();
+    if tokens.is_next(TokenType::OpenBracket)
+    {
+        tokens.expect(TokenType::OpenBracket)?;
+        loop
+        {
+            let argument = parse_expression(tokens)?;
+            if argument.is_none() {
+                break;
+            }
+
+            arguments.push(argument.unwrap());
+            if !tokens.is_next(TokenType::Comma) {
+                break;
+            }
+            tokens.next();
+        }
+        tokens.expect(TokenType::CloseBracket)?;
+    }
+
+    Ok(Expression::VariantLiteral(VariantLiteral
+    {
+        data_type,
+        variant,
+        arguments,
+    }))
+}
+
+/// Either a struct, like `new Point { x = 1, y = 2 }`, or
+/// an enum variant, like `new Shape.Circle(5)`.
+fn parse_new(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Expression, Box<dyn Error>>
+{
+    tokens.expect(TokenType::New)?;
+    let data_type = parse_data_type(tokens)?;
+    if tokens.is_next(TokenType::Dot) {
+        parse_variant_literal(tokens, data_type)
+    } else {
+        parse_initializer_list(tokens, data_type)
+    }
+}
+
 fn parse_array(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Expression, Box<dyn Error>>
 {
@@ -110,6 +233,61 @@ fn parse_array(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     Ok(Expression::ArrayLiteral(array))
 }
 
+fn parse_tuple_literal(tokens: &mut Peekable<impl Iterator<Item = Token>>,
+                       first_item: Expression)
+    -> Result<Expression, Box<dyn Error>>
+{
+    let mut items = vec![first_item];
+    while tokens.is_next(TokenType::Comma)
+    {
+        tokens.expect(TokenType::Comma)?;
+        match parse_expression(tokens)?
+        {
+            Some(item) => items.push(item),
+            None =>
+            {
+                return Err(Box::from(UnexpectedError
+                {
+                    expected: TokenType::Identifier,
+                    got: tokens.next(),
+                }));
+            },
+        }
+    }
+
+    tokens.expect(TokenType::CloseBracket)?;
+    Ok(Expression::TupleLiteral(items))
+}
+
+/// Either an expression in brackets, like `(a + b)`, or a
+/// tuple, like `(a, b)`.
+fn parse_group(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Expression, Box<dyn Error>>
+{
+    tokens.expect(TokenType::OpenBracket)?;
+    let expression = parse_expression(tokens)?;
+    match expression
+    {
+        Some(first_item) if tokens.is_next(TokenType::Comma) =>
+            return parse_tuple_literal(tokens, first_item),
+        _ => {},
+    }
+
+    let close_bracket = tokens.expect(TokenType::CloseBracket)?;
+    match expression
+    {
+        Some(expression) => Ok(expression),
+        None =>
+        {
+            Err(Box::from(UnexpectedError
+            {
+                expected: TokenType::Identifier,
+                got: Some(close_bracket),
+            }))
+        },
+    }
+}
+
 fn parse_extern_call(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Expression, Box<dyn Error>>
 {
@@ -122,6 +300,66 @@ fn parse_extern_call(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     }
 }
 
+fn parse_function_ref(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Expression, Box<dyn Error>>
+{
+    tokens.expect(TokenType::Fun)?;
+    let name = tokens.expect(TokenType::Identifier)?;
+    let params = parse_param_types(tokens)?;
+    let type_variable = parse_type_variable(tokens)?;
+    Ok(Expression::FunctionRef(FunctionRef
+    {
+        name,
+        params,
+        type_variable,
+    }))
+}
+
+/// An int literal, negated if it has the `-` token before it.
+fn parse_int_literal(token: Token, negate_token: Option<&Token>)
+    -> Result<Expression, Box<dyn Error>>
+{
+    let is_negated = negate_token.is_some();
+    if let Err(message) = tokenizer::parse_int_literal(token.content(), is_negated)
+    {
+        return Err(Box::from(UnexpectedError
+        {
+            expected: TokenType::IntLiteral,
+            got: Some(negate_token.unwrap_or(&token).with_content(TokenType::Error, &message)),
+        }));
+    }
+
+    let bits = if is_negated { token.int_bits().wrapping_neg() } else { token.int_bits() };
+    let data_type = match token.int_suffix()
+    {
+        None => return Ok(Expression::IntLiteral(bits as i32)),
+        Some("i8") => DataType::I8,
+        Some("i16") => DataType::I16,
+        Some("i32") => DataType::Int,
+        Some("i64") => DataType::I64,
+        Some("u8") => DataType::U8,
+        Some("u16") => DataType::U16,
+        Some("u32") => DataType::U32,
+        Some("u64") => DataType::U64,
+        Some(_) => panic!(),
+    };
+
+    let (size, _) = data_type.int_layout().unwrap();
+    let mask = u64::MAX >> (64 - size * 8);
+    Ok(Expression::TypedIntLiteral(bits & mask, data_type))
+}
+
+fn parse_float_literal(token: Token) -> Expression
+{
+    match token.float_suffix()
+    {
+        None => Expression::FloatLiteral(token.float_value()),
+        Some("f32") => Expression::TypedFloatLiteral(token.float_value(), DataType::F32),
+        Some("f64") => Expression::TypedFloatLiteral(token.float_value(), DataType::F64),
+        Some(_) => panic!(),
+    }
+}
+
 pub fn parse_value(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Option<Expression>, Box<dyn Error>>
 {
@@ -133,7 +371,10 @@ pub fn parse_value(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     Ok(match next.unwrap().token_type()
     {
         TokenType::IntLiteral => 
-            Some(Expression::IntLiteral(tokens.next().unwrap().content().parse::<i32>().unwrap())),
+            Some(parse_int_literal(tokens.next().unwrap(), None)?),
+
+        TokenType::FloatLiteral => 
+            Some(parse_float_literal(tokens.next().unwrap())),
 
         TokenType::StringLiteral => 
             Some(Expression::StringLiteral(tokens.next().unwrap())),
@@ -148,16 +389,22 @@ pub fn parse_value(tokens: &mut Peekable<impl Iterator<Item = Token>>)
             Some(Expression::Identifier(tokens.next().unwrap())),
 
         TokenType::New =>
-            Some(parse_initializer_list(tokens)?),
+            Some(parse_new(tokens)?),
 
         TokenType::Extern =>
             Some(parse_extern_call(tokens)?),
 
+        TokenType::Fun =>
+            Some(parse_function_ref(tokens)?),
+
         TokenType::OpenSquare =>
             Some(parse_array(tokens)?),
 
+        TokenType::OpenBracket =>
+            Some(parse_group(tokens)?),
+
         TokenType::Ref =>
-            parse_unary(tokens, OperationType::Ref)?,
+            parse_ref(tokens)?,
 
         TokenType::Sizeof =>
             parse_unary(tokens, OperationType::Sizeof)?,
@@ -165,6 +412,15 @@ pub fn parse_value(tokens: &mut Peekable<impl Iterator<Item = Token>>)
         TokenType::Deref =>
             parse_unary(tokens, OperationType::Deref)?,
 
+        TokenType::Not =>
+            parse_unary(tokens, OperationType::Not)?,
+
+        TokenType::Tilde =>
+            parse_unary(tokens, OperationType::BitNot)?,
+
+        TokenType::Minus =>
+            parse_unary(tokens, OperationType::Negate)?,
+
         _ =>
             None,
     })
@@ -255,7 +511,7 @@ fn next_operation(tokens: &mut Peekable<impl Iterator<Item = Token>>,
 
 fn parse_operation<ParseFunc, Tokens>(tokens: &mut Peekable<Tokens>,
                                       lhs: Expression, parse_rhs: ParseFunc,
-                                      operation: OperationType)
+                                      operation: OperationType, token: Token)
         -> Result<Expression, Box<dyn Error>>
     where ParseFunc: Fn(&mut Peekable<Tokens>) -> Result<Option<Expression>, Box<dyn Error>>,
           Tokens: Iterator<Item = Token>
@@ -272,6 +528,7 @@ fn parse_operation<ParseFunc, Tokens>(tokens: &mut Peekable<Tokens>,
         operation_type: operation,
         lhs: Box::from(lhs),
         rhs: rhs,
+        token,
     }))
 }
 
@@ -296,9 +553,9 @@ fn parse_operation_order<F, T>(tokens: &mut Peekable<T>,
         }
 
         let operation = operation_or_none.unwrap();
-        tokens.next();
+        let token = tokens.next().unwrap();
         lhs = parse_operation(tokens, lhs,
-            parse_operand, operation.clone())?;
+            parse_operand, operation.clone(), token)?;
 
         // NOTE: Little hacky
         if operation == OperationType::Indexed {
@@ -315,21 +572,114 @@ fn parse_term(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     parse_operation_order(tokens, &parse_computed_value, &TERM_OPERATIONS)
 }
 
-fn parse_arithmatic(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+fn next_binary_operation(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Option<(OperationType, u8, Associativity)>
+{
+    let token_type = tokens.peek()?.token_type();
+    for (operation_token_type, operation_type, precedence, associativity) in &BINARY_OPERATIONS
+    {
+        if token_type == operation_token_type {
+            return Some((operation_type.clone(), *precedence, *associativity));
+        }
+    }
+
+    None
+}
+
+fn parse_cast(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Option<Expression>, Box<dyn Error>>
 {
-    parse_operation_order(tokens, &parse_term, &ARITHMATIC_OPERATIONS)
+    let value_or_none = parse_term(tokens)?;
+    if value_or_none.is_none() {
+        return Ok(None);
+    }
+
+    let mut value = value_or_none.unwrap();
+    while tokens.is_next(TokenType::As)
+    {
+        let as_token = tokens.expect(TokenType::As)?;
+
+        // NOTE: Only primitive types can be cast to, so the type is always
+        //       a single keyword. This way a following identifier isn't
+        //       mistaken for a generic type.
+        let type_token = tokens.next();
+        let data_type = type_token.as_ref()
+            .and_then(|token| primitive_type(token.token_type()));
+        if data_type.is_none()
+        {
+            return Err(Box::from(UnexpectedError
+            {
+                expected: TokenType::Int,
+                got: type_token,
+            }));
+        }
+
+        value = Expression::Cast(Cast
+        {
+            value: Box::from(value),
+            data_type: data_type.unwrap(),
+            token: as_token,
+        });
+    }
+
+    Ok(Some(value))
 }
 
-fn parse_logic(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+/// Parse an expression where every binary operation binds at least
+/// as tightly as `min_precedence`.
+fn parse_binary_operation(tokens: &mut Peekable<impl Iterator<Item = Token>>,
+                          min_precedence: u8)
     -> Result<Option<Expression>, Box<dyn Error>>
 {
-    parse_operation_order(tokens, &parse_arithmatic, &LOGIC_OPERATIONS)
+    let lhs_or_none = parse_cast(tokens)?;
+    if lhs_or_none.is_none() {
+        return Ok(None);
+    }
+
+    let mut lhs = lhs_or_none.unwrap();
+    loop
+    {
+        let (operation, precedence, associativity) = match next_binary_operation(tokens)
+        {
+            Some(next) if next.1 >= min_precedence => next,
+            _ => break,
+        };
+
+        let token = tokens.next().unwrap();
+        let rhs_min_precedence = match associativity
+        {
+            Associativity::Left => precedence + 1,
+            Associativity::Right => precedence,
+        };
+
+        let rhs = match parse_binary_operation(tokens, rhs_min_precedence)?
+        {
+            Some(rhs) => rhs,
+            None =>
+            {
+                let message = format!("Expected a value after '{}'", token.content());
+                return Err(Box::from(UnexpectedError
+                {
+                    expected: TokenType::Identifier,
+                    got: Some(token.with_content(TokenType::Error, &message)),
+                }));
+            },
+        };
+
+        lhs = Expression::Operation(Operation
+        {
+            operation_type: operation,
+            lhs: Box::from(lhs),
+            rhs: Some(Box::from(rhs)),
+            token,
+        });
+    }
+
+    Ok(Some(lhs))
 }
 
 pub fn parse_expression(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Option<Expression>, Box<dyn Error>>
 {
-    parse_operation_order(tokens, &parse_logic, &EXPRESSION_OPERATIONS)
+    parse_binary_operation(tokens, 0)
 }
-
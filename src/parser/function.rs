@@ -1,9 +1,9 @@
 use super::TokenStream;
 use super::statement::parse_block;
 use super::data_type::{parse_data_type, parse_data_type_description};
-use super::data_type::parse_type_variable;
+use super::data_type::parse_bounded_type_variable;
 use crate::tokenizer::{Token, TokenType};
-use crate::ast::{Function, Param, Statement};
+use crate::ast::{Function, Param, Statement, ROOT_MODULE};
 use crate::data_type::DataType;
 use std::iter::Peekable;
 use std::error::Error;
@@ -16,7 +16,10 @@ fn parse_function_params(tokens: &mut Peekable<impl Iterator<Item = Token>>)
 
     loop
     {
-        if !tokens.is_next(TokenType::Identifier) {
+        let is_mutable = tokens.is_next(TokenType::Mut);
+        if is_mutable {
+            tokens.expect(TokenType::Mut)?;
+        } else if !tokens.is_next(TokenType::Identifier) {
             break;
         }
 
@@ -27,6 +30,7 @@ fn parse_function_params(tokens: &mut Peekable<impl Iterator<Item = Token>>)
         params.push(Param
         {
             name,
+            is_mutable,
             data_type_description,
         });
 
@@ -68,7 +72,7 @@ pub fn parse_function(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     let name = tokens.expect(TokenType::Identifier)?;
     let params = parse_function_params(tokens)?;
     let return_type = parse_function_return_type(tokens)?;
-    let type_variable = parse_type_variable(tokens)?;
+    let (type_variable, type_variable_bounds) = parse_bounded_type_variable(tokens)?;
     let body = parse_function_body(tokens)?;
 
     Ok(Function
@@ -76,8 +80,11 @@ pub fn parse_function(tokens: &mut Peekable<impl Iterator<Item = Token>>)
         name,
         params,
         type_variable,
+        type_variable_bounds,
         return_type,
         body,
+        module: ROOT_MODULE,
+        is_exported: false,
     })
 }
 
@@ -2,9 +2,17 @@ mod function;
 mod statement;
 mod expression;
 mod struct_;
+mod enum_;
 mod data_type;
+mod use_;
+mod trait_;
+mod const_;
 use function::parse_function;
 use struct_::parse_struct;
+use enum_::parse_enum;
+use trait_::parse_trait;
+use use_::parse_use;
+use const_::parse_const;
 use crate::tokenizer::{tokenize, Token, TokenType};
 use crate::ast::SourceFile;
 use std::fmt;
@@ -30,6 +38,14 @@ impl fmt::Display for UnexpectedError
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
     {
+        // Tokenizer errors already describe what went wrong
+        if let Some(token) = &self.got
+        {
+            if token.token_type() == &TokenType::Error {
+                return write!(f, "{}: {}", token.position(), token.content());
+            }
+        }
+
         let (content, position) = match &self.got
         {
             Some(token) => (token.content(), token.position()),
@@ -84,6 +100,68 @@ impl<Iter> TokenStream for Peekable<Iter>
 
 }
 
+fn parse_export(tokens: &mut Peekable<impl Iterator<Item = Token>>,
+                output: &mut SourceFile)
+    -> Result<(), Box<dyn Error>>
+{
+    let token_or_none = tokens.next();
+    match token_or_none.as_ref().map(|token| token.token_type())
+    {
+        Some(TokenType::Fun) =>
+        {
+            let mut function = parse_function(tokens)?;
+            function.is_exported = true;
+            output.functions.push(function);
+        },
+
+        Some(TokenType::Struct) =>
+        {
+            let mut struct_ = parse_struct(tokens)?;
+            struct_.is_exported = true;
+            output.structs.push(struct_);
+        },
+
+        Some(TokenType::Enum) =>
+        {
+            let mut enum_ = parse_enum(tokens)?;
+            enum_.is_exported = true;
+            output.enums.push(enum_);
+        },
+
+        Some(TokenType::Trait) =>
+        {
+            let mut trait_ = parse_trait(tokens)?;
+            trait_.is_exported = true;
+            output.traits.push(trait_);
+        },
+
+        Some(TokenType::Const) =>
+        {
+            let mut const_ = parse_const(tokens)?;
+            const_.is_exported = true;
+            output.consts.push(const_);
+        },
+
+        Some(TokenType::Use) =>
+        {
+            let mut use_ = parse_use(tokens)?;
+            use_.is_exported = true;
+            output.uses.push(use_);
+        },
+
+        _ =>
+        {
+            return Err(Box::from(UnexpectedError
+            {
+                expected: TokenType::Fun,
+                got: token_or_none,
+            }));
+        },
+    }
+
+    Ok(())
+}
+
 pub fn parse(source_file_path: impl AsRef<Path>)
     -> Result<SourceFile, Box<dyn Error>>
 {
@@ -104,7 +182,12 @@ pub fn parse(source_file_path: impl AsRef<Path>)
         {
             TokenType::Fun => output.functions.push(parse_function(&mut tokens)?),
             TokenType::Struct => output.structs.push(parse_struct(&mut tokens)?),
+            TokenType::Enum => output.enums.push(parse_enum(&mut tokens)?),
+            TokenType::Trait => output.traits.push(parse_trait(&mut tokens)?),
+            TokenType::Const => output.consts.push(parse_const(&mut tokens)?),
             TokenType::Extern => output.externs.push(tokens.next().unwrap()),
+            TokenType::Use => output.uses.push(parse_use(&mut tokens)?),
+            TokenType::Export => parse_export(&mut tokens, &mut output)?,
 
             _ =>
             {
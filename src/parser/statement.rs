@@ -1,7 +1,8 @@
 use super::TokenStream;
 use super::expression::parse_expression;
+use super::data_type::parse_data_type;
 use crate::tokenizer::{Token, TokenType};
-use crate::ast::{Statement, Let, If};
+use crate::ast::{Statement, Let, LetTuple, TupleBinding, If, For, Iterable, Match, MatchArm};
 use std::iter::Peekable;
 use std::error::Error;
 
@@ -27,11 +28,93 @@ fn parse_expression_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>
     }
 }
 
+fn parse_tuple_bindings(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Vec<TupleBinding>, Box<dyn Error>>
+{
+    tokens.expect(TokenType::OpenBracket)?;
+    let mut bindings = Vec::new();
+    loop
+    {
+        let is_mutable = tokens.is_next(TokenType::Mut);
+        if is_mutable {
+            tokens.expect(TokenType::Mut)?;
+        }
+
+        let name = tokens.expect(TokenType::Identifier)?;
+        bindings.push(TupleBinding
+        {
+            name,
+            is_mutable,
+        });
+
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseBracket)?;
+
+    Ok(bindings)
+}
+
+fn parse_let_tuple_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Option<Statement>, Box<dyn Error>>
+{
+    let bindings = parse_tuple_bindings(tokens)?;
+    let mut data_type = None;
+    if tokens.is_next(TokenType::Colon)
+    {
+        tokens.expect(TokenType::Colon)?;
+        data_type = Some(parse_data_type(tokens)?);
+    }
+
+    tokens.expect(TokenType::Equals)?;
+    let value = parse_expression(tokens)?;
+    if value.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(Statement::LetTuple(LetTuple
+    {
+        bindings,
+        data_type,
+        value: value.unwrap(),
+    })))
+}
+
 fn parse_let_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Option<Statement>, Box<dyn Error>>
 {
     tokens.expect(TokenType::Let)?;
+    if tokens.is_next(TokenType::OpenBracket) {
+        return parse_let_tuple_statement(tokens);
+    }
+
+    let is_mutable = tokens.is_next(TokenType::Mut);
+    if is_mutable {
+        tokens.expect(TokenType::Mut)?;
+    }
+
     let name = tokens.expect(TokenType::Identifier)?;
+    let mut data_type = None;
+    if tokens.is_next(TokenType::Colon)
+    {
+        tokens.expect(TokenType::Colon)?;
+        data_type = Some(parse_data_type(tokens)?);
+
+        // NOTE: Variables with a type don't need a value.
+        if !tokens.is_next(TokenType::Equals)
+        {
+            return Ok(Some(Statement::Let(Let
+            {
+                name,
+                is_mutable,
+                data_type,
+                value: None,
+            })));
+        }
+    }
+
     tokens.expect(TokenType::Equals)?;
     let value = parse_expression(tokens)?;
     if value.is_none() {
@@ -41,7 +124,9 @@ fn parse_let_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     return Ok(Some(Statement::Let(Let
     {
         name: name,
-        value: value.unwrap(),
+        is_mutable,
+        data_type,
+        value,
     })));
 }
 
@@ -75,6 +160,14 @@ fn parse_else(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     if tokens.is_next(TokenType::Else)
     {
         tokens.expect(TokenType::Else)?;
+
+        // NOTE: `else if` is an else block holding just the next if.
+        if tokens.is_next(TokenType::If)
+        {
+            let if_ = parse_if_statement(tokens)?;
+            return Ok(Some(vec![if_.unwrap()]));
+        }
+
         tokens.expect(TokenType::Arrow)?;
         Ok(Some(parse_block(tokens)?))
     }
@@ -122,11 +215,126 @@ fn parse_while_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     Ok(Some(Statement::While(condition.unwrap(), block)))
 }
 
+fn parse_for_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Option<Statement>, Box<dyn Error>>
+{
+    tokens.expect(TokenType::For)?;
+    let variable = tokens.expect(TokenType::Identifier)?;
+    tokens.expect(TokenType::In)?;
+
+    let start = parse_expression(tokens)?;
+    assert!(start.is_some());
+
+    let iterable =
+        if tokens.is_next(TokenType::DoubleDot)
+        {
+            tokens.expect(TokenType::DoubleDot)?;
+            let end = parse_expression(tokens)?;
+            assert!(end.is_some());
+
+            Iterable::Range(start.unwrap(), end.unwrap())
+        }
+        else
+        {
+            Iterable::Collection(start.unwrap())
+        };
+
+    tokens.expect(TokenType::Arrow)?;
+    let block = parse_block(tokens)?;
+    Ok(Some(Statement::For(For
+    {
+        variable,
+        iterable,
+        block,
+    })))
+}
+
+fn parse_match_bindings(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Option<Vec<Token>>, Box<dyn Error>>
+{
+    if !tokens.is_next(TokenType::OpenBracket) {
+        return Ok(None);
+    }
+
+    tokens.expect(TokenType::OpenBracket)?;
+    let mut bindings = Vec::new();
+    loop
+    {
+        if !tokens.is_next(TokenType::Identifier) {
+            break;
+        }
+
+        bindings.push(tokens.next().unwrap());
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseBracket)?;
+
+    Ok(Some(bindings))
+}
+
+fn parse_match_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Option<Statement>, Box<dyn Error>>
+{
+    let token = tokens.expect(TokenType::Match)?;
+    let value = parse_expression(tokens)?;
+    assert!(value.is_some());
+
+    tokens.expect(TokenType::OpenSquiggly)?;
+    let mut arms = Vec::new();
+    let mut else_block = None;
+    loop
+    {
+        // NOTE: `else` matches every variant not already matched,
+        //       so has to be the last arm.
+        if tokens.is_next(TokenType::Else)
+        {
+            tokens.expect(TokenType::Else)?;
+            tokens.expect(TokenType::Arrow)?;
+            else_block = Some(parse_block(tokens)?);
+            break;
+        }
+
+        if !tokens.is_next(TokenType::Identifier) {
+            break;
+        }
+
+        let variant = tokens.next().unwrap();
+        let bindings = parse_match_bindings(tokens)?;
+        tokens.expect(TokenType::Arrow)?;
+        let block = parse_block(tokens)?;
+        arms.push(MatchArm
+        {
+            variant,
+            bindings,
+            block,
+        });
+    }
+    tokens.expect(TokenType::CloseSquiggly)?;
+
+    Ok(Some(Statement::Match(Match
+    {
+        token,
+        value: value.unwrap(),
+        arms,
+        else_block,
+    })))
+}
+
 fn parse_break_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Option<Statement>, Box<dyn Error>>
 {
-    tokens.expect(TokenType::Break)?;
-    Ok(Some(Statement::Break))
+    let token = tokens.expect(TokenType::Break)?;
+    Ok(Some(Statement::Break(token)))
+}
+
+fn parse_continue_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Option<Statement>, Box<dyn Error>>
+{
+    let token = tokens.expect(TokenType::Continue)?;
+    Ok(Some(Statement::Continue(token)))
 }
 
 pub fn parse_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
@@ -144,7 +352,10 @@ pub fn parse_statement(tokens: &mut Peekable<impl Iterator<Item = Token>>)
         TokenType::If => Ok(parse_if_statement(tokens)?),
         TokenType::Loop => Ok(parse_loop_statement(tokens)?),
         TokenType::While => Ok(parse_while_statement(tokens)?),
+        TokenType::For => Ok(parse_for_statement(tokens)?),
+        TokenType::Match => Ok(parse_match_statement(tokens)?),
         TokenType::Break => Ok(parse_break_statement(tokens)?),
+        TokenType::Continue => Ok(parse_continue_statement(tokens)?),
         _ => Ok(parse_expression_statement(tokens)?),
     }
 }
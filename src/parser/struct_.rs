@@ -1,7 +1,7 @@
 use super::TokenStream;
-use super::data_type::{parse_data_type, parse_type_variable};
+use super::data_type::{parse_data_type, parse_type_variable, parse_implemented_traits};
 use crate::tokenizer::{Token, TokenType};
-use crate::ast::{Struct, Field};
+use crate::ast::{Struct, Field, ROOT_MODULE};
 use std::iter::Peekable;
 use std::error::Error;
 
@@ -9,6 +9,7 @@ pub fn parse_struct(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     -> Result<Struct, Box<dyn Error>>
 {
     let name = tokens.expect(TokenType::Identifier)?;
+    let traits = parse_implemented_traits(tokens)?;
     let type_variable = parse_type_variable(tokens)?;
 
     tokens.expect(TokenType::OpenSquiggly)?;
@@ -40,7 +41,10 @@ pub fn parse_struct(tokens: &mut Peekable<impl Iterator<Item = Token>>)
     {
         name,
         type_variable,
+        traits,
         fields,
+        module: ROOT_MODULE,
+        is_exported: false,
     })
 }
 
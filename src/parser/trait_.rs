@@ -0,0 +1,69 @@
+use super::TokenStream;
+use super::data_type::{parse_data_type, parse_type_variable_list};
+use super::data_type::parse_implemented_traits;
+use crate::tokenizer::{Token, TokenType};
+use crate::ast::{Trait, TraitFunction, ROOT_MODULE};
+use std::iter::Peekable;
+use std::error::Error;
+
+fn parse_trait_function(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<TraitFunction, Box<dyn Error>>
+{
+    tokens.expect(TokenType::Fun)?;
+    let name = tokens.expect(TokenType::Identifier)?;
+
+    tokens.expect(TokenType::OpenBracket)?;
+    let mut params = Vec::new();
+    while !tokens.is_next(TokenType::CloseBracket)
+    {
+        params.push(parse_data_type(tokens)?);
+        if !tokens.is_next(TokenType::Comma) {
+            break;
+        }
+        tokens.expect(TokenType::Comma)?;
+    }
+    tokens.expect(TokenType::CloseBracket)?;
+
+    let return_type =
+        if tokens.is_next(TokenType::Arrow)
+        {
+            tokens.expect(TokenType::Arrow)?;
+            Some(parse_data_type(tokens)?)
+        }
+        else
+        {
+            None
+        };
+
+    Ok(TraitFunction
+    {
+        name,
+        params,
+        return_type,
+    })
+}
+
+pub fn parse_trait(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Trait, Box<dyn Error>>
+{
+    let name = tokens.expect(TokenType::Identifier)?;
+    let supertraits = parse_implemented_traits(tokens)?;
+    let type_variables = parse_type_variable_list(tokens)?;
+
+    tokens.expect(TokenType::OpenSquiggly)?;
+    let mut functions = Vec::new();
+    while tokens.is_next(TokenType::Fun) {
+        functions.push(parse_trait_function(tokens)?);
+    }
+    tokens.expect(TokenType::CloseSquiggly)?;
+
+    Ok(Trait
+    {
+        name,
+        type_variables,
+        supertraits,
+        functions,
+        module: ROOT_MODULE,
+        is_exported: false,
+    })
+}
@@ -0,0 +1,22 @@
+use super::TokenStream;
+use crate::tokenizer::{Token, TokenType};
+use crate::ast::Use;
+use std::iter::Peekable;
+use std::error::Error;
+
+pub fn parse_use(tokens: &mut Peekable<impl Iterator<Item = Token>>)
+    -> Result<Use, Box<dyn Error>>
+{
+    let mut path = vec![tokens.expect(TokenType::Identifier)?];
+    while tokens.is_next(TokenType::DoubleColon)
+    {
+        tokens.expect(TokenType::DoubleColon)?;
+        path.push(tokens.expect(TokenType::Identifier)?);
+    }
+
+    Ok(Use
+    {
+        path,
+        is_exported: false,
+    })
+}
@@ -13,19 +13,39 @@ pub enum TokenType
     Return,
     Struct,
     Let,
+    Mut,
     If,
     Else,
     Loop,
     While,
+    For,
+    In,
     Break,
+    Continue,
     Extern,
     Ref,
     Deref,
     Of,
     New,
     Sizeof,
+    Use,
+    Export,
+    Trait,
+    Const,
+    As,
+    Enum,
+    Match,
 
     Int,
+    I8,
+    I16,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
     Char,
     Bool,
     Any,
@@ -37,18 +57,46 @@ pub enum TokenType
     OpenSquare,
     CloseSquare,
     Colon,
+    DoubleColon,
     Comma,
     Arrow,
 
     Plus,
     Star,
+    Slash,
+    Percent,
     Minus,
     GreaterThan,
     LessThan,
+    GreaterThanEquals,
+    LessThanEquals,
+    DoubleEquals,
+    NotEquals,
+    Not,
+    Ampersand,
+    DoubleAmpersand,
+    Pipe,
+    DoublePipe,
+    Caret,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
     Dot,
+    DoubleDot,
     Equals,
+    PlusEquals,
+    MinusEquals,
+    StarEquals,
+    SlashEquals,
+    PercentEquals,
+    AmpersandEquals,
+    PipeEquals,
+    CaretEquals,
+    ShiftLeftEquals,
+    ShiftRightEquals,
 
     IntLiteral,
+    FloatLiteral,
     StringLiteral,
     CharLiteral,
     BoolLiteral,
@@ -68,19 +116,39 @@ impl fmt::Display for TokenType
             Self::Return => write!(f, "return"),
             Self::Struct => write!(f, "struct"),
             Self::Let => write!(f, "let"),
+            Self::Mut => write!(f, "mut"),
             Self::If => write!(f, "if"),
             Self::Else => write!(f, "else"),
             Self::Loop => write!(f, "loop"),
             Self::While => write!(f, "while"),
+            Self::For => write!(f, "for"),
+            Self::In => write!(f, "in"),
             Self::Break => write!(f, "break"),
+            Self::Continue => write!(f, "continue"),
             Self::Extern => write!(f, "extern"),
             Self::Ref => write!(f, "ref"),
             Self::Deref => write!(f, "deref"),
             Self::Of => write!(f, "of"),
             Self::New => write!(f, "new"),
             Self::Sizeof => write!(f, "sizeof"),
+            Self::Use => write!(f, "use"),
+            Self::Export => write!(f, "export"),
+            Self::Trait => write!(f, "trait"),
+            Self::Const => write!(f, "const"),
+            Self::As => write!(f, "as"),
+            Self::Enum => write!(f, "enum"),
+            Self::Match => write!(f, "match"),
 
             Self::Int => write!(f, "int"),
+            Self::I8 => write!(f, "i8"),
+            Self::I16 => write!(f, "i16"),
+            Self::I64 => write!(f, "i64"),
+            Self::U8 => write!(f, "u8"),
+            Self::U16 => write!(f, "u16"),
+            Self::U32 => write!(f, "u32"),
+            Self::U64 => write!(f, "u64"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
             Self::Char => write!(f, "char"),
             Self::Bool => write!(f, "bool"),
             Self::Any => write!(f, "any"),
@@ -92,18 +160,46 @@ impl fmt::Display for TokenType
             Self::OpenSquare => write!(f, "["),
             Self::CloseSquare => write!(f, "]"),
             Self::Colon => write!(f, ":"),
+            Self::DoubleColon => write!(f, "::"),
             Self::Comma => write!(f, ","),
             Self::Arrow => write!(f, "->"),
 
             Self::Plus => write!(f, "+"),
             Self::Star => write!(f, "*"),
+            Self::Slash => write!(f, "/"),
+            Self::Percent => write!(f, "%"),
             Self::Minus => write!(f, "-"),
             Self::GreaterThan => write!(f, ">"),
             Self::LessThan => write!(f, "<"),
+            Self::GreaterThanEquals => write!(f, ">="),
+            Self::LessThanEquals => write!(f, "<="),
+            Self::DoubleEquals => write!(f, "=="),
+            Self::NotEquals => write!(f, "!="),
+            Self::Not => write!(f, "!"),
+            Self::Ampersand => write!(f, "&"),
+            Self::DoubleAmpersand => write!(f, "&&"),
+            Self::Pipe => write!(f, "|"),
+            Self::DoublePipe => write!(f, "||"),
+            Self::Caret => write!(f, "^"),
+            Self::Tilde => write!(f, "~"),
+            Self::ShiftLeft => write!(f, "<<"),
+            Self::ShiftRight => write!(f, ">>"),
             Self::Dot => write!(f, "."),
+            Self::DoubleDot => write!(f, ".."),
             Self::Equals => write!(f, "="),
+            Self::PlusEquals => write!(f, "+="),
+            Self::MinusEquals => write!(f, "-="),
+            Self::StarEquals => write!(f, "*="),
+            Self::SlashEquals => write!(f, "/="),
+            Self::PercentEquals => write!(f, "%="),
+            Self::AmpersandEquals => write!(f, "&="),
+            Self::PipeEquals => write!(f, "|="),
+            Self::CaretEquals => write!(f, "^="),
+            Self::ShiftLeftEquals => write!(f, "<<="),
+            Self::ShiftRightEquals => write!(f, ">>="),
 
             Self::IntLiteral => write!(f, "Int Literal"),
+            Self::FloatLiteral => write!(f, "Float Literal"),
             Self::StringLiteral => write!(f, "String Literal"),
             Self::CharLiteral => write!(f, "Char Literal"),
             Self::BoolLiteral => write!(f, "Bool Literal"),
@@ -145,6 +241,13 @@ impl Token
         }
     }
 
+    /// A token at the same position as this one, for code the
+    /// compiler generates itself.
+    pub fn with_content(&self, token_type: TokenType, content: &str) -> Self
+    {
+        Self::new(token_type, content, self.position.clone())
+    }
+
     pub fn token_type(&self) -> &TokenType
     {
         &self.token_type
@@ -155,6 +258,41 @@ impl Token
         &self.content
     }
 
+    /// The bytes of a string or char literal, with escapes replaced.
+    pub fn unescaped_content(&self) -> Vec<u8>
+    {
+        // NOTE: Escapes are checked when the literal is tokenized.
+        unescape(&self.content).unwrap()
+    }
+
+    /// The value of an int literal's digits. Whether it fits in the
+    /// type given by its suffix is checked by the parser.
+    pub fn int_bits(&self) -> u64
+    {
+        // NOTE: Int literals are checked when they're tokenized.
+        let (value, _) = parse_int_literal_digits(&self.content).unwrap();
+        value
+    }
+
+    /// The type suffix of an int literal, like the `u8` in `255u8`.
+    pub fn int_suffix(&self) -> Option<&'static str>
+    {
+        split_int_literal_suffix(&self.content).1
+    }
+
+    /// The value of a float literal.
+    pub fn float_value(&self) -> f64
+    {
+        // NOTE: Float literals are checked when they're tokenized.
+        parse_float_literal(&self.content).unwrap()
+    }
+
+    /// The type suffix of a float literal, like the `f32` in `1.5f32`.
+    pub fn float_suffix(&self) -> Option<&'static str>
+    {
+        split_float_literal_suffix(&self.content).1
+    }
+
     pub fn position(&self) -> String
     {
         format!("{}:{}:{}",
@@ -202,15 +340,59 @@ enum State
     Initial,
     Identifier,
     Number,
+    NumberDot,
+    Float,
     String,
+    StringEscape,
     Char,
+    CharEscape,
     Slash,
     SingleLineComment,
     MultiLineComment,
     MultiLineCommentStar,
     DoubleToken(char, char, TokenType, TokenType),
+    ChoiceToken(char, TokenType, &'static [(char, TokenType)]),
+    CompoundAssignToken(TokenType, TokenType),
 }
 
+static MINUS_CHOICES: [(char, TokenType); 2] =
+[
+    ('>', TokenType::Arrow),
+    ('=', TokenType::MinusEquals),
+];
+
+static GREATER_THAN_CHOICES: [(char, TokenType); 2] =
+[
+    ('=', TokenType::GreaterThanEquals),
+    ('>', TokenType::ShiftRight),
+];
+
+static LESS_THAN_CHOICES: [(char, TokenType); 2] =
+[
+    ('=', TokenType::LessThanEquals),
+    ('<', TokenType::ShiftLeft),
+];
+
+static AMPERSAND_CHOICES: [(char, TokenType); 2] =
+[
+    ('&', TokenType::DoubleAmpersand),
+    ('=', TokenType::AmpersandEquals),
+];
+
+static PIPE_CHOICES: [(char, TokenType); 2] =
+[
+    ('|', TokenType::DoublePipe),
+    ('=', TokenType::PipeEquals),
+];
+
+/// Two character tokens that make a compound assignment when
+/// followed by a `=`.
+static COMPOUND_ASSIGN_TOKENS: [(TokenType, TokenType); 2] =
+[
+    (TokenType::ShiftLeft, TokenType::ShiftLeftEquals),
+    (TokenType::ShiftRight, TokenType::ShiftRightEquals),
+];
+
 enum StateTransition
 {
     Keep(State),
@@ -245,6 +427,28 @@ impl Lexer
         self.buffer_start_position = None;
     }
 
+    /// Emit the buffer as a token if it passes `check`, otherwise
+    /// emit an error token with the reason it doesn't.
+    fn emit_checked_buffer_as_token(&mut self, token_type: TokenType,
+                                    check: fn(&str) -> Result<(), String>)
+    {
+        let error = from_utf8(&self.buffer).ok()
+            .and_then(|text| check(text).err());
+
+        match error
+        {
+            Some(message) =>
+            {
+                let position = self.buffer_start_position.clone().unwrap();
+                self.tokens.push(Token::new(TokenType::Error, &message, position));
+                self.buffer.clear();
+                self.buffer_start_position = None;
+            },
+
+            None => self.emit_buffer_as_token(token_type),
+        }
+    }
+
 }
 
 fn handle_initial(byte: u8,
@@ -269,15 +473,21 @@ fn handle_initial(byte: u8,
         '}' => emit(TokenType::CloseSquiggly, "}"),
         '[' => emit(TokenType::OpenSquare, "["),
         ']' => emit(TokenType::CloseSquare, "]"),
-        ':' => emit(TokenType::Colon, ":"),
         ',' => emit(TokenType::Comma, ","),
-        '+' => emit(TokenType::Plus, "+"),
-        '*' => emit(TokenType::Star, "*"),
-        '>' => emit(TokenType::GreaterThan, ">"),
-        '<' => emit(TokenType::LessThan, "<"),
-        '.' => emit(TokenType::Dot, "."),
-        '=' => emit(TokenType::Equals, "="),
-        '-' => StateTransition::Consume(State::DoubleToken('-', '>', TokenType::Minus, TokenType::Arrow)),
+        '+' => StateTransition::Consume(State::DoubleToken('+', '=', TokenType::Plus, TokenType::PlusEquals)),
+        '*' => StateTransition::Consume(State::DoubleToken('*', '=', TokenType::Star, TokenType::StarEquals)),
+        '%' => StateTransition::Consume(State::DoubleToken('%', '=', TokenType::Percent, TokenType::PercentEquals)),
+        '^' => StateTransition::Consume(State::DoubleToken('^', '=', TokenType::Caret, TokenType::CaretEquals)),
+        '~' => emit(TokenType::Tilde, "~"),
+        '>' => StateTransition::Consume(State::ChoiceToken('>', TokenType::GreaterThan, &GREATER_THAN_CHOICES)),
+        '<' => StateTransition::Consume(State::ChoiceToken('<', TokenType::LessThan, &LESS_THAN_CHOICES)),
+        '!' => StateTransition::Consume(State::DoubleToken('!', '=', TokenType::Not, TokenType::NotEquals)),
+        '&' => StateTransition::Consume(State::ChoiceToken('&', TokenType::Ampersand, &AMPERSAND_CHOICES)),
+        '|' => StateTransition::Consume(State::ChoiceToken('|', TokenType::Pipe, &PIPE_CHOICES)),
+        '.' => StateTransition::Consume(State::DoubleToken('.', '.', TokenType::Dot, TokenType::DoubleDot)),
+        '=' => StateTransition::Consume(State::DoubleToken('=', '=', TokenType::Equals, TokenType::DoubleEquals)),
+        '-' => StateTransition::Consume(State::ChoiceToken('-', TokenType::Minus, &MINUS_CHOICES)),
+        ':' => StateTransition::Consume(State::DoubleToken(':', ':', TokenType::Colon, TokenType::DoubleColon)),
         '#' => StateTransition::Consume(State::SingleLineComment),
         '/' => StateTransition::Consume(State::Slash),
         '\"' => StateTransition::Consume(State::String),
@@ -302,6 +512,8 @@ fn parse_identifier(text: &str) -> TokenType
         TokenType::Struct
     } else if text == "let" {
         TokenType::Let
+    } else if text == "mut" {
+        TokenType::Mut
     } else if text == "if" {
         TokenType::If
     } else if text == "else" {
@@ -310,16 +522,40 @@ fn parse_identifier(text: &str) -> TokenType
         TokenType::Loop
     } else if text == "while" {
         TokenType::While
+    } else if text == "for" {
+        TokenType::For
+    } else if text == "in" {
+        TokenType::In
     } else if text == "break" {
         TokenType::Break
+    } else if text == "continue" {
+        TokenType::Continue
     } else if text == "extern" {
         TokenType::Extern
     } else if text == "ref" {
         TokenType::Ref
     } else if text == "deref" {
         TokenType::Deref
-    } else if text == "int" {
+    } else if text == "int" || text == "i32" {
         TokenType::Int
+    } else if text == "i8" {
+        TokenType::I8
+    } else if text == "i16" {
+        TokenType::I16
+    } else if text == "i64" {
+        TokenType::I64
+    } else if text == "u8" {
+        TokenType::U8
+    } else if text == "u16" {
+        TokenType::U16
+    } else if text == "u32" {
+        TokenType::U32
+    } else if text == "u64" {
+        TokenType::U64
+    } else if text == "f32" {
+        TokenType::F32
+    } else if text == "f64" {
+        TokenType::F64
     } else if text == "char" {
         TokenType::Char
     } else if text == "bool" {
@@ -332,6 +568,20 @@ fn parse_identifier(text: &str) -> TokenType
         TokenType::New
     } else if text == "sizeof" {
         TokenType::Sizeof
+    } else if text == "use" {
+        TokenType::Use
+    } else if text == "export" {
+        TokenType::Export
+    } else if text == "trait" {
+        TokenType::Trait
+    } else if text == "const" {
+        TokenType::Const
+    } else if text == "as" {
+        TokenType::As
+    } else if text == "enum" {
+        TokenType::Enum
+    } else if text == "match" {
+        TokenType::Match
     } else if text == "true" {
         TokenType::BoolLiteral
     } else if text == "false" {
@@ -380,30 +630,300 @@ fn handle_number(byte: u8, lexer: &mut Lexer) -> StateTransition
 {
     match byte as char
     {
-        '0'..='9' =>
+        // NOTE: Letters are allowed here for the '0x', '0b' and '0o'
+        //       prefixes, hex digits and type suffixes, they're checked
+        //       once the whole literal has been read.
+        '0'..='9' | 'a'..='z' | 'A'..='Z' | '_' =>
         {
             lexer.buffer.push(byte);
             StateTransition::Consume(State::Number)
         }
 
+        '.' => StateTransition::Consume(State::NumberDot),
+
         _ =>
         {
-            lexer.emit_buffer_as_token(TokenType::IntLiteral);
+            lexer.emit_checked_buffer_as_token(TokenType::IntLiteral, check_int_literal);
             StateTransition::Keep(State::Initial)
         },
     }
 }
 
+/// A number followed by a dot is only a float literal if a digit comes
+/// next, otherwise it's an int followed by a `.` or `..`, like `0..10`.
+/// A number straight after a dot is a tuple index, so `t.0.1` is two
+/// indices rather than the float `0.1`.
+fn handle_number_dot(byte: u8, position: TokenPosition, lexer: &mut Lexer) -> StateTransition
+{
+    let is_tuple_index = matches!(
+        lexer.tokens.last().map(|token| token.token_type()),
+        Some(TokenType::Dot));
+
+    match byte as char
+    {
+        '0'..='9' if !is_tuple_index =>
+        {
+            lexer.buffer.push(b'.');
+            lexer.buffer.push(byte);
+            StateTransition::Consume(State::Float)
+        },
+
+        _ =>
+        {
+            // NOTE: The dot has already been read, so the one after
+            //       it is as close as we can place it.
+            lexer.emit_checked_buffer_as_token(TokenType::IntLiteral, check_int_literal);
+            lexer.buffer_start_position = Some(position);
+            StateTransition::Keep(State::DoubleToken('.', '.', TokenType::Dot, TokenType::DoubleDot))
+        },
+    }
+}
+
+fn handle_float(byte: u8, lexer: &mut Lexer) -> StateTransition
+{
+    let is_exponent_sign = (byte == b'+' || byte == b'-') &&
+        matches!(lexer.buffer.last(), Some(b'e') | Some(b'E'));
+
+    match byte as char
+    {
+        '0'..='9' | 'a'..='z' | 'A'..='Z' | '_' =>
+        {
+            lexer.buffer.push(byte);
+            StateTransition::Consume(State::Float)
+        },
+
+        _ if is_exponent_sign =>
+        {
+            lexer.buffer.push(byte);
+            StateTransition::Consume(State::Float)
+        },
+
+        _ =>
+        {
+            lexer.emit_checked_buffer_as_token(TokenType::FloatLiteral, check_float_literal);
+            StateTransition::Keep(State::Initial)
+        },
+    }
+}
+
+/// The suffixes that give an int literal an exact type, like `255u8`,
+/// with the number of bits in that type and whether it is signed.
+const INT_LITERAL_SUFFIXES: [(&str, u32, bool); 8] =
+[
+    ("i8", 8, true),
+    ("i16", 16, true),
+    ("i32", 32, true),
+    ("i64", 64, true),
+    ("u8", 8, false),
+    ("u16", 16, false),
+    ("u32", 32, false),
+    ("u64", 64, false),
+];
+
+/// Split an int literal into its digits and type suffix, if it has one.
+pub fn split_int_literal_suffix(text: &str) -> (&str, Option<&'static str>)
+{
+    for (suffix, _, _) in &INT_LITERAL_SUFFIXES
+    {
+        if let Some(digits) = text.strip_suffix(suffix) {
+            return (digits, Some(suffix));
+        }
+    }
+
+    (text, None)
+}
+
+/// The value of an int literal's digits, and their radix, whether or not
+/// it fits in its type. The digits are in decimal or have a '0x', '0b' or
+/// '0o' prefix, with optional '_' separators between them.
+fn parse_int_literal_digits(text: &str) -> Result<(u64, u32), String>
+{
+    let (text_without_suffix, _) = split_int_literal_suffix(text);
+    let (digits, radix) =
+        if let Some(digits) = text_without_suffix.strip_prefix("0x") { (digits, 16) }
+        else if let Some(digits) = text_without_suffix.strip_prefix("0b") { (digits, 2) }
+        else if let Some(digits) = text_without_suffix.strip_prefix("0o") { (digits, 8) }
+        else { (text_without_suffix, 10) };
+
+    if digits.is_empty() || digits.starts_with('_') || digits.ends_with('_') {
+        return Err(format!("Invalid int literal '{}'", text));
+    }
+
+    let digits = digits.replace('_', "");
+    if let Some(invalid) = digits.chars().find(|c| !c.is_digit(radix)) {
+        return Err(format!("Invalid digit '{}' in int literal '{}'", invalid, text));
+    }
+
+    u64::from_str_radix(&digits, radix)
+        .map(|value| (value, radix))
+        .map_err(|_| format!("Int literal '{}' is too large", text))
+}
+
+/// Parse an int literal with an optional type suffix. Without a suffix
+/// the literal is an int. Decimal literals must fit in their type, the
+/// others may use all of its bits. A negated one must be signed, and fit
+/// in its type once negated, whatever its base.
+pub fn parse_int_literal(text: &str, is_negated: bool) -> Result<u64, String>
+{
+    let (value, radix) = parse_int_literal_digits(text)?;
+    let (_, suffix) = split_int_literal_suffix(text);
+    let (bits, is_signed) = INT_LITERAL_SUFFIXES.iter()
+        .find(|(name, _, _)| Some(*name) == suffix)
+        .map(|(_, bits, is_signed)| (*bits, *is_signed))
+        .unwrap_or((32, true));
+
+    if is_negated && !is_signed {
+        return Err(format!("Can not negate unsigned int literal '{}'", text));
+    }
+
+    let max =
+        if is_negated { 1 << (bits - 1) }
+        else if radix == 10 && is_signed { u64::MAX >> (64 - bits + 1) }
+        else { u64::MAX >> (64 - bits) };
+
+    if value > max
+    {
+        let shown_text = if is_negated { format!("-{}", text) } else { text.to_owned() };
+        return Err(format!("Int literal '{}' is too large to fit in {}", shown_text,
+            suffix.map(|suffix| format!("'{}'", suffix)).unwrap_or("an int".to_owned())));
+    }
+
+    Ok(value)
+}
+
+fn check_int_literal(text: &str) -> Result<(), String>
+{
+    // NOTE: Whether the literal is negated isn't known yet, so
+    //       whether it fits in its type is left for the parser.
+    parse_int_literal_digits(text)?;
+    Ok(())
+}
+
+/// Split a float literal into its number and type suffix, if it has one.
+pub fn split_float_literal_suffix(text: &str) -> (&str, Option<&'static str>)
+{
+    for suffix in &["f32", "f64"]
+    {
+        if let Some(number) = text.strip_suffix(suffix) {
+            return (number, Some(suffix));
+        }
+    }
+
+    (text, None)
+}
+
+/// Parse a float literal, like `1.5`, `2.0e-3` or `0.25f32`, with
+/// optional '_' separators between digits. Without a suffix the
+/// literal is an `f64`.
+pub fn parse_float_literal(text: &str) -> Result<f64, String>
+{
+    let (number, suffix) = split_float_literal_suffix(text);
+    let is_valid = number.ends_with(|c: char| c.is_ascii_digit()) &&
+        !number.contains("_.") && !number.contains("._") &&
+        number.chars().all(|c| c.is_ascii_digit() || "._eE+-".contains(c));
+    if !is_valid {
+        return Err(format!("Invalid float literal '{}'", text));
+    }
+
+    let value = number.replace('_', "").parse::<f64>()
+        .map_err(|_| format!("Invalid float literal '{}'", text))?;
+
+    let is_too_large = match suffix
+    {
+        Some("f32") => (value as f32).is_infinite(),
+        _ => value.is_infinite(),
+    };
+    if is_too_large
+    {
+        return Err(format!("Float literal '{}' is too large to fit in '{}'",
+            text, suffix.unwrap_or("f64")));
+    }
+
+    Ok(value)
+}
+
+fn check_float_literal(text: &str) -> Result<(), String>
+{
+    parse_float_literal(text)?;
+    Ok(())
+}
+
+/// Replace escape sequences with the bytes they stand for.
+pub fn unescape(text: &str) -> Result<Vec<u8>, String>
+{
+    let mut bytes = Vec::new();
+    let mut chars = text.bytes();
+    while let Some(byte) = chars.next()
+    {
+        if byte != b'\\'
+        {
+            bytes.push(byte);
+            continue;
+        }
+
+        let escape = chars.next().ok_or("Unfinished escape sequence")?;
+        bytes.push(match escape
+        {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'\\' => b'\\',
+            b'\"' => b'\"',
+            b'\'' => b'\'',
+            b'0' => 0,
+
+            b'x' =>
+            {
+                let digits = [chars.next(), chars.next()];
+                let hex = match digits
+                {
+                    [Some(high), Some(low)] => [high, low],
+                    _ => return Err("Expected two hex digits after '\\x'".to_owned()),
+                };
+
+                from_utf8(&hex).ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or(format!("Invalid hex escape '\\x{}'",
+                        String::from_utf8_lossy(&hex)))?
+            },
+
+            _ => return Err(format!("Unknown escape sequence '\\{}'", escape as char)),
+        });
+    }
+
+    Ok(bytes)
+}
+
+fn check_string_literal(text: &str) -> Result<(), String>
+{
+    unescape(text)?;
+    Ok(())
+}
+
+fn check_char_literal(text: &str) -> Result<(), String>
+{
+    if unescape(text)?.len() != 1 {
+        return Err("A char literal must be a single character".to_owned());
+    }
+
+    Ok(())
+}
+
 fn handle_string(byte: u8, lexer: &mut Lexer) -> StateTransition
 {
     match byte as char
     {
         '\"' =>
         {
-            lexer.emit_buffer_as_token(TokenType::StringLiteral);
+            lexer.emit_checked_buffer_as_token(TokenType::StringLiteral, check_string_literal);
             StateTransition::Consume(State::Initial)
         },
 
+        '\\' =>
+        {
+            lexer.buffer.push(byte);
+            StateTransition::Consume(State::StringEscape)
+        },
+
         _ =>
         {
             lexer.buffer.push(byte);
@@ -418,36 +938,60 @@ fn handle_char(byte: u8, lexer: &mut Lexer) -> StateTransition
     {
         '\'' =>
         {
-            lexer.emit_buffer_as_token(TokenType::CharLiteral);
+            lexer.emit_checked_buffer_as_token(TokenType::CharLiteral, check_char_literal);
             StateTransition::Consume(State::Initial)
         },
 
+        '\\' =>
+        {
+            lexer.buffer.push(byte);
+            StateTransition::Consume(State::CharEscape)
+        },
+
         _ =>
         {
-            assert!(lexer.buffer.is_empty());
             lexer.buffer.push(byte);
             StateTransition::Consume(State::Char)
         },
     }
 }
 
-fn handle_slash(byte: u8) -> StateTransition
+/// Keep the escaped character as is, so a quote doesn't end the
+/// literal. The escape is replaced later, by `unescape`.
+fn handle_escape(byte: u8, lexer: &mut Lexer, next_state: State) -> StateTransition
 {
-    if byte as char == '*' {
-        StateTransition::Consume(State::MultiLineComment)
-    } else {
-        // FIXME: This need to be an error
-        StateTransition::Consume(State::Initial)
+    lexer.buffer.push(byte);
+    StateTransition::Consume(next_state)
+}
+
+fn handle_slash(byte: u8, lexer: &mut Lexer) -> StateTransition
+{
+    let position = lexer.buffer_start_position.clone().unwrap();
+    match byte as char
+    {
+        '*' => StateTransition::Consume(State::MultiLineComment),
+
+        '=' =>
+        {
+            lexer.tokens.push(Token::new(TokenType::SlashEquals, "/=", position));
+            StateTransition::Consume(State::Initial)
+        },
+
+        _ =>
+        {
+            lexer.tokens.push(Token::new(TokenType::Slash, "/", position));
+            StateTransition::Keep(State::Initial)
+        },
     }
 }
 
 fn handle_double_token(byte: u8,
                        first: char, second: char,
                        single: &TokenType, double: &TokenType,
-                       position: TokenPosition,
                        lexer: &mut Lexer)
     -> StateTransition
 {
+    let position = lexer.buffer_start_position.clone().unwrap();
     if byte as char == second
     {
         lexer.tokens.push(Token::new(double.clone(), &format!(
@@ -462,6 +1006,57 @@ fn handle_double_token(byte: u8,
     }
 }
 
+/// Like a double token, but the second character can be one of
+/// several, each giving a different token.
+fn handle_choice_token(byte: u8,
+                       first: char, single: &TokenType,
+                       choices: &[(char, TokenType)],
+                       lexer: &mut Lexer)
+    -> StateTransition
+{
+    let position = lexer.buffer_start_position.clone().unwrap();
+    for (second, double) in choices
+    {
+        if byte as char != *second {
+            continue;
+        }
+
+        let compound_assign = COMPOUND_ASSIGN_TOKENS.iter()
+            .find(|(token_type, _)| token_type == double);
+        if let Some((token_type, assign)) = compound_assign {
+            return StateTransition::Consume(State::CompoundAssignToken(token_type.clone(), assign.clone()));
+        }
+
+        lexer.tokens.push(Token::new(double.clone(), &format!(
+            "{}{}", first, second), position));
+        return StateTransition::Consume(State::Initial);
+    }
+
+    lexer.tokens.push(Token::new(single.clone(), &format!(
+        "{}", first), position));
+    StateTransition::Keep(State::Initial)
+}
+
+/// A two character token, or the same followed by a `=`,
+/// like `<<` and `<<=`.
+fn handle_compound_assign_token(byte: u8,
+                                single: &TokenType, assign: &TokenType,
+                                lexer: &mut Lexer)
+    -> StateTransition
+{
+    let position = lexer.buffer_start_position.clone().unwrap();
+    if byte as char == '='
+    {
+        lexer.tokens.push(Token::new(assign.clone(), &assign.to_string(), position));
+        StateTransition::Consume(State::Initial)
+    }
+    else
+    {
+        lexer.tokens.push(Token::new(single.clone(), &single.to_string(), position));
+        StateTransition::Keep(State::Initial)
+    }
+}
+
 fn handle_single_line_comment(byte: u8) -> StateTransition
 {
     if byte as char == '\n' {
@@ -521,9 +1116,13 @@ pub fn tokenize(file_path: &str, source_code: impl Read)
             State::Initial => handle_initial(current_byte, position.clone(), &mut lexer),
             State::Identifier => handle_identifier(current_byte, &mut lexer),
             State::Number => handle_number(current_byte, &mut lexer),
+            State::NumberDot => handle_number_dot(current_byte, position.clone(), &mut lexer),
+            State::Float => handle_float(current_byte, &mut lexer),
             State::String => handle_string(current_byte, &mut lexer),
             State::Char => handle_char(current_byte, &mut lexer),
-            State::Slash => handle_slash(current_byte),
+            State::StringEscape => handle_escape(current_byte, &mut lexer, State::String),
+            State::CharEscape => handle_escape(current_byte, &mut lexer, State::Char),
+            State::Slash => handle_slash(current_byte, &mut lexer),
             State::SingleLineComment => handle_single_line_comment(current_byte),
             State::MultiLineComment => handle_multi_line_comment(current_byte),
             State::MultiLineCommentStar => handle_multi_line_comment_star(current_byte),
@@ -531,9 +1130,17 @@ pub fn tokenize(file_path: &str, source_code: impl Read)
             State::DoubleToken(first, second, ref single, ref double) =>
             {
                 handle_double_token(current_byte,
-                    first, second, single, double,
-                    position.clone(), &mut lexer)
+                    first, second, single, double, &mut lexer)
+            },
+
+            State::ChoiceToken(first, ref single, choices) =>
+            {
+                handle_choice_token(current_byte,
+                    first, single, choices, &mut lexer)
             },
+
+            State::CompoundAssignToken(ref single, ref assign) =>
+                handle_compound_assign_token(current_byte, single, assign, &mut lexer),
         };
 
         state = match transition
@@ -0,0 +1,215 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Compile `source` as a file called `name`, giving the assembly, or
+/// the error the compiler reported.
+fn compile(name: &str, source: &str) -> Result<String, String>
+{
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join(name)
+        .with_extension("fun");
+    fs::write(&path, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_fun"))
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    if output.status.success() {
+        Ok(String::from_utf8(output.stdout).unwrap())
+    } else {
+        Err(String::from_utf8(output.stderr).unwrap())
+    }
+}
+
+/// Compile `source` alongside the given modules, as a file called `name`
+/// in a directory of its own.
+fn compile_with_modules(name: &str, modules: &[(&str, &str)], source: &str)
+    -> Result<String, String>
+{
+    let directory = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::create_dir_all(&directory).unwrap();
+    for (module_name, module_source) in modules
+    {
+        let path = directory.join(module_name).with_extension("fun");
+        fs::write(&path, module_source).unwrap();
+    }
+
+    compile(&format!("{}/main", name), source)
+}
+
+#[test]
+fn hash_set_of_int()
+{
+    let asm = compile("hash_set_of_int", "
+use std::hash_set
+
+extern printf
+
+export fun hash(self: ref int) -> int
+{
+    return deref self
+}
+
+fun main()
+{
+    let mut set = hash_set() of int
+    extern printf(\"\")
+
+    put(ref mut set, 3)
+    put(ref mut set, 28)
+    put(ref mut set, 3)
+
+    let item = 28
+    extern printf(\"%d %d\\n\", len(ref set), contains(ref set, ref item))
+}
+").unwrap();
+
+    assert!(asm.contains("call hash_refintint"));
+}
+
+#[test]
+fn return_value_from_void_function()
+{
+    let err = compile("return_value_from_void_function", "
+fun f()
+{
+    return 1
+}
+
+fun main()
+{
+    f()
+}
+").unwrap_err();
+
+    assert!(err.contains("Can not return a value from a function with no return type"));
+}
+
+#[test]
+fn call_function_in_field()
+{
+    let asm = compile("call_function_in_field", "
+extern printf
+
+struct Holder
+{
+    f: fun(int) -> int,
+}
+
+fun double(x: int) -> int
+{
+    return x * 2
+}
+
+fun main()
+{
+    let h = new Holder { f = fun double(int) }
+    extern printf(\"%d\\n\", h.f(4))
+}
+").unwrap();
+
+    assert!(asm.contains("call eax"));
+}
+
+#[test]
+fn not_of_int_literal()
+{
+    let err = compile("not_of_int_literal", "
+fun main()
+{
+    let a = !5
+}
+").unwrap_err();
+
+    assert!(err.contains(":4:13: Expected type 'bool', got 'int'"));
+}
+
+#[test]
+fn division_by_zero()
+{
+    let err = compile("division_by_zero", "
+fun main()
+{
+    let a = 5
+    let b = a / 0
+}
+").unwrap_err();
+
+    assert!(err.contains(":5:15: Division by zero"));
+}
+
+#[test]
+fn private_structs_with_the_same_name()
+{
+    let asm = compile_with_modules("private_structs_with_the_same_name", &[
+        ("first", "
+struct Point
+{
+    x: int,
+}
+
+export fun first() -> int
+{
+    let p = new Point { x = 1 }
+    return p.x
+}
+"),
+        ("second", "
+struct Point
+{
+    x: int,
+    y: int,
+}
+
+export fun second() -> int
+{
+    let p = new Point { x = 2, y = 3 }
+    return p.x + p.y
+}
+"),
+    ], "
+use first
+use second
+
+extern printf
+
+fun main()
+{
+    extern printf(\"%d %d\\n\", first(), second())
+}
+").unwrap();
+
+    assert!(asm.contains("call module1.first.first_int"));
+    assert!(asm.contains("call module2.second.second_int"));
+}
+
+#[test]
+fn ambiguous_struct()
+{
+    let err = compile_with_modules("ambiguous_struct", &[
+        ("first", "
+export struct Point
+{
+    x: int,
+}
+"),
+        ("second", "
+export struct Point
+{
+    x: int,
+}
+"),
+    ], "
+use first
+use second
+
+fun main()
+{
+    let p = new Point { x = 1 }
+}
+").unwrap_err();
+
+    assert!(err.contains("Type 'Point' is ambiguous, it could be from module 'first' or 'second'"));
+}